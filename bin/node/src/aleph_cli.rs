@@ -1,11 +1,89 @@
 use aleph_primitives::DEFAULT_UNIT_CREATION_DELAY;
 use clap::Parser;
-use finality_aleph::UnitCreationDelay;
+use finality_aleph::{
+    AddressPolicy, AggregationPolicy, AggregatorConfig, PeerReputationConfig, SentryRole,
+    SentryRoleError, UnitCreationDelay,
+};
+use ip_network::IpNetwork;
+use sc_network::Multiaddr;
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+const DEFAULT_PEER_BAN_THRESHOLD: i32 = 1000;
+const DEFAULT_PEER_BAN_DURATION_SECS: u64 = 600;
+const DEFAULT_PEER_SCORE_DECAY_PER_MINUTE: i32 = 50;
 
 #[derive(Debug, Parser, Clone)]
 pub struct AlephCli {
     #[clap(long)]
     unit_creation_delay: Option<u64>,
+
+    /// Only wait for the multisignatures of the newest finalized blocks, dropping older blocks
+    /// that are not signed yet. The last blocks of sessions are always signed.
+    #[clap(long)]
+    aggregator_skip_ahead: bool,
+
+    /// With --aggregator-skip-ahead, also always sign blocks with numbers divisible by this.
+    #[clap(long, requires = "aggregator-skip-ahead")]
+    aggregator_keep_every: Option<u32>,
+
+    /// Local misbehavior score after which a peer is banned from the Aleph protocols.
+    #[clap(long)]
+    peer_ban_threshold: Option<i32>,
+
+    /// For how many seconds a misbehaving peer stays banned.
+    #[clap(long)]
+    peer_ban_duration: Option<u64>,
+
+    /// How much the misbehavior score of a peer decreases every minute.
+    #[clap(long)]
+    peer_score_decay: Option<i32>,
+
+    /// Only publish own IP addresses from these networks to other validators, in CIDR notation.
+    /// All networks are allowed if none are given.
+    #[clap(long, multiple_occurrences = true)]
+    allow_addresses_from: Vec<IpNetwork>,
+
+    /// Never publish own IP addresses from these networks to other validators, in CIDR notation.
+    #[clap(long, multiple_occurrences = true)]
+    deny_addresses_from: Vec<IpNetwork>,
+
+    /// Publish these addresses to other validators instead of the ones reported by the network.
+    /// Defaults to the addresses given with --public-addr.
+    #[clap(long, multiple_occurrences = true)]
+    validator_public_addr: Vec<Multiaddr>,
+
+    /// Publish only DNS addresses to other validators.
+    #[clap(long)]
+    dns_addresses_only: bool,
+
+    /// Hide this validator behind the sentry with these addresses, which all have to contain the
+    /// same PeerId. Only a single sentry is supported, while it is down the validator is cut off
+    /// from the other validators. The node should not connect to anyone but the sentry, e.g. by
+    /// using --reserved-only.
+    #[clap(
+        long,
+        multiple_occurrences = true,
+        conflicts_with = "protected-validator"
+    )]
+    sentry_node: Vec<Multiaddr>,
+
+    /// Relay the validator traffic of the validator with these addresses, which all have to contain
+    /// the same PeerId. Has to be used together with --validator, but without session keys.
+    #[clap(long, multiple_occurrences = true)]
+    protected_validator: Vec<Multiaddr>,
+
+    /// Append every block finalized by Aleph, with its justification, as a line of JSON to this
+    /// file.
+    #[clap(long, parse(from_os_str))]
+    finality_log_file: Option<PathBuf>,
+
+    /// Send every block finalized by Aleph, with its justification, as a JSON datagram to the Unix
+    /// socket at this path. Blocks are dropped if nothing listens on the socket.
+    #[clap(long, parse(from_os_str))]
+    finality_socket: Option<PathBuf>,
 }
 
 impl AlephCli {
@@ -15,4 +93,62 @@ impl AlephCli {
                 .unwrap_or(DEFAULT_UNIT_CREATION_DELAY),
         )
     }
+
+    pub fn aggregator_config(&self) -> AggregatorConfig {
+        let policy = if self.aggregator_skip_ahead {
+            AggregationPolicy::SkipAhead
+        } else {
+            AggregationPolicy::All
+        };
+        AggregatorConfig {
+            policy,
+            keep_every: self.aggregator_keep_every.unwrap_or(0),
+        }
+    }
+
+    pub fn peer_reputation_config(&self) -> PeerReputationConfig {
+        PeerReputationConfig::new(
+            self.peer_ban_threshold
+                .unwrap_or(DEFAULT_PEER_BAN_THRESHOLD),
+            Duration::from_secs(
+                self.peer_ban_duration
+                    .unwrap_or(DEFAULT_PEER_BAN_DURATION_SECS),
+            ),
+            self.peer_score_decay
+                .unwrap_or(DEFAULT_PEER_SCORE_DECAY_PER_MINUTE),
+        )
+    }
+
+    /// The policy for the published addresses, where `network_public_addresses` are the ones given
+    /// to the network with --public-addr.
+    pub fn address_policy(&self, network_public_addresses: &[Multiaddr]) -> AddressPolicy {
+        let public_addresses = match self.validator_public_addr.is_empty() {
+            true => network_public_addresses.to_vec(),
+            false => self.validator_public_addr.clone(),
+        };
+        AddressPolicy::new(
+            self.allow_addresses_from.clone(),
+            self.deny_addresses_from.clone(),
+            public_addresses,
+            self.dns_addresses_only,
+        )
+    }
+
+    pub fn finality_log_file(&self) -> Option<&Path> {
+        self.finality_log_file.as_deref()
+    }
+
+    pub fn finality_socket(&self) -> Option<&Path> {
+        self.finality_socket.as_deref()
+    }
+
+    pub fn sentry_role(&self) -> Result<SentryRole, SentryRoleError> {
+        if !self.sentry_node.is_empty() {
+            return SentryRole::protected(self.sentry_node.clone());
+        }
+        if !self.protected_validator.is_empty() {
+            return SentryRole::sentry(self.protected_validator.clone());
+        }
+        Ok(SentryRole::None)
+    }
 }
@@ -0,0 +1,56 @@
+//! RPC methods exposing the internal state of the Aleph finality gadget.
+
+use finality_aleph::{DataStoreStatus, DataStoreStatusHandle, NetworkStatus, NetworkStatusHandle};
+use jsonrpc_core::Result;
+use jsonrpc_derive::rpc;
+use sc_rpc_api::DenyUnsafe;
+
+/// Aleph node RPC methods.
+#[rpc]
+pub trait AlephNodeApi {
+    /// Returns the connection manager's view of the validator network: for each active session
+    /// our own index, the known and missing authorities with their resolved PeerIds and addresses,
+    /// and the peers we have open validator protocol connections with.
+    #[rpc(name = "aleph_networkStatus")]
+    fn network_status(&self) -> Result<NetworkStatus>;
+
+    /// Returns the latest snapshot of the data store of the current session: the proposals it is
+    /// waiting for, with their status and how long they have been pending, and the number of
+    /// messages held back because of them.
+    #[rpc(name = "aleph_dataStoreStatus")]
+    fn data_store_status(&self) -> Result<DataStoreStatus>;
+}
+
+/// Implements the AlephNodeApi RPC trait.
+pub struct AlephNode {
+    network_status: NetworkStatusHandle,
+    data_store_status: DataStoreStatusHandle,
+    deny_unsafe: DenyUnsafe,
+}
+
+impl AlephNode {
+    /// Creates a new instance of the AlephNode RPC handler.
+    pub fn new(
+        network_status: NetworkStatusHandle,
+        data_store_status: DataStoreStatusHandle,
+        deny_unsafe: DenyUnsafe,
+    ) -> Self {
+        AlephNode {
+            network_status,
+            data_store_status,
+            deny_unsafe,
+        }
+    }
+}
+
+impl AlephNodeApi for AlephNode {
+    fn network_status(&self) -> Result<NetworkStatus> {
+        self.deny_unsafe.check_if_safe()?;
+        Ok(self.network_status.status())
+    }
+
+    fn data_store_status(&self) -> Result<DataStoreStatus> {
+        self.deny_unsafe.check_if_safe()?;
+        Ok(self.data_store_status.status())
+    }
+}
@@ -7,15 +7,17 @@ use aleph_runtime::{
     SessionKeys, StakingConfig, SudoConfig, SystemConfig, VestingConfig, WASM_BINARY,
 };
 use clap::Args;
+use finality_aleph::DataStoreConfig;
 use libp2p::PeerId;
 use pallet_staking::{Forcing, StakerStatus};
+use sc_chain_spec::ChainSpecExtension;
 use sc_service::{config::BasePath, ChainType};
 use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::{Number, Value};
 use sp_application_crypto::Ss58Codec;
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
 use sp_core::{sr25519, Pair};
-use std::{collections::HashSet, path::PathBuf, str::FromStr};
+use std::{collections::HashSet, path::PathBuf, str::FromStr, time::Duration};
 
 pub const CHAINTYPE_DEV: &str = "dev";
 pub const CHAINTYPE_LOCAL: &str = "local";
@@ -27,7 +29,66 @@ pub const DEFAULT_CHAIN_ID: &str = "a0dnet1";
 pub const DEFAULT_SUDO_ACCOUNT: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
 
 /// Specialized `ChainSpec`. This is a specialization of the general Substrate ChainSpec type.
-pub type ChainSpec = sc_service::GenericChainSpec<GenesisConfig>;
+pub type ChainSpec = sc_service::GenericChainSpec<GenesisConfig, Extensions>;
+
+/// Node-side settings specific to the chain. They only affect the local node, so they do not have
+/// to be the same on all the nodes.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, ChainSpecExtension)]
+#[serde(rename_all = "camelCase")]
+pub struct Extensions {
+    #[serde(default)]
+    pub aleph_data_store: Option<DataStoreSpec>,
+}
+
+/// Overrides of the default `DataStoreConfig`, which are used for all the missing values.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DataStoreSpec {
+    pub max_triggers_pending: Option<usize>,
+    pub max_proposals_pending: Option<usize>,
+    pub max_messages_pending: Option<usize>,
+    pub available_proposals_cache_capacity: Option<usize>,
+    pub periodic_maintenance_interval_ms: Option<u64>,
+    pub request_block_after_ms: Option<u64>,
+    pub block_cache_capacity: Option<usize>,
+}
+
+impl DataStoreSpec {
+    pub fn data_store_config(&self) -> DataStoreConfig {
+        let mut config = DataStoreConfig::default();
+        if let Some(max_triggers_pending) = self.max_triggers_pending {
+            config.max_triggers_pending = max_triggers_pending;
+        }
+        if let Some(max_proposals_pending) = self.max_proposals_pending {
+            config.max_proposals_pending = max_proposals_pending;
+        }
+        if let Some(max_messages_pending) = self.max_messages_pending {
+            config.max_messages_pending = max_messages_pending;
+        }
+        if let Some(capacity) = self.available_proposals_cache_capacity {
+            config.available_proposals_cache_capacity = capacity;
+        }
+        if let Some(interval) = self.periodic_maintenance_interval_ms {
+            config.periodic_maintenance_interval = Duration::from_millis(interval);
+        }
+        if let Some(after) = self.request_block_after_ms {
+            config.request_block_after = Duration::from_millis(after);
+        }
+        if let Some(capacity) = self.block_cache_capacity {
+            config.chain_info_cache.block_cache_capacity = capacity;
+        }
+        config
+    }
+}
+
+/// Returns the `DataStoreConfig` for the chain, using the defaults if the chain spec does not
+/// override them.
+pub fn data_store_config(chain_spec: &dyn sc_service::ChainSpec) -> DataStoreConfig {
+    sc_chain_spec::get_extension::<Extensions>(chain_spec.extensions())
+        .and_then(|extensions| extensions.aleph_data_store.clone())
+        .unwrap_or_default()
+        .data_store_config()
+}
 
 #[derive(Clone)]
 pub struct SerializablePeerId {
@@ -247,7 +308,7 @@ fn generate_chain_spec_config(
         // Properties
         Some(system_properties(token_symbol)),
         // Extensions
-        None,
+        Default::default(),
     ))
 }
 
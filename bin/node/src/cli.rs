@@ -98,4 +98,7 @@ pub enum Subcommand {
 
     /// Revert the chain to a previous state.
     Revert(sc_cli::RevertCmd),
+
+    /// Benchmark the runtime pallets, the node has to be built with `--features runtime-benchmarks`.
+    Benchmark(frame_benchmarking_cli::BenchmarkCmd),
 }
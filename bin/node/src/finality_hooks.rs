@@ -0,0 +1,118 @@
+//! Finality hooks notifying services running next to the node about blocks finalized by Aleph.
+
+use aleph_runtime::opaque::Block;
+use codec::Encode;
+use finality_aleph::{AlephJustification, FinalityHook};
+use log::{debug, warn};
+use serde::Serialize;
+use sp_runtime::traits::{Block as BlockT, NumberFor};
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    os::unix::net::UnixDatagram,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+/// A finalized block as reported to the hooks' consumers, serialized as a single line of JSON.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FinalizedBlock {
+    hash: String,
+    number: NumberFor<Block>,
+    justification: String,
+}
+
+fn finalized_block_line(
+    hash: <Block as BlockT>::Hash,
+    number: NumberFor<Block>,
+    justification: &AlephJustification,
+) -> Vec<u8> {
+    let block = FinalizedBlock {
+        hash: format!("{:?}", hash),
+        number,
+        justification: format!("0x{}", hex::encode(justification.encode())),
+    };
+    let mut line = serde_json::to_vec(&block).expect("serializing a finalized block works");
+    line.push(b'\n');
+    line
+}
+
+/// Appends every finalized block to a file.
+pub struct FileHook {
+    file: Mutex<File>,
+}
+
+impl FileHook {
+    pub fn new(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(FileHook {
+            file: Mutex::new(file),
+        })
+    }
+}
+
+impl FinalityHook<Block> for FileHook {
+    fn on_block_finalized(
+        &self,
+        hash: <Block as BlockT>::Hash,
+        block_number: NumberFor<Block>,
+        justification: &AlephJustification,
+    ) {
+        let line = finalized_block_line(hash, block_number, justification);
+        let mut file = self
+            .file
+            .lock()
+            .expect("the finality log file lock is not poisoned");
+        if let Err(e) = file.write_all(&line) {
+            warn!(target: "aleph-finality", "Failed to write finalized block {:?} to the finality log: {:?}", hash, e);
+        }
+    }
+}
+
+/// Sends every finalized block as a datagram to a Unix socket. The socket is never waited on, so
+/// blocks are dropped if nothing listens on it or the listener cannot keep up.
+pub struct UnixSocketHook {
+    socket: UnixDatagram,
+    path: PathBuf,
+}
+
+impl UnixSocketHook {
+    pub fn new(path: &Path) -> io::Result<Self> {
+        let socket = UnixDatagram::unbound()?;
+        socket.set_nonblocking(true)?;
+        Ok(UnixSocketHook {
+            socket,
+            path: path.to_path_buf(),
+        })
+    }
+}
+
+impl FinalityHook<Block> for UnixSocketHook {
+    fn on_block_finalized(
+        &self,
+        hash: <Block as BlockT>::Hash,
+        block_number: NumberFor<Block>,
+        justification: &AlephJustification,
+    ) {
+        let line = finalized_block_line(hash, block_number, justification);
+        if let Err(e) = self.socket.send_to(&line, &self.path) {
+            debug!(target: "aleph-finality", "Failed to send finalized block {:?} to {:?}: {:?}", hash, self.path, e);
+        }
+    }
+}
+
+/// Creates the hooks enabled in the configuration.
+pub fn finality_hooks(
+    log_file: Option<&Path>,
+    socket: Option<&Path>,
+) -> io::Result<Vec<Arc<dyn FinalityHook<Block>>>> {
+    let mut hooks: Vec<Arc<dyn FinalityHook<Block>>> = Vec::new();
+    if let Some(path) = log_file {
+        hooks.push(Arc::new(FileHook::new(path)?));
+    }
+    if let Some(path) = socket {
+        hooks.push(Arc::new(UnixSocketHook::new(path)?));
+    }
+    Ok(hooks)
+}
@@ -0,0 +1,199 @@
+//! Tracking which committee members contributed to finalization, reported to the runtime in an
+//! inherent so that they are rewarded for it.
+//!
+//! AlephBFT does not expose the creators of the ordered units, so the participation is measured by
+//! the signatures the members contributed to the justifications of the session. For every member
+//! one of its signatures is reported, so that the runtime can check it.
+
+use aleph_primitives::{
+    first_block_of_session, session_of_block, AlephSessionApi, AuthoritySignature,
+    FinalityParticipation, ParticipationProof, SessionIndex, SessionPeriodChange,
+    FINALITY_PARTICIPATION_INHERENT_IDENTIFIER,
+};
+use aleph_runtime::opaque::Block;
+use finality_aleph::{
+    constant_schedule, supports_session_schedule, AlephJustification, FinalityHook, SessionPeriod,
+};
+use log::{debug, warn};
+use sp_api::ProvideRuntimeApi;
+use sp_inherents::{InherentData, InherentIdentifier};
+use sp_runtime::{
+    generic::BlockId,
+    traits::{Block as BlockT, NumberFor},
+};
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+struct TrackedSession {
+    session: SessionIndex,
+    // Whether all the justifications of the session so far were seen.
+    complete: bool,
+    // The latest signature of every member, recent blocks are the most likely to still have their
+    // hashes kept by the runtime.
+    participants: BTreeMap<u32, ParticipationProof>,
+}
+
+impl TrackedSession {
+    fn new(session: SessionIndex, complete: bool) -> Self {
+        TrackedSession {
+            session,
+            complete,
+            participants: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    schedule: Vec<SessionPeriodChange>,
+    tracked: Option<TrackedSession>,
+    latest: Option<FinalityParticipation>,
+}
+
+/// A finality hook collecting the signers of the justifications of every session. Once the last
+/// block of a session is finalized, its participation is ready to be reported, unless the node
+/// started tracking in the middle of the session.
+pub struct ParticipationTracker<C> {
+    client: Arc<C>,
+    state: Mutex<State>,
+}
+
+impl<C> ParticipationTracker<C>
+where
+    C: ProvideRuntimeApi<Block> + Send + Sync,
+    C::Api: AlephSessionApi<Block>,
+{
+    pub fn new(client: Arc<C>) -> Self {
+        ParticipationTracker {
+            client,
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// The participation in the latest session whose justifications were all seen by this node.
+    pub fn latest(&self) -> Option<FinalityParticipation> {
+        self.state
+            .lock()
+            .expect("the participation lock is not poisoned")
+            .latest
+            .clone()
+    }
+
+    /// Creates the provider of the inherent reporting the latest participation.
+    pub fn inherent_data_provider(&self) -> InherentDataProvider {
+        InherentDataProvider(self.latest())
+    }
+
+    fn schedule_at(&self, hash: <Block as BlockT>::Hash) -> Option<Vec<SessionPeriodChange>> {
+        let at = BlockId::Hash(hash);
+        // Older runtimes only know a constant session period.
+        if !supports_session_schedule(self.client.as_ref(), &at) {
+            return match self.client.runtime_api().session_period(&at) {
+                Ok(period) => Some(constant_schedule(SessionPeriod(period))),
+                Err(e) => {
+                    warn!(target: "aleph-participation", "Failed to read the session period at {:?}: {:?}", hash, e);
+                    None
+                }
+            };
+        }
+        match self.client.runtime_api().session_period_schedule(&at) {
+            Ok(schedule) => Some(schedule),
+            Err(e) => {
+                warn!(target: "aleph-participation", "Failed to read the session schedule at {:?}: {:?}", hash, e);
+                None
+            }
+        }
+    }
+}
+
+impl<C> FinalityHook<Block> for ParticipationTracker<C>
+where
+    C: ProvideRuntimeApi<Block> + Send + Sync,
+    C::Api: AlephSessionApi<Block>,
+{
+    fn on_block_finalized(
+        &self,
+        hash: <Block as BlockT>::Hash,
+        block_number: NumberFor<Block>,
+        justification: &AlephJustification,
+    ) {
+        let mut state = self
+            .state
+            .lock()
+            .expect("the participation lock is not poisoned");
+
+        // The schedule only changes at session boundaries, so it is refreshed once per session.
+        let mut session = session_of_block(&state.schedule, block_number);
+        if state.tracked.as_ref().map(|tracked| tracked.session) != Some(session) {
+            if let Some(schedule) = self.schedule_at(hash) {
+                state.schedule = schedule;
+            }
+            session = session_of_block(&state.schedule, block_number);
+        }
+        if state.schedule.is_empty() {
+            return;
+        }
+
+        let mut tracked = match state.tracked.take() {
+            Some(tracked) if tracked.session == session => tracked,
+            _ => TrackedSession::new(session, false),
+        };
+        tracked
+            .participants
+            .extend(justification.signature.iter().map(|(index, signature)| {
+                let member = index.0 as u32;
+                let proof = ParticipationProof {
+                    member,
+                    block: block_number,
+                    signature: AuthoritySignature::from(signature.clone()),
+                };
+                (member, proof)
+            }));
+
+        if block_number + 1 < first_block_of_session(&state.schedule, session + 1) {
+            state.tracked = Some(tracked);
+            return;
+        }
+
+        if tracked.complete {
+            debug!(target: "aleph-participation", "Session {:?} finalized with participants {:?}.", session, tracked.participants.keys());
+            state.latest = Some(FinalityParticipation {
+                session,
+                participants: tracked.participants.into_values().collect(),
+            });
+        }
+        state.tracked = Some(TrackedSession::new(session + 1, true));
+    }
+}
+
+/// Provides the latest finality participation, if any, for the block being authored.
+pub struct InherentDataProvider(Option<FinalityParticipation>);
+
+#[async_trait::async_trait]
+impl sp_inherents::InherentDataProvider for InherentDataProvider {
+    fn provide_inherent_data(
+        &self,
+        inherent_data: &mut InherentData,
+    ) -> Result<(), sp_inherents::Error> {
+        match &self.0 {
+            Some(participation) => {
+                inherent_data.put_data(FINALITY_PARTICIPATION_INHERENT_IDENTIFIER, participation)
+            }
+            None => Ok(()),
+        }
+    }
+
+    async fn try_handle_error(
+        &self,
+        identifier: &InherentIdentifier,
+        _error: &[u8],
+    ) -> Option<Result<(), sp_inherents::Error>> {
+        if *identifier != FINALITY_PARTICIPATION_INHERENT_IDENTIFIER {
+            return None;
+        }
+        warn!(target: "aleph-participation", "The finality participation inherent was rejected.");
+        Some(Ok(()))
+    }
+}
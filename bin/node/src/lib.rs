@@ -1,11 +1,15 @@
 mod aleph_cli;
+mod aleph_rpc;
 mod chain_spec;
 mod cli;
 mod commands;
 mod executor;
+mod finality_hooks;
+mod finality_participation;
 mod resources;
 mod rpc;
 mod service;
 
 pub use cli::{Cli, Subcommand};
+pub use executor::ExecutorDispatch;
 pub use service::{new_authority, new_full, new_partial};
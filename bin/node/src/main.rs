@@ -2,7 +2,8 @@ use sc_cli::SubstrateCli;
 use sc_network::config::Role;
 use sc_service::PartialComponents;
 
-use aleph_node::{new_authority, new_full, new_partial, Cli, Subcommand};
+use aleph_node::{new_authority, new_full, new_partial, Cli, ExecutorDispatch, Subcommand};
+use aleph_runtime::opaque::Block;
 use clap::Parser;
 
 fn main() -> sc_cli::Result<()> {
@@ -74,6 +75,16 @@ fn main() -> sc_cli::Result<()> {
                 Ok((cmd.run(client, backend, None), task_manager))
             })
         }
+        Some(Subcommand::Benchmark(cmd)) => {
+            if cfg!(feature = "runtime-benchmarks") {
+                let runner = cli.create_runner(cmd)?;
+                runner.sync_run(|config| cmd.run::<Block, ExecutorDispatch>(config))
+            } else {
+                Err("Benchmarking was not enabled when building the node. \
+                    You can enable it with `--features runtime-benchmarks`."
+                    .into())
+            }
+        }
         None => {
             let runner = cli.create_runner(&cli.run)?;
             let aleph_cli_config = cli.aleph;
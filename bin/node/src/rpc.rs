@@ -8,6 +8,7 @@
 use std::sync::Arc;
 
 use aleph_runtime::{opaque::Block, AccountId, Balance, BlockNumber, Hash, Index};
+use finality_aleph::{DataStoreStatusHandle, NetworkStatusHandle};
 pub use sc_rpc_api::DenyUnsafe;
 use sc_transaction_pool_api::TransactionPool;
 use sp_api::ProvideRuntimeApi;
@@ -22,6 +23,10 @@ pub struct FullDeps<C, P> {
     pub pool: Arc<P>,
     /// Whether to deny unsafe calls
     pub deny_unsafe: DenyUnsafe,
+    /// Status of the Aleph validator network.
+    pub network_status: NetworkStatusHandle,
+    /// Status of the Aleph data store.
+    pub data_store_status: DataStoreStatusHandle,
 }
 
 /// Instantiate all full RPC extensions.
@@ -36,6 +41,7 @@ where
     C::Api: BlockBuilder<Block>,
     P: TransactionPool + 'static,
 {
+    use crate::aleph_rpc::{AlephNode, AlephNodeApi};
     use pallet_contracts_rpc::{Contracts, ContractsApi};
     use pallet_transaction_payment_rpc::{TransactionPayment, TransactionPaymentApi};
     use substrate_frame_rpc_system::{FullSystem, SystemApi};
@@ -45,6 +51,8 @@ where
         client,
         pool,
         deny_unsafe,
+        network_status,
+        data_store_status,
     } = deps;
 
     io.extend_with(SystemApi::to_delegate(FullSystem::new(
@@ -59,5 +67,11 @@ where
 
     io.extend_with(ContractsApi::to_delegate(Contracts::new(client)));
 
+    io.extend_with(AlephNodeApi::to_delegate(AlephNode::new(
+        network_status,
+        data_store_status,
+        deny_unsafe,
+    )));
+
     io
 }
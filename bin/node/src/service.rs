@@ -1,22 +1,27 @@
 //! Service and ServiceFactory implementation. Specialized wrapper over substrate service.
 
-use crate::{aleph_cli::AlephCli, executor::AlephExecutor};
+use crate::{
+    aleph_cli::AlephCli, chain_spec::data_store_config, executor::AlephExecutor,
+    finality_hooks::finality_hooks, finality_participation::ParticipationTracker,
+};
 use aleph_primitives::AlephSessionApi;
 use aleph_runtime::{self, opaque::Block, RuntimeApi, MAX_BLOCK_SIZE};
 use finality_aleph::{
-    run_nonvalidator_node, run_validator_node, AlephBlockImport, AlephConfig,
-    JustificationNotification, Metrics, MillisecsPerBlock, Protocol, SessionPeriod,
+    run_nonvalidator_node, run_validator_node, AlephBlockImport, AlephConfig, AlephSelectChain,
+    DataStoreStatusHandle, JustificationNotification, Metrics, MillisecsPerBlock,
+    NetworkStatusHandle, Protocol, SessionPeriod,
 };
 use futures::channel::mpsc;
 use log::warn;
+use prometheus_endpoint::Registry;
 use sc_client_api::ExecutorProvider;
 use sc_consensus_aura::{ImportQueueParams, SlotProportion, StartAuraParams};
 use sc_network::NetworkService;
 use sc_service::{
     error::Error as ServiceError, Configuration, KeystoreContainer, NetworkStarter, RpcHandlers,
-    TFullClient, TaskManager,
+    SpawnEssentialTaskHandle, TFullClient, TaskManager,
 };
-use sc_telemetry::{Telemetry, TelemetryWorker};
+use sc_telemetry::{Telemetry, TelemetryHandle, TelemetryWorker};
 use sp_api::ProvideRuntimeApi;
 use sp_consensus_aura::sr25519::AuthorityPair as AuraPair;
 use sp_runtime::{
@@ -29,6 +34,46 @@ type FullClient = sc_service::TFullClient<Block, RuntimeApi, AlephExecutor>;
 type FullBackend = sc_service::TFullBackend<Block>;
 type FullSelectChain = sc_consensus::LongestChain<FullBackend, Block>;
 
+// Builds an Aura import queue feeding blocks to the given block import. Besides the queue used by
+// the Substrate network, validators use another one for blocks fetched from the committee.
+fn aura_import_queue(
+    client: Arc<FullClient>,
+    block_import: AlephBlockImport<Block, FullBackend, FullClient>,
+    spawner: &SpawnEssentialTaskHandle,
+    registry: Option<&Registry>,
+    telemetry: Option<TelemetryHandle>,
+) -> Result<sc_consensus::DefaultImportQueue<Block, FullClient>, ServiceError> {
+    let slot_duration = sc_consensus_aura::slot_duration(&*client)?;
+
+    let import_queue = sc_consensus_aura::import_queue::<AuraPair, _, _, _, _, _, _>(
+        ImportQueueParams {
+            block_import: block_import.clone(),
+            justification_import: Some(Box::new(block_import)),
+            client: client.clone(),
+            create_inherent_data_providers: move |_, ()| async move {
+                let timestamp = sp_timestamp::InherentDataProvider::from_system_time();
+
+                let slot =
+                    sp_consensus_aura::inherents::InherentDataProvider::from_timestamp_and_slot_duration(
+                        *timestamp,
+                        slot_duration,
+                    );
+
+                Ok((timestamp, slot))
+            },
+            spawner,
+            registry,
+            can_author_with: sp_consensus::CanAuthorWithNativeVersion::new(
+                client.executor().clone(),
+            ),
+            check_for_equivocation: Default::default(),
+            telemetry,
+        },
+    )?;
+
+    Ok(import_queue)
+}
+
 #[allow(clippy::type_complexity)]
 pub fn new_partial(
     config: &Configuration,
@@ -104,32 +149,12 @@ pub fn new_partial(
     let aleph_block_import =
         AlephBlockImport::new(client.clone() as Arc<_>, justification_tx, metrics.clone());
 
-    let slot_duration = sc_consensus_aura::slot_duration(&*client)?;
-
-    let import_queue = sc_consensus_aura::import_queue::<AuraPair, _, _, _, _, _, _>(
-        ImportQueueParams {
-            block_import: aleph_block_import.clone(),
-            justification_import: Some(Box::new(aleph_block_import.clone())),
-            client: client.clone(),
-            create_inherent_data_providers: move |_, ()| async move {
-                let timestamp = sp_timestamp::InherentDataProvider::from_system_time();
-
-                let slot =
-                    sp_consensus_aura::inherents::InherentDataProvider::from_timestamp_and_slot_duration(
-                        *timestamp,
-                        slot_duration,
-                    );
-
-                Ok((timestamp, slot))
-            },
-            spawner: &task_manager.spawn_essential_handle(),
-            registry: config.prometheus_registry(),
-            can_author_with: sp_consensus::CanAuthorWithNativeVersion::new(
-                client.executor().clone(),
-            ),
-            check_for_equivocation: Default::default(),
-            telemetry: telemetry.as_ref().map(|x| x.handle()),
-        },
+    let import_queue = aura_import_queue(
+        client.clone(),
+        aleph_block_import.clone(),
+        &task_manager.spawn_essential_handle(),
+        config.prometheus_registry(),
+        telemetry.as_ref().map(|x| x.handle()),
     )?;
 
     Ok(sc_service::PartialComponents {
@@ -155,6 +180,8 @@ fn setup(
     task_manager: &mut TaskManager,
     client: Arc<FullClient>,
     telemetry: &mut Option<Telemetry>,
+    network_status: NetworkStatusHandle,
+    data_store_status: DataStoreStatusHandle,
 ) -> Result<
     (
         RpcHandlers,
@@ -188,6 +215,8 @@ fn setup(
                 client: client.clone(),
                 pool: pool.clone(),
                 deny_unsafe,
+                network_status: network_status.clone(),
+                data_store_status: data_store_status.clone(),
             };
 
             Ok(crate::rpc::create_full(deps))
@@ -245,10 +274,27 @@ pub fn new_authority(
     );
 
     let unit_creation_delay = aleph_config.unit_creation_delay();
+    let aggregator_config = aleph_config.aggregator_config();
+    let mut finality_hooks = finality_hooks(
+        aleph_config.finality_log_file(),
+        aleph_config.finality_socket(),
+    )
+    .map_err(|e| ServiceError::Other(format!("Failed to set up finality hooks: {:?}", e)))?;
+    // Reports the finality participation of the committee in the blocks we author.
+    let participation_tracker = Arc::new(ParticipationTracker::new(client.clone()));
+    finality_hooks.push(participation_tracker.clone());
+    let data_store_config = data_store_config(&*config.chain_spec);
+    let peer_reputation = aleph_config.peer_reputation_config();
+    let address_policy = aleph_config.address_policy(&config.network.public_addresses);
+    let sentry_role = aleph_config
+        .sentry_role()
+        .map_err(|e| ServiceError::Other(format!("Invalid sentry configuration: {:?}", e)))?;
 
     let force_authoring = config.force_authoring;
     let backoff_authoring_blocks: Option<()> = None;
     let prometheus_registry = config.prometheus_registry().cloned();
+    let network_status = NetworkStatusHandle::default();
+    let data_store_status = DataStoreStatusHandle::default();
 
     let (_rpc_handlers, network, network_starter) = setup(
         config,
@@ -259,8 +305,20 @@ pub fn new_authority(
         &mut task_manager,
         client.clone(),
         &mut telemetry,
+        network_status.clone(),
+        data_store_status.clone(),
     )?;
 
+    // Blocks fetched by the committee are imported through a separate queue, its metrics are not
+    // registered to avoid clashing with the main one.
+    let block_import_queue = Box::new(aura_import_queue(
+        client.clone(),
+        block_import.clone(),
+        &task_manager.spawn_essential_handle(),
+        None,
+        telemetry.as_ref().map(|x| x.handle()),
+    )?);
+
     let mut proposer_factory = sc_basic_authorship::ProposerFactory::new(
         task_manager.spawn_handle(),
         client.clone(),
@@ -274,23 +332,30 @@ pub fn new_authority(
 
     let slot_duration = sc_consensus_aura::slot_duration(&*client)?;
 
+    // Aura never builds on forks conflicting with the blocks already ordered by Aleph.
+    let aura_select_chain = AlephSelectChain::new(select_chain.clone(), client.clone());
+    let ordered_head = aura_select_chain.ordered_head();
+
     let aura = sc_consensus_aura::start_aura::<AuraPair, _, _, _, _, _, _, _, _, _, _, _>(
         StartAuraParams {
             slot_duration,
             client: client.clone(),
-            select_chain: select_chain.clone(),
+            select_chain: aura_select_chain,
             block_import,
             proposer_factory,
-            create_inherent_data_providers: move |_, ()| async move {
-                let timestamp = sp_timestamp::InherentDataProvider::from_system_time();
-
-                let slot =
-                    sp_consensus_aura::inherents::InherentDataProvider::from_timestamp_and_slot_duration(
-                        *timestamp,
-                        slot_duration,
-                    );
-
-                Ok((timestamp, slot))
+            create_inherent_data_providers: move |_, ()| {
+                let participation = participation_tracker.inherent_data_provider();
+                async move {
+                    let timestamp = sp_timestamp::InherentDataProvider::from_system_time();
+
+                    let slot =
+                        sp_consensus_aura::inherents::InherentDataProvider::from_timestamp_and_slot_duration(
+                            *timestamp,
+                            slot_duration,
+                        );
+
+                    Ok((timestamp, slot, participation))
+                }
             },
             force_authoring,
             backoff_authoring_blocks,
@@ -319,6 +384,17 @@ pub fn new_authority(
         justification_rx,
         metrics,
         unit_creation_delay,
+        aggregator_config,
+        data_store_config,
+        peer_reputation,
+        address_policy,
+        sentry_role,
+        registry: prometheus_registry,
+        network_status,
+        data_store_status,
+        ordered_head,
+        block_import_queue,
+        finality_hooks,
     };
     task_manager.spawn_essential_handle().spawn_blocking(
         "aleph",
@@ -342,8 +418,13 @@ pub fn new_full(
         keystore_container,
         select_chain,
         transaction_pool,
-        other: (_, justification_rx, mut telemetry, metrics),
+        other: (block_import, justification_rx, mut telemetry, metrics),
     } = new_partial(&config)?;
+    let prometheus_registry = config.prometheus_registry().cloned();
+    let network_status = NetworkStatusHandle::default();
+    let data_store_status = DataStoreStatusHandle::default();
+    let data_store_config = data_store_config(&*config.chain_spec);
+    let address_policy = aleph_config.address_policy(&config.network.public_addresses);
 
     let (_rpc_handlers, network, network_starter) = setup(
         config,
@@ -354,8 +435,18 @@ pub fn new_full(
         &mut task_manager,
         client.clone(),
         &mut telemetry,
+        network_status.clone(),
+        data_store_status.clone(),
     )?;
 
+    let block_import_queue = Box::new(aura_import_queue(
+        client.clone(),
+        block_import,
+        &task_manager.spawn_essential_handle(),
+        None,
+        telemetry.as_ref().map(|x| x.handle()),
+    )?);
+
     let session_period = SessionPeriod(
         client
             .runtime_api()
@@ -371,6 +462,16 @@ pub fn new_full(
     );
 
     let unit_creation_delay = aleph_config.unit_creation_delay();
+    let aggregator_config = aleph_config.aggregator_config();
+    let finality_hooks = finality_hooks(
+        aleph_config.finality_log_file(),
+        aleph_config.finality_socket(),
+    )
+    .map_err(|e| ServiceError::Other(format!("Failed to set up finality hooks: {:?}", e)))?;
+    let peer_reputation = aleph_config.peer_reputation_config();
+    let sentry_role = aleph_config
+        .sentry_role()
+        .map_err(|e| ServiceError::Other(format!("Invalid sentry configuration: {:?}", e)))?;
 
     let aleph_config = AlephConfig {
         network,
@@ -383,6 +484,17 @@ pub fn new_full(
         justification_rx,
         metrics,
         unit_creation_delay,
+        aggregator_config,
+        data_store_config,
+        peer_reputation,
+        address_policy,
+        sentry_role,
+        registry: prometheus_registry,
+        network_status,
+        data_store_status,
+        ordered_head: Default::default(),
+        block_import_queue,
+        finality_hooks,
     };
 
     task_manager.spawn_essential_handle().spawn_blocking(
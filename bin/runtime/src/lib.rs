@@ -48,7 +48,8 @@ use frame_system::{EnsureRoot, EnsureSignedBy};
 pub use primitives::Balance;
 use primitives::{
     staking::MAX_NOMINATORS_REWARDED_PER_VALIDATOR, wrap_methods, ApiError as AlephApiError,
-    AuthorityId as AlephId, DEFAULT_MILLISECS_PER_BLOCK, DEFAULT_SESSIONS_PER_ERA,
+    AuthorityId as AlephId, EraValidators, SessionIndex, SessionPeriodChange,
+    Version as FinalityVersion, DEFAULT_MILLISECS_PER_BLOCK, DEFAULT_SESSIONS_PER_ERA,
     DEFAULT_SESSION_PERIOD, TOKEN,
 };
 
@@ -113,7 +114,7 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
     spec_name: create_runtime_str!("aleph-node"),
     impl_name: create_runtime_str!("aleph-node"),
     authoring_version: 1,
-    spec_version: 14,
+    spec_version: 15,
     impl_version: 1,
     apis: RUNTIME_API_VERSIONS,
     transaction_version: 6,
@@ -323,6 +324,11 @@ impl pallet_sudo::Config for Runtime {
 
 impl pallet_aleph::Config for Runtime {
     type AuthorityId = AlephId;
+    type SessionPeriod = SessionPeriod;
+    type SessionAuthoritiesHistoryDepth = SessionAuthoritiesHistoryDepth;
+    type MaxSessionPeriodChanges = MaxSessionPeriodChanges;
+    type Event = Event;
+    type WeightInfo = pallet_aleph::weights::SubstrateWeight<Runtime>;
 }
 
 impl_opaque_keys! {
@@ -334,6 +340,12 @@ impl_opaque_keys! {
 
 parameter_types! {
     pub const SessionPeriod: u32 = DEFAULT_SESSION_PERIOD;
+    // Keep the authorities for as long as the validators stay bonded.
+    pub const SessionAuthoritiesHistoryDepth: SessionIndex = DEFAULT_SESSIONS_PER_ERA * 14;
+    pub const MaxSessionPeriodChanges: u32 = 32;
+    pub const PerformanceHistoryDepth: EraIndex = 14;
+    pub const FinalityRewardRatio: Perbill = Perbill::from_percent(20);
+    pub const MaxMembers: u32 = 1000;
 }
 
 impl pallet_elections::Config for Runtime {
@@ -341,23 +353,26 @@ impl pallet_elections::Config for Runtime {
     type Event = Event;
     type DataProvider = Staking;
     type SessionInfoProvider = Session;
-    type SessionPeriod = SessionPeriod;
+    type SessionPeriodProvider = Aleph;
     type SessionManager = pallet_session::historical::NoteHistoricalRoot<Runtime, Staking>;
     type ValidatorRewardsHandler = Staking;
+    type ValidatorBondProvider = Staking;
+    type Randomness = RandomnessCollectiveFlip;
+    type PerformanceHistoryDepth = PerformanceHistoryDepth;
+    type FinalityRewardRatio = FinalityRewardRatio;
+    type FinalityParticipationVerifier = Aleph;
+    type MaxMembers = MaxMembers;
+    type WeightInfo = pallet_elections::weights::SubstrateWeight<Runtime>;
 }
 
 impl pallet_randomness_collective_flip::Config for Runtime {}
 
-parameter_types! {
-    pub const Offset: u32 = 0;
-}
-
 impl pallet_session::Config for Runtime {
     type Event = Event;
     type ValidatorId = <Self as frame_system::Config>::AccountId;
     type ValidatorIdOf = pallet_staking::StashOf<Self>;
-    type ShouldEndSession = pallet_session::PeriodicSessions<SessionPeriod, Offset>;
-    type NextSessionRotation = pallet_session::PeriodicSessions<SessionPeriod, Offset>;
+    type ShouldEndSession = Aleph;
+    type NextSessionRotation = Aleph;
     type SessionManager = Elections;
     type SessionHandler = <SessionKeys as OpaqueKeys>::KeyTypeIdProviders;
     type Keys = SessionKeys;
@@ -671,8 +686,8 @@ construct_runtime!(
         Staking: pallet_staking::{Pallet, Call, Storage, Config<T>, Event<T>} = 8,
         History: pallet_session::historical::{Pallet} = 9,
         Session: pallet_session::{Pallet, Call, Storage, Event, Config<T>} = 10,
-        Aleph: pallet_aleph::{Pallet, Storage} = 11,
-        Elections: pallet_elections::{Pallet, Call, Storage, Config<T>, Event<T>} = 12,
+        Aleph: pallet_aleph::{Pallet, Call, Storage, Event<T>} = 11,
+        Elections: pallet_elections::{Pallet, Call, Storage, Config<T>, Event<T>, Inherent} = 12,
         Treasury: pallet_treasury::{Pallet, Call, Storage, Config, Event<T>} = 13,
         Vesting: pallet_vesting::{Pallet, Call, Storage, Event<T>, Config<T>} = 14,
         Utility: pallet_utility::{Pallet, Call, Storage, Event} = 15,
@@ -821,15 +836,38 @@ impl_runtime_apis! {
             Aleph::authorities()
         }
 
+        fn authorities_for_session(session: SessionIndex) -> Option<Vec<AlephId>> {
+            Aleph::session_authorities(session)
+        }
+
         fn millisecs_per_block() -> u64 {
             MILLISECS_PER_BLOCK
         }
 
         fn session_period() -> u32 {
-            SessionPeriod::get()
+            Aleph::session_period(Session::current_index())
+        }
+
+        fn session_period_schedule() -> Vec<SessionPeriodChange> {
+            Aleph::session_period_schedule()
+        }
+
+        fn max_data_branch_len() -> u32 {
+            Aleph::max_data_branch_len()
+        }
+
+        fn finality_version() -> FinalityVersion {
+            Aleph::finality_version()
+        }
+
+        fn finality_version_for_session(session: SessionIndex) -> FinalityVersion {
+            Aleph::finality_version_for_session(session)
         }
 
         fn next_session_authorities() -> Result<Vec<AlephId>, AlephApiError> {
+            if let Some(authorities) = Aleph::next_session_authorities_override() {
+                return Ok(authorities);
+            }
             Session::queued_keys()
                 .iter()
                 .map(|(_, key)| key.get(AlephId::ID).ok_or(AlephApiError::DecodeKey))
@@ -837,6 +875,24 @@ impl_runtime_apis! {
         }
     }
 
+    impl primitives::ElectionsApi<Block, AccountId> for Runtime {
+        fn next_session_committee() -> Vec<AccountId> {
+            Elections::next_session_committee()
+        }
+
+        fn era_validators() -> EraValidators<AccountId> {
+            Elections::era_validators()
+        }
+
+        fn validator_block_count(account: AccountId) -> u32 {
+            Elections::validator_block_count(account)
+        }
+
+        fn era_reward_points_preview() -> Vec<(AccountId, u32)> {
+            Elections::era_reward_points_preview()
+        }
+    }
+
     impl pallet_contracts_rpc_runtime_api::ContractsApi<Block, AccountId, Balance, BlockNumber, Hash> for Runtime {
 
         fn call(
@@ -881,4 +937,45 @@ impl_runtime_apis! {
 
     }
 
+    #[cfg(feature = "runtime-benchmarks")]
+    impl frame_benchmarking::Benchmark<Block> for Runtime {
+        fn benchmark_metadata(extra: bool) -> (
+            Vec<frame_benchmarking::BenchmarkList>,
+            Vec<frame_support::traits::StorageInfo>,
+        ) {
+            use frame_benchmarking::{list_benchmark, Benchmarking, BenchmarkList};
+            use frame_support::traits::StorageInfoTrait;
+
+            let mut list = Vec::<BenchmarkList>::new();
+            list_benchmark!(list, extra, pallet_aleph, Aleph);
+            list_benchmark!(list, extra, pallet_elections, Elections);
+
+            let storage_info = AllPalletsWithSystem::storage_info();
+            (list, storage_info)
+        }
+
+        fn dispatch_benchmark(
+            config: frame_benchmarking::BenchmarkConfig
+        ) -> Result<Vec<frame_benchmarking::BenchmarkBatch>, sp_runtime::RuntimeString> {
+            use frame_benchmarking::{add_benchmark, Benchmarking, BenchmarkBatch, TrackedStorageKey};
+            use frame_support::storage::storage_prefix;
+
+            // Accessed by every block anyway, so not counted in the weights.
+            let whitelist: Vec<TrackedStorageKey> = [
+                &b"Number"[..],
+                b"ExecutionPhase",
+                b"EventCount",
+                b"Events",
+            ]
+            .iter()
+            .map(|item| storage_prefix(b"System", item).to_vec().into())
+            .collect();
+            let mut batches = Vec::<BenchmarkBatch>::new();
+            let params = (&config, &whitelist);
+            add_benchmark!(params, batches, pallet_aleph, Aleph);
+            add_benchmark!(params, batches, pallet_elections, Elections);
+
+            Ok(batches)
+        }
+    }
 }
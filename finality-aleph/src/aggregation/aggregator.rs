@@ -26,36 +26,88 @@ pub enum IOError {
 pub type AggregatorResult<R> = Result<R, AggregatorError>;
 pub type IOResult = Result<(), IOError>;
 
-/// A wrapper around an `rmc::Multicast` returning the signed hashes in the order of the [`Multicast::start_multicast`] calls.
+/// Decides which of the hashes given to the aggregator have to be multisigned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AggregationPolicy {
+    /// Every hash is multisigned and returned.
+    All,
+    /// Hashes still waiting for their multisignatures are dropped once a newer hash arrives, as
+    /// finalizing a block finalizes its ancestors anyway. Hashes marked as required are kept.
+    SkipAhead,
+}
+
+impl Default for AggregationPolicy {
+    fn default() -> Self {
+        AggregationPolicy::All
+    }
+}
+
+/// A wrapper around a `Multicast` returning the signed hashes in the order of the [`Multicast::start_multicast`] calls.
 pub struct BlockSignatureAggregator<H: Hash + Copy, PMS> {
+    policy: AggregationPolicy,
     signatures: HashMap<H, PMS>,
     hash_queue: VecDeque<H>,
+    required_hashes: HashSet<H>,
+    dropped_hashes: HashSet<H>,
     last_hash_placed: bool,
     started_hashes: HashSet<H>,
     metrics: Option<Metrics<H>>,
 }
 
 impl<H: Copy + Hash, PMS> BlockSignatureAggregator<H, PMS> {
-    pub(crate) fn new(metrics: Option<Metrics<H>>) -> Self {
+    pub(crate) fn new(policy: AggregationPolicy, metrics: Option<Metrics<H>>) -> Self {
         BlockSignatureAggregator {
+            policy,
             signatures: HashMap::new(),
             hash_queue: VecDeque::new(),
+            required_hashes: HashSet::new(),
+            dropped_hashes: HashSet::new(),
             last_hash_placed: false,
             started_hashes: HashSet::new(),
             metrics,
         }
     }
 
-    fn on_start(&mut self, hash: H) -> AggregatorResult<()> {
+    // Returns the hashes dropped in favour of the new one.
+    fn on_start(&mut self, hash: H, required: bool) -> AggregatorResult<Vec<H>> {
         if !self.started_hashes.insert(hash) {
             return Err(AggregatorError::DuplicateHash);
         }
         if let Some(metrics) = &self.metrics {
             metrics.report_block(hash, std::time::Instant::now(), Checkpoint::Aggregating);
         }
+        let dropped = match self.policy {
+            AggregationPolicy::SkipAhead => self.drop_pending_hashes(),
+            AggregationPolicy::All => Vec::new(),
+        };
+        if required {
+            self.required_hashes.insert(hash);
+        }
         self.hash_queue.push_back(hash);
 
-        Ok(())
+        Ok(dropped)
+    }
+
+    // Drops the queued hashes that are neither required nor multisigned already.
+    fn drop_pending_hashes(&mut self) -> Vec<H> {
+        let mut dropped = Vec::new();
+        let BlockSignatureAggregator {
+            signatures,
+            hash_queue,
+            required_hashes,
+            dropped_hashes,
+            ..
+        } = self;
+        hash_queue.retain(|hash| {
+            if required_hashes.contains(hash) || signatures.contains_key(hash) {
+                return true;
+            }
+            trace!(target: "aleph-aggregator", "Dropping hash {:?} in favour of a newer one.", hash);
+            dropped_hashes.insert(*hash);
+            dropped.push(*hash);
+            false
+        });
+        dropped
     }
 
     pub(crate) fn notify_last_hash(&mut self) {
@@ -63,6 +115,14 @@ impl<H: Copy + Hash, PMS> BlockSignatureAggregator<H, PMS> {
     }
 
     fn on_multisigned_hash(&mut self, hash: H, signature: PMS) {
+        if self.dropped_hashes.contains(&hash) {
+            trace!(target: "aleph-aggregator", "Ignoring multisigned hash {:?}, as it was dropped.", hash);
+            return;
+        }
+        if self.started_hashes.contains(&hash) && !self.hash_queue.contains(&hash) {
+            trace!(target: "aleph-aggregator", "Ignoring multisigned hash {:?}, as it was already returned.", hash);
+            return;
+        }
         debug!(target: "aleph-aggregator", "New multisigned_hash {:?}.", hash);
         self.signatures.insert(hash, signature);
     }
@@ -71,6 +131,7 @@ impl<H: Copy + Hash, PMS> BlockSignatureAggregator<H, PMS> {
         match self.hash_queue.pop_front() {
             Some(hash) => {
                 if let Some(multisignature) = self.signatures.remove(&hash) {
+                    self.required_hashes.remove(&hash);
                     Ok((hash, multisignature))
                 } else {
                     self.hash_queue.push_front(hash);
@@ -96,7 +157,7 @@ pub struct IO<
     RMC: Multicast<H, PMS>,
 > {
     messages_for_rmc: mpsc::UnboundedSender<D>,
-    messages_from_rmc: mpsc::UnboundedReceiver<D>,
+    messages_from_rmc: mpsc::UnboundedReceiver<(D, Recipient)>,
     network: N,
     multicast: RMC,
     aggregator: BlockSignatureAggregator<H, PMS>,
@@ -112,7 +173,7 @@ impl<
 {
     pub(crate) fn new(
         messages_for_rmc: mpsc::UnboundedSender<D>,
-        messages_from_rmc: mpsc::UnboundedReceiver<D>,
+        messages_from_rmc: mpsc::UnboundedReceiver<(D, Recipient)>,
         network: N,
         multicast: RMC,
         aggregator: BlockSignatureAggregator<H, PMS>,
@@ -126,11 +187,20 @@ impl<
         }
     }
 
-    pub(crate) async fn start_aggregation(&mut self, hash: H) {
+    /// Starts multisigning the hash. Required hashes are never dropped by the aggregation policy.
+    pub(crate) async fn start_aggregation(&mut self, hash: H, required: bool) {
         debug!(target: "aleph-aggregator", "Started aggregation for block hash {:?}", hash);
-        if let Err(AggregatorError::DuplicateHash) = self.aggregator.on_start(hash) {
-            debug!(target: "aleph-aggregator", "Aggregation already started for block hash {:?}, ignoring.", hash);
-            return;
+        match self.aggregator.on_start(hash, required) {
+            Ok(dropped) => {
+                for dropped_hash in dropped {
+                    self.multicast.forget(&dropped_hash);
+                }
+            }
+            Err(AggregatorError::DuplicateHash) => {
+                debug!(target: "aleph-aggregator", "Aggregation already started for block hash {:?}, ignoring.", hash);
+                return;
+            }
+            Err(_) => {}
         }
         self.multicast
             .start_multicast(SignableHash::new(hash))
@@ -151,8 +221,8 @@ impl<
                 message_from_rmc = self.messages_from_rmc.next() => {
                     trace!(target: "aleph-aggregator", "Our rmc message {:?}.", message_from_rmc);
                     match message_from_rmc {
-                        Some(message_from_rmc) => {
-                            self.network.send(message_from_rmc, Recipient::Everyone)
+                        Some((message_from_rmc, recipient)) => {
+                            self.network.send(message_from_rmc, recipient)
                                         .expect("sending message from rmc failed");
                         },
                         None => {
@@ -181,6 +251,7 @@ impl<
             trace!(target: "aleph-aggregator", "Entering next_multisigned_hash loop.");
             match self.aggregator.try_pop_hash() {
                 Ok(res) => {
+                    self.multicast.forget(&res.0);
                     return Some(res);
                 }
                 Err(AggregatorError::LastHashPlaced) => {
@@ -209,14 +280,20 @@ impl<
 
 #[cfg(test)]
 mod tests {
-    use crate::aggregation::aggregator::{AggregatorError, BlockSignatureAggregator};
+    use crate::aggregation::aggregator::{
+        AggregationPolicy, AggregatorError, BlockSignatureAggregator,
+    };
     use substrate_test_runtime::Hash as THash;
 
     type TestMultisignature = usize;
     const TEST_SIGNATURE: TestMultisignature = 42;
 
     fn build_aggregator() -> BlockSignatureAggregator<THash, TestMultisignature> {
-        BlockSignatureAggregator::new(None)
+        BlockSignatureAggregator::new(AggregationPolicy::All, None)
+    }
+
+    fn build_skipping_aggregator() -> BlockSignatureAggregator<THash, TestMultisignature> {
+        BlockSignatureAggregator::new(AggregationPolicy::SkipAhead, None)
     }
 
     fn build_hash(b0: u8) -> THash {
@@ -228,7 +305,7 @@ mod tests {
     #[test]
     fn returns_with_matching_multisigned_hash() {
         let mut aggregator = build_aggregator();
-        let res = aggregator.on_start(build_hash(0));
+        let res = aggregator.on_start(build_hash(0), false);
         assert!(res.is_ok());
 
         aggregator.on_multisigned_hash(build_hash(0), TEST_SIGNATURE);
@@ -240,7 +317,7 @@ mod tests {
     #[test]
     fn doesnt_return_without_matching_multisigned_hash() {
         let mut aggregator = build_aggregator();
-        let res = aggregator.on_start(build_hash(0));
+        let res = aggregator.on_start(build_hash(0), false);
         assert!(res.is_ok());
 
         aggregator.on_multisigned_hash(build_hash(1), TEST_SIGNATURE);
@@ -256,4 +333,130 @@ mod tests {
         let res = aggregator.try_pop_hash();
         assert_eq!(res, Err(AggregatorError::LastHashPlaced));
     }
+
+    #[test]
+    fn returns_all_hashes_in_order_without_skipping() {
+        let mut aggregator = build_aggregator();
+        assert!(aggregator.on_start(build_hash(0), false).is_ok());
+        assert!(aggregator.on_start(build_hash(1), false).is_ok());
+
+        aggregator.on_multisigned_hash(build_hash(1), TEST_SIGNATURE);
+        assert_eq!(aggregator.try_pop_hash(), Err(AggregatorError::NoHashFound));
+
+        aggregator.on_multisigned_hash(build_hash(0), TEST_SIGNATURE);
+        assert_eq!(
+            aggregator.try_pop_hash(),
+            Ok((build_hash(0), TEST_SIGNATURE))
+        );
+        assert_eq!(
+            aggregator.try_pop_hash(),
+            Ok((build_hash(1), TEST_SIGNATURE))
+        );
+    }
+
+    #[test]
+    fn skips_pending_hash_when_newer_arrives() {
+        let mut aggregator = build_skipping_aggregator();
+        assert!(aggregator.on_start(build_hash(0), false).is_ok());
+        assert!(aggregator.on_start(build_hash(1), false).is_ok());
+
+        aggregator.on_multisigned_hash(build_hash(1), TEST_SIGNATURE);
+        assert_eq!(
+            aggregator.try_pop_hash(),
+            Ok((build_hash(1), TEST_SIGNATURE))
+        );
+        assert_eq!(aggregator.try_pop_hash(), Err(AggregatorError::NoHashFound));
+    }
+
+    #[test]
+    fn ignores_multisignature_of_skipped_hash() {
+        let mut aggregator = build_skipping_aggregator();
+        assert!(aggregator.on_start(build_hash(0), false).is_ok());
+        assert!(aggregator.on_start(build_hash(1), false).is_ok());
+
+        aggregator.on_multisigned_hash(build_hash(0), TEST_SIGNATURE);
+        assert_eq!(aggregator.try_pop_hash(), Err(AggregatorError::NoHashFound));
+
+        aggregator.on_multisigned_hash(build_hash(1), TEST_SIGNATURE);
+        assert_eq!(
+            aggregator.try_pop_hash(),
+            Ok((build_hash(1), TEST_SIGNATURE))
+        );
+    }
+
+    #[test]
+    fn keeps_required_hash_when_skipping() {
+        let mut aggregator = build_skipping_aggregator();
+        assert!(aggregator.on_start(build_hash(0), true).is_ok());
+        assert!(aggregator.on_start(build_hash(1), false).is_ok());
+
+        aggregator.on_multisigned_hash(build_hash(1), TEST_SIGNATURE);
+        assert_eq!(aggregator.try_pop_hash(), Err(AggregatorError::NoHashFound));
+
+        aggregator.on_multisigned_hash(build_hash(0), TEST_SIGNATURE);
+        assert_eq!(
+            aggregator.try_pop_hash(),
+            Ok((build_hash(0), TEST_SIGNATURE))
+        );
+        assert_eq!(
+            aggregator.try_pop_hash(),
+            Ok((build_hash(1), TEST_SIGNATURE))
+        );
+    }
+
+    #[test]
+    fn keeps_multisigned_hash_when_skipping() {
+        let mut aggregator = build_skipping_aggregator();
+        assert!(aggregator.on_start(build_hash(0), false).is_ok());
+        aggregator.on_multisigned_hash(build_hash(0), TEST_SIGNATURE);
+        assert!(aggregator.on_start(build_hash(1), false).is_ok());
+
+        assert_eq!(
+            aggregator.try_pop_hash(),
+            Ok((build_hash(0), TEST_SIGNATURE))
+        );
+    }
+
+    #[test]
+    fn keeps_last_hash_when_skipping() {
+        let mut aggregator = build_skipping_aggregator();
+        assert!(aggregator.on_start(build_hash(0), false).is_ok());
+        assert!(aggregator.on_start(build_hash(1), true).is_ok());
+        aggregator.notify_last_hash();
+
+        aggregator.on_multisigned_hash(build_hash(1), TEST_SIGNATURE);
+        assert_eq!(
+            aggregator.try_pop_hash(),
+            Ok((build_hash(1), TEST_SIGNATURE))
+        );
+        assert_eq!(
+            aggregator.try_pop_hash(),
+            Err(AggregatorError::LastHashPlaced)
+        );
+    }
+
+    #[test]
+    fn reports_dropped_hashes() {
+        let mut aggregator = build_skipping_aggregator();
+        assert_eq!(aggregator.on_start(build_hash(0), true), Ok(Vec::new()));
+        assert_eq!(aggregator.on_start(build_hash(1), false), Ok(Vec::new()));
+        assert_eq!(
+            aggregator.on_start(build_hash(2), false),
+            Ok(vec![build_hash(1)])
+        );
+    }
+
+    #[test]
+    fn ignores_multisignature_of_returned_hash() {
+        let mut aggregator = build_aggregator();
+        assert!(aggregator.on_start(build_hash(0), false).is_ok());
+        aggregator.on_multisigned_hash(build_hash(0), TEST_SIGNATURE);
+        assert_eq!(
+            aggregator.try_pop_hash(),
+            Ok((build_hash(0), TEST_SIGNATURE))
+        );
+
+        aggregator.on_multisigned_hash(build_hash(0), TEST_SIGNATURE);
+        assert!(aggregator.signatures.is_empty());
+    }
 }
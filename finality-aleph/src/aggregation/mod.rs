@@ -1,13 +1,29 @@
-use crate::crypto::Signature;
-use aleph_bft::SignatureSet;
-use aleph_bft_rmc::Message;
 use sp_runtime::traits::Block;
 
 mod aggregator;
 mod multicast;
 
-pub use aggregator::{BlockSignatureAggregator, IO};
-pub use multicast::SignableHash;
+pub use aggregator::{AggregationPolicy, BlockSignatureAggregator, IO};
+pub use multicast::{Multicast, RmcMulticast, SignableHash, ThresholdMulticast};
 
-pub type RmcNetworkData<B> =
-    Message<SignableHash<<B as Block>::Hash>, Signature, SignatureSet<Signature>>;
+pub type RmcNetworkData<B> = multicast::NetworkData<<B as Block>::Hash>;
+
+/// Decides how the members of the committee exchange signatures of blocks. All members of a
+/// session have to use the same variant, so it is chosen by the finality version of the session.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MulticastVariant {
+    /// Every member broadcasts its signatures using the `ReliableMulticast`.
+    Reliable,
+    /// Signatures are combined by a leader rotating between blocks, see `ThresholdMulticast`.
+    Threshold,
+}
+
+/// Configures which blocks get multisigned by the aggregator.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AggregatorConfig {
+    pub policy: AggregationPolicy,
+    /// When skipping ahead, blocks with numbers divisible by this are kept as well, so that
+    /// justifications of blocks within a session remain available to syncing nodes. Zero keeps
+    /// only the last blocks of sessions.
+    pub keep_every: u32,
+}
@@ -1,13 +1,40 @@
 //! A set of abstractions for dealing with `ReliableMulticast` in a more testable
 //! and modular way.
 //!
-//! We expose the `Multicast` trait, mimicking the interface of `aleph_bft::ReliableMulticast`
+//! We expose the `Multicast` trait, mimicking the interface of `aleph_bft::ReliableMulticast`,
+//! `RmcMulticast`, which signs every hash using the `ReliableMulticast`, and `ThresholdMulticast`,
+//! which collects signatures at a leader and falls back to the `ReliableMulticast` if that takes
+//! too long. Both speak the same `NetworkData`.
 
 use crate::crypto::{KeyBox, Signature};
-use aleph_bft::{Signable, SignatureSet};
-use aleph_bft_rmc::ReliableMulticast;
+use aleph_bft::{
+    Index, KeyBox as _, MultiKeychain, NodeCount, NodeIndex, PartialMultisignature, Recipient,
+    Signable, SignatureSet,
+};
+use aleph_bft_rmc::{DoublingDelayScheduler, Message as RmcMessage, ReliableMulticast};
 use codec::{Codec, Decode, Encode};
-use std::{fmt::Debug, hash::Hash as StdHash};
+use futures::{channel::mpsc, StreamExt};
+use futures_timer::Delay;
+use log::{debug, trace, warn};
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Debug,
+    hash::Hash as StdHash,
+    time::{Duration, Instant},
+};
+
+// How long we wait for the multisignature from the leader before falling back to the
+// `ReliableMulticast`.
+const FALLBACK_TIMEOUT: Duration = Duration::from_secs(2);
+const FALLBACK_CHECK_INTERVAL: Duration = Duration::from_millis(200);
+const RMC_START_DELAY: Duration = Duration::from_millis(500);
+// How long we keep the multisignature of a hash the aggregator no longer needs, so that members
+// which did not get it from the leader can still get it from us.
+const FINISHED_RETENTION: Duration = Duration::from_secs(10);
+// The most hashes we keep signatures of, a bit more than the blocks in a default session. Above
+// this the oldest entries are forgotten, so members cannot make us store shares of arbitrary
+// hashes.
+const MAX_KEPT_HASHES: usize = 1024;
 
 /// A convenience trait for gathering all of the desired hash characteristics.
 pub trait Hash: AsRef<[u8]> + StdHash + Eq + Clone + Codec + Debug + Send + Sync {}
@@ -45,6 +72,9 @@ impl<H: Hash> Signable for SignableHash<H> {
 pub trait Multicast<H: Hash, PMS>: Send + Sync {
     async fn start_multicast(&mut self, signable: SignableHash<H>);
     async fn next_signed_pair(&mut self) -> (H, PMS);
+    /// Called once the aggregator no longer needs the hash, so that anything kept for it can be
+    /// dropped.
+    fn forget(&mut self, _hash: &H) {}
 }
 
 #[async_trait::async_trait]
@@ -60,3 +90,618 @@ impl<'a, H: Hash> Multicast<H, SignatureSet<Signature>>
         (ms.as_signable().get_hash(), ms.signature())
     }
 }
+
+type RmcData<H> = RmcMessage<SignableHash<H>, Signature, SignatureSet<Signature>>;
+
+/// The messages sent by `RmcMulticast` and `ThresholdMulticast`.
+#[derive(Clone, Debug, Encode, Decode)]
+pub enum NetworkData<H: Hash> {
+    /// A signature of the hash made by the given member, sent to the leader of the hash.
+    Share(H, NodeIndex, Signature),
+    /// A complete multisignature of the hash, broadcast by its leader.
+    Multisigned(H, SignatureSet<Signature>),
+    /// A message of the fallback `ReliableMulticast`.
+    Rmc(RmcData<H>),
+}
+
+fn send<H: Hash>(
+    messages_for_network: &mpsc::UnboundedSender<(NetworkData<H>, Recipient)>,
+    data: NetworkData<H>,
+    recipient: Recipient,
+) {
+    if messages_for_network
+        .unbounded_send((data, recipient))
+        .is_err()
+    {
+        warn!(target: "aleph-aggregator", "Channel for messages from the multicast closed.");
+    }
+}
+
+fn new_rmc<'a, H: Hash>(
+    keychain: &'a KeyBox,
+) -> (
+    ReliableMulticast<'a, SignableHash<H>, KeyBox>,
+    mpsc::UnboundedSender<RmcData<H>>,
+    mpsc::UnboundedReceiver<(RmcData<H>, Recipient)>,
+) {
+    let (messages_for_rmc, messages_from_network_for_rmc) = mpsc::unbounded();
+    let (messages_for_network_from_rmc, messages_from_rmc) = mpsc::unbounded();
+    let rmc = ReliableMulticast::new(
+        messages_from_network_for_rmc,
+        messages_for_network_from_rmc,
+        keychain,
+        keychain.node_count(),
+        DoublingDelayScheduler::new(RMC_START_DELAY),
+    );
+    (rmc, messages_for_rmc, messages_from_rmc)
+}
+
+/// Signs every hash using the `ReliableMulticast`, wrapping its messages in `NetworkData`.
+pub struct RmcMulticast<'a, H: Hash> {
+    rmc: ReliableMulticast<'a, SignableHash<H>, KeyBox>,
+    messages_for_rmc: mpsc::UnboundedSender<RmcData<H>>,
+    messages_from_rmc: mpsc::UnboundedReceiver<(RmcData<H>, Recipient)>,
+    messages_from_network: mpsc::UnboundedReceiver<NetworkData<H>>,
+    messages_for_network: mpsc::UnboundedSender<(NetworkData<H>, Recipient)>,
+}
+
+impl<'a, H: Hash> RmcMulticast<'a, H> {
+    pub fn new(
+        messages_from_network: mpsc::UnboundedReceiver<NetworkData<H>>,
+        messages_for_network: mpsc::UnboundedSender<(NetworkData<H>, Recipient)>,
+        keychain: &'a KeyBox,
+    ) -> Self {
+        let (rmc, messages_for_rmc, messages_from_rmc) = new_rmc(keychain);
+        RmcMulticast {
+            rmc,
+            messages_for_rmc,
+            messages_from_rmc,
+            messages_from_network,
+            messages_for_network,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<'a, H: Hash> Multicast<H, SignatureSet<Signature>> for RmcMulticast<'a, H> {
+    async fn start_multicast(&mut self, signable: SignableHash<H>) {
+        self.rmc.start_rmc(signable).await;
+    }
+
+    async fn next_signed_pair(&mut self) -> (H, SignatureSet<Signature>) {
+        loop {
+            tokio::select! {
+                multisigned = self.rmc.next_multisigned_hash() => {
+                    let multisigned = multisigned.into_unchecked();
+                    return (multisigned.as_signable().get_hash(), multisigned.signature());
+                }
+                Some(data) = self.messages_from_network.next() => match data {
+                    NetworkData::Rmc(message) => {
+                        if self.messages_for_rmc.unbounded_send(message).is_err() {
+                            warn!(target: "aleph-aggregator", "Channel for messages to the rmc closed.");
+                        }
+                    }
+                    data => {
+                        trace!(target: "aleph-aggregator", "Ignoring {:?}, as only the rmc is used.", data);
+                    }
+                },
+                Some((message, recipient)) = self.messages_from_rmc.next() => {
+                    send(&self.messages_for_network, NetworkData::Rmc(message), recipient);
+                }
+            }
+        }
+    }
+}
+
+// A map forgetting its oldest entries once it holds more than `MAX_KEPT_HASHES` of them.
+struct BoundedMap<K, V> {
+    entries: HashMap<K, V>,
+    order: VecDeque<K>,
+}
+
+impl<K: StdHash + Eq + Clone, V> BoundedMap<K, V> {
+    fn new() -> Self {
+        BoundedMap {
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    fn get(&self, key: &K) -> Option<&V> {
+        self.entries.get(key)
+    }
+
+    fn contains_key(&self, key: &K) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    fn insert(&mut self, key: K, value: V) {
+        if self.entries.insert(key.clone(), value).is_none() {
+            self.order.push_back(key);
+        }
+        while self.entries.len() > MAX_KEPT_HASHES {
+            match self.order.pop_front() {
+                Some(oldest) => {
+                    self.entries.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        let value = self.entries.remove(key)?;
+        self.order.retain(|kept| kept != key);
+        Some(value)
+    }
+}
+
+// The leader of a hash is determined by the hash itself, so the role rotates between the members
+// with consecutive blocks.
+fn leader_of<H: Hash>(hash: &H, node_count: NodeCount) -> NodeIndex {
+    let index = hash
+        .as_ref()
+        .iter()
+        .fold(0, |acc, byte| (acc * 256 + *byte as usize) % node_count.0);
+    NodeIndex(index)
+}
+
+/// Collects signatures of hashes without broadcasting them to everyone. Every member sends its
+/// signature to the leader of the hash, which combines them and broadcasts only the resulting
+/// multisignature. If the multisignature does not arrive in time, the hash is signed using the
+/// `ReliableMulticast` instead.
+pub struct ThresholdMulticast<'a, H: Hash> {
+    keychain: &'a KeyBox,
+    rmc: ReliableMulticast<'a, SignableHash<H>, KeyBox>,
+    messages_for_rmc: mpsc::UnboundedSender<RmcData<H>>,
+    messages_from_rmc: mpsc::UnboundedReceiver<(RmcData<H>, Recipient)>,
+    messages_from_network: mpsc::UnboundedReceiver<NetworkData<H>>,
+    messages_for_network: mpsc::UnboundedSender<(NetworkData<H>, Recipient)>,
+    // Signatures collected as the leader.
+    shares: BoundedMap<H, SignatureSet<Signature>>,
+    // Hashes we signed, with the time we did so, waiting for their multisignatures.
+    pending: HashMap<H, Instant>,
+    // Multisignatures we already have, kept to help members that fell back to the rmc.
+    finished: BoundedMap<H, SignatureSet<Signature>>,
+    // Hashes the aggregator no longer needs, with the time it told us so.
+    forgotten: VecDeque<(H, Instant)>,
+    ready: VecDeque<(H, SignatureSet<Signature>)>,
+    fallback_check: Delay,
+}
+
+impl<'a, H: Hash> ThresholdMulticast<'a, H> {
+    pub fn new(
+        messages_from_network: mpsc::UnboundedReceiver<NetworkData<H>>,
+        messages_for_network: mpsc::UnboundedSender<(NetworkData<H>, Recipient)>,
+        keychain: &'a KeyBox,
+    ) -> Self {
+        let (rmc, messages_for_rmc, messages_from_rmc) = new_rmc(keychain);
+        ThresholdMulticast {
+            keychain,
+            rmc,
+            messages_for_rmc,
+            messages_from_rmc,
+            messages_from_network,
+            messages_for_network,
+            shares: BoundedMap::new(),
+            pending: HashMap::new(),
+            finished: BoundedMap::new(),
+            forgotten: VecDeque::new(),
+            ready: VecDeque::new(),
+            fallback_check: Delay::new(FALLBACK_CHECK_INTERVAL),
+        }
+    }
+
+    fn send(&self, data: NetworkData<H>, recipient: Recipient) {
+        send(&self.messages_for_network, data, recipient);
+    }
+
+    fn finish(&mut self, hash: H, multisignature: SignatureSet<Signature>) {
+        self.pending.remove(&hash);
+        self.shares.remove(&hash);
+        if !self.finished.contains_key(&hash) {
+            self.finished.insert(hash.clone(), multisignature.clone());
+            self.ready.push_back((hash, multisignature));
+        }
+    }
+
+    // Drops the multisignatures of hashes forgotten long enough ago.
+    fn evict_forgotten(&mut self) {
+        while let Some((hash, forgotten_at)) = self.forgotten.front() {
+            if forgotten_at.elapsed() < FINISHED_RETENTION {
+                break;
+            }
+            self.finished.remove(hash);
+            self.forgotten.pop_front();
+        }
+    }
+
+    fn on_share(&mut self, hash: H, index: NodeIndex, signature: Signature) {
+        if self.finished.contains_key(&hash)
+            || leader_of(&hash, self.keychain.node_count()) != self.keychain.index()
+            || !self.keychain.verify(hash.as_ref(), &signature, index)
+        {
+            return;
+        }
+        let shares = self
+            .shares
+            .remove(&hash)
+            .unwrap_or_else(|| SignatureSet::with_size(self.keychain.node_count()))
+            .add_signature(&signature, index);
+        if self.keychain.is_complete(hash.as_ref(), &shares) {
+            debug!(target: "aleph-aggregator", "Collected enough signatures of {:?} as the leader.", hash);
+            self.send(
+                NetworkData::Multisigned(hash.clone(), shares.clone()),
+                Recipient::Everyone,
+            );
+            self.finish(hash, shares);
+        } else {
+            self.shares.insert(hash, shares);
+        }
+    }
+
+    fn on_network_data(&mut self, data: NetworkData<H>) {
+        match data {
+            NetworkData::Share(hash, index, signature) => self.on_share(hash, index, signature),
+            NetworkData::Multisigned(hash, multisignature) => {
+                if !self.finished.contains_key(&hash)
+                    && self.keychain.is_complete(hash.as_ref(), &multisignature)
+                {
+                    self.finish(hash, multisignature);
+                }
+            }
+            NetworkData::Rmc(message) => {
+                if let RmcMessage::SignedHash(unchecked) = &message {
+                    let indexed = unchecked.as_signable();
+                    let hash = indexed.as_signable().get_hash();
+                    // The member did not get the multisignature from the leader, we send it
+                    // directly instead of joining the rmc.
+                    if let Some(multisignature) = self.finished.get(&hash) {
+                        self.send(
+                            NetworkData::Multisigned(hash, multisignature.clone()),
+                            Recipient::Node(indexed.index()),
+                        );
+                        return;
+                    }
+                }
+                if self.messages_for_rmc.unbounded_send(message).is_err() {
+                    warn!(target: "aleph-aggregator", "Channel for messages to the rmc closed.");
+                }
+            }
+        }
+    }
+
+    async fn fall_back_expired(&mut self) {
+        let expired: Vec<_> = self
+            .pending
+            .iter()
+            .filter(|(_, started)| started.elapsed() >= FALLBACK_TIMEOUT)
+            .map(|(hash, _)| hash.clone())
+            .collect();
+        for hash in expired {
+            debug!(target: "aleph-aggregator", "No multisignature of {:?} from the leader, falling back to rmc.", hash);
+            self.rmc.start_rmc(SignableHash::new(hash.clone())).await;
+            // Only removed after the rmc started, so it is retried if we get cancelled before.
+            self.pending.remove(&hash);
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl<'a, H: Hash> Multicast<H, SignatureSet<Signature>> for ThresholdMulticast<'a, H> {
+    async fn start_multicast(&mut self, signable: SignableHash<H>) {
+        let hash = signable.get_hash();
+        if self.finished.contains_key(&hash) || self.pending.contains_key(&hash) {
+            return;
+        }
+        let signature = self.keychain.sign(hash.as_ref()).await;
+        self.pending.insert(hash.clone(), Instant::now());
+        let index = self.keychain.index();
+        let leader = leader_of(&hash, self.keychain.node_count());
+        if leader == index {
+            self.on_share(hash, index, signature);
+        } else {
+            trace!(target: "aleph-aggregator", "Sending signature of {:?} to the leader {:?}.", hash, leader);
+            self.send(
+                NetworkData::Share(hash, index, signature),
+                Recipient::Node(leader),
+            );
+        }
+    }
+
+    async fn next_signed_pair(&mut self) -> (H, SignatureSet<Signature>) {
+        loop {
+            if let Some(pair) = self.ready.pop_front() {
+                return pair;
+            }
+            tokio::select! {
+                multisigned = self.rmc.next_multisigned_hash() => {
+                    let multisigned = multisigned.into_unchecked();
+                    self.finish(multisigned.as_signable().get_hash(), multisigned.signature());
+                }
+                Some(data) = self.messages_from_network.next() => {
+                    self.on_network_data(data);
+                }
+                Some((message, recipient)) = self.messages_from_rmc.next() => {
+                    self.send(NetworkData::Rmc(message), recipient);
+                }
+                _ = &mut self.fallback_check => {
+                    self.fall_back_expired().await;
+                    self.evict_forgotten();
+                    self.fallback_check = Delay::new(FALLBACK_CHECK_INTERVAL);
+                }
+            }
+        }
+    }
+
+    fn forget(&mut self, hash: &H) {
+        self.pending.remove(hash);
+        self.shares.remove(hash);
+        if self.finished.contains_key(hash) {
+            self.forgotten.push_back((hash.clone(), Instant::now()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        leader_of, BoundedMap, Multicast, NetworkData, RmcMulticast, SignableHash,
+        ThresholdMulticast, MAX_KEPT_HASHES,
+    };
+    use crate::crypto::{AuthorityPen, AuthorityVerifier, KeyBox, Signature};
+    use aleph_bft::{
+        KeyBox as _, NodeCount, NodeIndex, PartialMultisignature, Recipient, SignatureSet,
+    };
+    use aleph_primitives::{AuthorityId, KEY_TYPE};
+    use futures::{channel::mpsc, StreamExt};
+    use sp_keystore::{testing::KeyStore, CryptoStore};
+    use std::{sync::Arc, time::Duration};
+    use substrate_test_runtime::Hash as THash;
+    use tokio::time::timeout;
+
+    const NODE_COUNT: usize = 4;
+
+    type Channels = (
+        mpsc::UnboundedSender<NetworkData<THash>>,
+        mpsc::UnboundedReceiver<(NetworkData<THash>, Recipient)>,
+    );
+
+    async fn keyboxes() -> Vec<KeyBox> {
+        let key_store = Arc::new(KeyStore::new());
+        let mut authority_ids = Vec::with_capacity(NODE_COUNT);
+        for i in 0..NODE_COUNT {
+            let pk = key_store
+                .ed25519_generate_new(KEY_TYPE, Some(&format!("//{}", i)))
+                .await
+                .unwrap();
+            authority_ids.push(AuthorityId::from(pk));
+        }
+        let verifier = AuthorityVerifier::new(authority_ids.clone());
+        let mut keyboxes = Vec::with_capacity(NODE_COUNT);
+        for (i, authority_id) in authority_ids.into_iter().enumerate() {
+            let pen = AuthorityPen::new(authority_id, key_store.clone())
+                .await
+                .expect("The keys should sign successfully");
+            keyboxes.push(KeyBox::new(NodeIndex(i), verifier.clone(), pen));
+        }
+        keyboxes
+    }
+
+    fn build_multicast(keychain: &KeyBox) -> (ThresholdMulticast<THash>, Channels) {
+        let (messages_for_multicast, messages_from_network) = mpsc::unbounded();
+        let (messages_for_network, messages_from_multicast) = mpsc::unbounded();
+        (
+            ThresholdMulticast::new(messages_from_network, messages_for_network, keychain),
+            (messages_for_multicast, messages_from_multicast),
+        )
+    }
+
+    fn build_hash(last_byte: u8) -> THash {
+        let mut bytes = [0u8; 32];
+        bytes[31] = last_byte;
+        THash::from(bytes)
+    }
+
+    // A hash led by the given member.
+    fn hash_led_by(leader: usize) -> THash {
+        (0..=u8::MAX)
+            .map(build_hash)
+            .find(|hash| leader_of(hash, NodeCount(NODE_COUNT)) == NodeIndex(leader))
+            .unwrap()
+    }
+
+    async fn sign(keychain: &KeyBox, hash: &THash) -> Signature {
+        keychain.sign(hash.as_ref()).await
+    }
+
+    #[test]
+    fn leader_is_a_member() {
+        for last_byte in 0..=u8::MAX {
+            let hash = build_hash(last_byte);
+            let leader = leader_of(&hash, NodeCount(NODE_COUNT));
+            assert!(leader.0 < NODE_COUNT);
+            assert_eq!(leader, leader_of(&hash, NodeCount(NODE_COUNT)));
+        }
+    }
+
+    #[tokio::test]
+    async fn sends_share_to_leader() {
+        let keyboxes = keyboxes().await;
+        let hash = hash_led_by(1);
+        let (mut multicast, (_, mut messages_from_multicast)) = build_multicast(&keyboxes[0]);
+
+        multicast.start_multicast(SignableHash::new(hash)).await;
+
+        match messages_from_multicast.next().await {
+            Some((NetworkData::Share(share_hash, index, _), Recipient::Node(leader))) => {
+                assert_eq!(share_hash, hash);
+                assert_eq!(index, NodeIndex(0));
+                assert_eq!(leader, NodeIndex(1));
+            }
+            other => panic!("Expected a share sent to the leader, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn leader_combines_shares_and_broadcasts_multisignature() {
+        let keyboxes = keyboxes().await;
+        let hash = hash_led_by(0);
+        let (mut multicast, (messages_for_multicast, mut messages_from_multicast)) =
+            build_multicast(&keyboxes[0]);
+
+        multicast.start_multicast(SignableHash::new(hash)).await;
+        for keychain in &keyboxes[1..3] {
+            let share = NetworkData::Share(hash, keychain.index(), sign(keychain, &hash).await);
+            messages_for_multicast.unbounded_send(share).unwrap();
+        }
+
+        let (signed_hash, multisignature) =
+            timeout(Duration::from_millis(500), multicast.next_signed_pair())
+                .await
+                .expect("the multisignature should be ready");
+        assert_eq!(signed_hash, hash);
+        assert!(keyboxes[0].is_complete(hash.as_ref(), &multisignature));
+        match messages_from_multicast.next().await {
+            Some((NetworkData::Multisigned(broadcast_hash, _), Recipient::Everyone)) => {
+                assert_eq!(broadcast_hash, hash)
+            }
+            other => panic!("Expected a broadcast multisignature, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn accepts_only_complete_multisignatures() {
+        let keyboxes = keyboxes().await;
+        let hash = hash_led_by(1);
+        let (mut multicast, (messages_for_multicast, _messages_from_multicast)) =
+            build_multicast(&keyboxes[0]);
+
+        let mut multisignature = SignatureSet::with_size(NodeCount(NODE_COUNT));
+        for keychain in &keyboxes[1..3] {
+            multisignature =
+                multisignature.add_signature(&sign(keychain, &hash).await, keychain.index());
+        }
+        messages_for_multicast
+            .unbounded_send(NetworkData::Multisigned(hash, multisignature.clone()))
+            .unwrap();
+        assert!(
+            timeout(Duration::from_millis(300), multicast.next_signed_pair())
+                .await
+                .is_err()
+        );
+
+        let multisignature =
+            multisignature.add_signature(&sign(&keyboxes[3], &hash).await, NodeIndex(3));
+        messages_for_multicast
+            .unbounded_send(NetworkData::Multisigned(hash, multisignature))
+            .unwrap();
+        let (signed_hash, _) = timeout(Duration::from_millis(300), multicast.next_signed_pair())
+            .await
+            .expect("the multisignature should be accepted");
+        assert_eq!(signed_hash, hash);
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_rmc_without_leader() {
+        let keyboxes = keyboxes().await;
+        let hash = hash_led_by(1);
+        let (mut multicast, (_messages_for_multicast, mut messages_from_multicast)) =
+            build_multicast(&keyboxes[0]);
+
+        multicast.start_multicast(SignableHash::new(hash)).await;
+        assert!(matches!(
+            messages_from_multicast.next().await,
+            Some((NetworkData::Share(..), Recipient::Node(_)))
+        ));
+
+        let _ = timeout(
+            super::FALLBACK_TIMEOUT + Duration::from_secs(1),
+            multicast.next_signed_pair(),
+        )
+        .await;
+        assert!(matches!(
+            messages_from_multicast.try_next(),
+            Ok(Some((NetworkData::Rmc(_), Recipient::Everyone)))
+        ));
+    }
+
+    #[test]
+    fn bounded_map_forgets_oldest_entries() {
+        let mut map = BoundedMap::new();
+        for i in 0..MAX_KEPT_HASHES + 2 {
+            map.insert(i, i);
+        }
+        assert_eq!(map.entries.len(), MAX_KEPT_HASHES);
+        assert!(!map.contains_key(&0));
+        assert!(!map.contains_key(&1));
+        assert_eq!(
+            map.get(&(MAX_KEPT_HASHES + 1)),
+            Some(&(MAX_KEPT_HASHES + 1))
+        );
+
+        assert_eq!(map.remove(&2), Some(2));
+        assert_eq!(map.order.len(), MAX_KEPT_HASHES - 1);
+    }
+
+    #[tokio::test]
+    async fn forgets_shares_and_pending_hashes() {
+        let keyboxes = keyboxes().await;
+        let hash = hash_led_by(0);
+        let (mut multicast, _channels) = build_multicast(&keyboxes[0]);
+
+        multicast.start_multicast(SignableHash::new(hash)).await;
+        assert!(multicast.pending.contains_key(&hash));
+        assert!(multicast.shares.contains_key(&hash));
+
+        multicast.forget(&hash);
+        assert!(!multicast.pending.contains_key(&hash));
+        assert!(!multicast.shares.contains_key(&hash));
+        assert!(multicast.forgotten.is_empty());
+    }
+
+    #[tokio::test]
+    async fn keeps_forgotten_multisignature_for_a_while() {
+        let keyboxes = keyboxes().await;
+        let hash = hash_led_by(1);
+        let (mut multicast, (messages_for_multicast, _messages_from_multicast)) =
+            build_multicast(&keyboxes[0]);
+
+        let mut multisignature = SignatureSet::with_size(NodeCount(NODE_COUNT));
+        for keychain in &keyboxes[1..] {
+            multisignature =
+                multisignature.add_signature(&sign(keychain, &hash).await, keychain.index());
+        }
+        messages_for_multicast
+            .unbounded_send(NetworkData::Multisigned(hash, multisignature))
+            .unwrap();
+        timeout(Duration::from_millis(300), multicast.next_signed_pair())
+            .await
+            .expect("the multisignature should be accepted");
+
+        multicast.forget(&hash);
+        multicast.evict_forgotten();
+        assert!(multicast.finished.contains_key(&hash));
+        assert_eq!(multicast.forgotten.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn rmc_multicast_wraps_rmc_messages() {
+        let keyboxes = keyboxes().await;
+        let hash = hash_led_by(1);
+        let (messages_for_multicast, messages_from_network) = mpsc::unbounded();
+        let (messages_for_network, mut messages_from_multicast) = mpsc::unbounded();
+        let mut multicast =
+            RmcMulticast::new(messages_from_network, messages_for_network, &keyboxes[0]);
+
+        let share = NetworkData::Share(hash, NodeIndex(1), sign(&keyboxes[1], &hash).await);
+        messages_for_multicast.unbounded_send(share).unwrap();
+        multicast.start_multicast(SignableHash::new(hash)).await;
+        let _ = timeout(Duration::from_secs(1), multicast.next_signed_pair()).await;
+
+        match messages_from_multicast.try_next() {
+            Ok(Some((NetworkData::Rmc(_), Recipient::Everyone))) => {}
+            other => panic!("Expected a broadcast rmc message, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,635 @@
+use crate::{
+    network::{ComponentNetwork, ReceiverComponent, RequestBlocks, SenderComponent},
+    BlockHashNum,
+};
+use aleph_bft::{NodeCount, NodeIndex, Recipient};
+use codec::{Decode, Encode};
+use futures::{
+    channel::{mpsc, oneshot},
+    Future, FutureExt, StreamExt,
+};
+use futures_timer::Delay;
+use log::{debug, trace, warn};
+use sc_client_api::{BlockBackend, HeaderBackend};
+use sc_consensus::import_queue::{
+    BlockImportError, BlockImportStatus, ImportQueue, IncomingBlock, Link,
+};
+use sp_consensus::BlockOrigin;
+use sp_runtime::{
+    generic::BlockId,
+    traits::{Block as BlockT, Header as HeaderT, NumberFor},
+};
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    task::Poll,
+    time::{Duration, Instant},
+};
+use tokio::sync::Mutex;
+
+// How many blocks we send in a single response at most.
+const MAX_BLOCKS_PER_RESPONSE: usize = 64;
+// The encoded size of a response has to stay well below the maximum notification size of the
+// validator network.
+const MAX_RESPONSE_SIZE: usize = 512 * 1024;
+// How long we wait for a response before asking another member of the committee.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+const TICK_INTERVAL: Duration = Duration::from_millis(500);
+// How many requests of a single member we serve within `RATE_LIMIT_PERIOD`.
+const MAX_REQUESTS_PER_PERIOD: usize = 20;
+const RATE_LIMIT_PERIOD: Duration = Duration::from_secs(10);
+// How many times we try to import a fetched block, and how long we wait between the attempts.
+const MAX_IMPORT_ATTEMPTS: usize = 3;
+const IMPORT_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// A request for the block with the given hash, together with its ancestors above the highest
+/// block finalized by the requester. The response goes to the member that sent the request, as
+/// authenticated by the network.
+#[derive(Clone, Debug, Encode, Decode)]
+pub struct BlockRequest<B: BlockT> {
+    hash: B::Hash,
+    finalized: NumberFor<B>,
+}
+
+/// The messages exchanged by committee members to fetch blocks referenced in proposals.
+#[derive(Clone, Debug, Encode, Decode)]
+pub enum BlockSyncData<B: BlockT> {
+    Request(BlockRequest<B>),
+    /// The requested block followed by its ancestors, in descending order.
+    Response(Vec<B>),
+}
+
+/// Requests blocks both from the committee and from the Substrate sync, which remains the fallback
+/// if no member of the committee has the block.
+#[derive(Clone)]
+pub struct CommitteeBlockRequester<B: BlockT, RB: RequestBlocks<B>> {
+    block_requester: RB,
+    fetch_requests: mpsc::UnboundedSender<(BlockHashNum<B>, Option<NodeIndex>)>,
+}
+
+impl<B: BlockT, RB: RequestBlocks<B>> CommitteeBlockRequester<B, RB> {
+    fn fetch(&self, hash: B::Hash, number: NumberFor<B>, source: Option<NodeIndex>) {
+        if self
+            .fetch_requests
+            .unbounded_send(((hash, number).into(), source))
+            .is_err()
+        {
+            debug!(target: "aleph-block-sync", "Block sync is not running, not fetching block {:?} from the committee", hash);
+        }
+        self.block_requester.request_stale_block(hash, number)
+    }
+}
+
+impl<B: BlockT, RB: RequestBlocks<B>> RequestBlocks<B> for CommitteeBlockRequester<B, RB> {
+    fn request_justification(&self, hash: &B::Hash, number: NumberFor<B>) {
+        self.block_requester.request_justification(hash, number)
+    }
+
+    fn request_stale_block(&self, hash: B::Hash, number: NumberFor<B>) {
+        self.fetch(hash, number, None)
+    }
+
+    fn request_stale_block_from(&self, hash: B::Hash, number: NumberFor<B>, member: NodeIndex) {
+        self.fetch(hash, number, Some(member))
+    }
+
+    fn clear_justification_requests(&self) {
+        self.block_requester.clear_justification_requests()
+    }
+}
+
+/// Passes fetched blocks to an import queue, which verifies and imports them like any other
+/// blocks received from the network.
+#[derive(Clone)]
+pub struct BlockImporter<B: BlockT> {
+    blocks_for_import: mpsc::UnboundedSender<Vec<B>>,
+}
+
+impl<B: BlockT> BlockImporter<B> {
+    /// Returns the importer and the task feeding the import queue, which has to be spawned for
+    /// any blocks to be imported.
+    pub fn new(import_queue: Box<dyn ImportQueue<B>>) -> (Self, impl Future<Output = ()>) {
+        let (blocks_for_import, blocks_from_sync) = mpsc::unbounded();
+        (
+            BlockImporter { blocks_for_import },
+            run_import_queue(import_queue, blocks_from_sync),
+        )
+    }
+
+    // The blocks have to be ordered so that parents come before their children.
+    fn import(&self, blocks: Vec<B>) {
+        if self.blocks_for_import.unbounded_send(blocks).is_err() {
+            warn!(target: "aleph-block-sync", "The block import task is not running, dropping fetched blocks");
+        }
+    }
+}
+
+// Whether a block that failed to import might still be imported later, e.g. once its parent is.
+fn should_retry_import(error: &BlockImportError) -> bool {
+    matches!(
+        error,
+        BlockImportError::UnknownParent
+            | BlockImportError::MissingState
+            | BlockImportError::Cancelled
+            | BlockImportError::Other(_)
+    )
+}
+
+/// Collects the results of importing fetched blocks, sorting out the ones worth importing again.
+struct RetryingLink<H> {
+    finished: Vec<H>,
+    to_retry: Vec<H>,
+}
+
+impl<H> RetryingLink<H> {
+    fn new() -> Self {
+        RetryingLink {
+            finished: Vec::new(),
+            to_retry: Vec::new(),
+        }
+    }
+}
+
+impl<B: BlockT> Link<B> for RetryingLink<B::Hash> {
+    fn blocks_processed(
+        &mut self,
+        imported: usize,
+        count: usize,
+        results: Vec<(
+            Result<BlockImportStatus<NumberFor<B>>, BlockImportError>,
+            B::Hash,
+        )>,
+    ) {
+        trace!(target: "aleph-block-sync", "Imported {:?} out of {:?} fetched blocks", imported, count);
+        for (result, hash) in results {
+            match result {
+                Ok(_) => self.finished.push(hash),
+                Err(e) if should_retry_import(&e) => {
+                    trace!(target: "aleph-block-sync", "Failed to import fetched block {:?}, will retry: {:?}", hash, e);
+                    self.to_retry.push(hash);
+                }
+                Err(e) => {
+                    debug!(target: "aleph-block-sync", "Failed to import fetched block {:?}: {:?}", hash, e);
+                    self.finished.push(hash);
+                }
+            }
+        }
+    }
+}
+
+fn incoming_block<B: BlockT>(block: B) -> IncomingBlock<B> {
+    let (header, body) = block.deconstruct();
+    IncomingBlock {
+        hash: header.hash(),
+        header: Some(header),
+        body: Some(body),
+        indexed_body: None,
+        justifications: None,
+        origin: None,
+        allow_missing_state: false,
+        skip_execution: false,
+        import_existing: false,
+        state: None,
+    }
+}
+
+// Passes the blocks to the import queue, remembering them in case the import has to be retried.
+// The blocks come with the number of previous import attempts.
+fn import_blocks<B: BlockT>(
+    import_queue: &mut dyn ImportQueue<B>,
+    in_flight: &mut HashMap<B::Hash, (B, usize)>,
+    blocks: Vec<(B, usize)>,
+) {
+    let incoming_blocks = blocks
+        .into_iter()
+        .map(|(block, attempts)| {
+            in_flight.insert(block.hash(), (block.clone(), attempts));
+            incoming_block(block)
+        })
+        .collect();
+    import_queue.import_blocks(BlockOrigin::NetworkBroadcast, incoming_blocks);
+}
+
+async fn run_import_queue<B: BlockT>(
+    mut import_queue: Box<dyn ImportQueue<B>>,
+    mut blocks_from_sync: mpsc::UnboundedReceiver<Vec<B>>,
+) {
+    let mut link = RetryingLink::new();
+    let mut in_flight = HashMap::new();
+    let mut to_retry: Vec<(B, usize)> = Vec::new();
+    let mut retry_delay = Delay::new(IMPORT_RETRY_DELAY);
+    futures::future::poll_fn(|cx| {
+        loop {
+            match blocks_from_sync.poll_next_unpin(cx) {
+                Poll::Ready(Some(blocks)) => import_blocks(
+                    &mut import_queue,
+                    &mut in_flight,
+                    blocks.into_iter().map(|block| (block, 0)).collect(),
+                ),
+                Poll::Ready(None) => return Poll::Ready(()),
+                Poll::Pending => break,
+            }
+        }
+        if retry_delay.poll_unpin(cx).is_ready() {
+            if !to_retry.is_empty() {
+                // Parents have to be imported before their children.
+                to_retry.sort_by_key(|(block, _)| *block.header().number());
+                import_blocks(&mut import_queue, &mut in_flight, std::mem::take(&mut to_retry));
+            }
+            retry_delay = Delay::new(IMPORT_RETRY_DELAY);
+            let _ = retry_delay.poll_unpin(cx);
+        }
+        import_queue.poll_actions(cx, &mut link);
+        for hash in link.finished.drain(..) {
+            in_flight.remove(&hash);
+        }
+        for hash in link.to_retry.drain(..) {
+            match in_flight.remove(&hash) {
+                Some((block, attempts)) if attempts + 1 < MAX_IMPORT_ATTEMPTS => {
+                    to_retry.push((block, attempts + 1))
+                }
+                Some(_) => {
+                    debug!(target: "aleph-block-sync", "Giving up on importing fetched block {:?}", hash)
+                }
+                None => (),
+            }
+        }
+        Poll::Pending
+    })
+    .await;
+    warn!(target: "aleph-block-sync", "Block import task finished, no more fetched blocks will be imported");
+}
+
+// Collects the block with the given hash and its ancestors above `finalized`, as long as they fit
+// in a single response. The blocks are in descending order.
+fn collect_branch<B, C>(client: &C, hash: B::Hash, finalized: NumberFor<B>) -> Vec<B>
+where
+    B: BlockT,
+    C: BlockBackend<B>,
+{
+    let mut blocks = Vec::new();
+    let mut response_size = 0;
+    let mut hash = hash;
+    while blocks.len() < MAX_BLOCKS_PER_RESPONSE {
+        let block = match client.block(&BlockId::Hash(hash)) {
+            Ok(Some(signed_block)) => signed_block.block,
+            _ => break,
+        };
+        if *block.header().number() <= finalized {
+            break;
+        }
+        response_size += block.encoded_size();
+        if response_size > MAX_RESPONSE_SIZE {
+            break;
+        }
+        hash = *block.header().parent_hash();
+        blocks.push(block);
+    }
+    blocks
+}
+
+// Returns the blocks of a response in the order they should be imported, or `None` if they do not
+// form a single branch.
+fn branch_to_import<B: BlockT>(mut blocks: Vec<B>) -> Option<Vec<B>> {
+    let linked = blocks
+        .windows(2)
+        .all(|pair| pair[0].header().parent_hash() == &pair[1].hash());
+    if !linked {
+        return None;
+    }
+    blocks.reverse();
+    Some(blocks)
+}
+
+struct PendingFetch {
+    // The member that referenced the block, so presumably has it.
+    source: Option<NodeIndex>,
+    attempts: usize,
+    last_request: Instant,
+}
+
+/// Limits how many block requests we serve to every member of the committee, as every response
+/// might be large.
+struct RequestLimiter {
+    period_start: Instant,
+    served: HashMap<NodeIndex, usize>,
+}
+
+impl RequestLimiter {
+    fn new() -> Self {
+        RequestLimiter {
+            period_start: Instant::now(),
+            served: HashMap::new(),
+        }
+    }
+
+    // Returns whether a request of the member should be served, counting it if so.
+    fn try_serve(&mut self, member: NodeIndex) -> bool {
+        if self.period_start.elapsed() >= RATE_LIMIT_PERIOD {
+            self.period_start = Instant::now();
+            self.served.clear();
+        }
+        let served = self.served.entry(member).or_insert(0);
+        if *served >= MAX_REQUESTS_PER_PERIOD {
+            return false;
+        }
+        *served += 1;
+        true
+    }
+}
+
+/// Fetches blocks referenced in proposals directly from other members of the committee, and serves
+/// such requests from them. The fetched blocks go straight to the import queue.
+pub struct BlockSync<B, C, S, R>
+where
+    B: BlockT,
+    C: HeaderBackend<B> + BlockBackend<B> + Send + Sync + 'static,
+    S: SenderComponent<BlockSyncData<B>>,
+    R: ReceiverComponent<BlockSyncData<B>>,
+{
+    client: Arc<C>,
+    importer: BlockImporter<B>,
+    node_id: NodeIndex,
+    node_count: NodeCount,
+    pending_fetches: HashMap<B::Hash, PendingFetch>,
+    fetch_requests: mpsc::UnboundedReceiver<(BlockHashNum<B>, Option<NodeIndex>)>,
+    request_limiter: RequestLimiter,
+    messages_for_network: S,
+    messages_from_network: Arc<Mutex<R>>,
+}
+
+impl<B, C, S, R> BlockSync<B, C, S, R>
+where
+    B: BlockT,
+    C: HeaderBackend<B> + BlockBackend<B> + Send + Sync + 'static,
+    S: SenderComponent<BlockSyncData<B>>,
+    R: ReceiverComponent<BlockSyncData<B>>,
+{
+    /// Returns a struct to be run and a block requester, which asks the committee for the requested
+    /// blocks in addition to passing the requests to `block_requester`.
+    pub fn new<RB, N>(
+        client: Arc<C>,
+        importer: BlockImporter<B>,
+        node_id: NodeIndex,
+        node_count: NodeCount,
+        block_requester: RB,
+        component_network: N,
+    ) -> (Self, CommitteeBlockRequester<B, RB>)
+    where
+        RB: RequestBlocks<B>,
+        N: ComponentNetwork<BlockSyncData<B>, S = S, R = R>,
+    {
+        let (fetch_requests_for_sync, fetch_requests) = mpsc::unbounded();
+        (
+            BlockSync {
+                client,
+                importer,
+                node_id,
+                node_count,
+                pending_fetches: HashMap::new(),
+                fetch_requests,
+                request_limiter: RequestLimiter::new(),
+                messages_for_network: component_network.sender().clone(),
+                messages_from_network: component_network.receiver(),
+            },
+            CommitteeBlockRequester {
+                block_requester,
+                fetch_requests: fetch_requests_for_sync,
+            },
+        )
+    }
+
+    pub async fn run(&mut self, mut exit: oneshot::Receiver<()>) {
+        let mut tick = Delay::new(TICK_INTERVAL);
+        let messages_from_network = self.messages_from_network.clone();
+        loop {
+            tokio::select! {
+                Some((block, source)) = self.fetch_requests.next() => {
+                    self.on_fetch_request(block, source);
+                }
+                Some((message, sender)) = async {
+                    let mut lock = messages_from_network.lock().await;
+                    lock.next_with_sender().await
+                } => {
+                    match (message, sender) {
+                        (BlockSyncData::Request(request), Some(sender)) => self.on_request(request, sender),
+                        (BlockSyncData::Response(blocks), Some(_)) => self.on_response(blocks),
+                        (_, None) => trace!(target: "aleph-block-sync", "Ignoring a message from a peer that is not a member of the committee"),
+                    }
+                }
+                _ = &mut tick => {
+                    self.retry_pending_fetches();
+                    tick = Delay::new(TICK_INTERVAL);
+                }
+                _ = &mut exit => {
+                    debug!(target: "aleph-block-sync", "Block sync task received exit signal. Terminating.");
+                    break;
+                }
+            }
+        }
+    }
+
+    fn on_fetch_request(&mut self, block: BlockHashNum<B>, source: Option<NodeIndex>) {
+        if self.node_count.0 <= 1
+            || self.pending_fetches.contains_key(&block.hash)
+            || self
+                .client
+                .header(BlockId::Hash(block.hash))
+                .ok()
+                .flatten()
+                .is_some()
+        {
+            return;
+        }
+        let source =
+            source.filter(|member| *member != self.node_id && member.0 < self.node_count.0);
+        self.pending_fetches.insert(
+            block.hash,
+            PendingFetch {
+                source,
+                attempts: 0,
+                last_request: Instant::now(),
+            },
+        );
+        self.send_request(block.hash, source, 0);
+    }
+
+    // The member that referenced the block is asked first. Otherwise requests for different blocks
+    // start at different members, to spread the load across the committee. Consecutive attempts go
+    // to consecutive members, never to ourselves.
+    fn member_to_ask(
+        &self,
+        hash: &B::Hash,
+        source: Option<NodeIndex>,
+        attempt: usize,
+    ) -> NodeIndex {
+        let attempt = match source {
+            Some(member) if attempt == 0 => return member,
+            Some(_) => attempt - 1,
+            None => attempt,
+        };
+        let others = self.node_count.0 - 1;
+        let start = hash.as_ref().first().copied().unwrap_or(0) as usize;
+        NodeIndex((self.node_id.0 + 1 + (start + attempt) % others) % self.node_count.0)
+    }
+
+    fn send_request(&self, hash: B::Hash, source: Option<NodeIndex>, attempt: usize) {
+        let member = self.member_to_ask(&hash, source, attempt);
+        let request = BlockRequest {
+            hash,
+            finalized: self.client.info().finalized_number,
+        };
+        trace!(target: "aleph-block-sync", "Requesting block {:?} from {:?}", hash, member);
+        if let Err(e) = self
+            .messages_for_network
+            .send(BlockSyncData::Request(request), Recipient::Node(member))
+        {
+            debug!(target: "aleph-block-sync", "Failed to request block {:?} from {:?}: {:?}", hash, member, e);
+        }
+    }
+
+    fn retry_pending_fetches(&mut self) {
+        let client = &self.client;
+        self.pending_fetches
+            .retain(|hash, _| client.header(BlockId::Hash(*hash)).ok().flatten().is_none());
+        let mut to_retry = Vec::new();
+        let others = self.node_count.0 - 1;
+        self.pending_fetches.retain(|hash, fetch| {
+            if fetch.last_request.elapsed() < REQUEST_TIMEOUT {
+                return true;
+            }
+            fetch.attempts += 1;
+            if fetch.attempts >= others + fetch.source.is_some() as usize {
+                debug!(target: "aleph-block-sync", "No member of the committee provided block {:?}, leaving it to the sync", hash);
+                return false;
+            }
+            fetch.last_request = Instant::now();
+            to_retry.push((*hash, fetch.source, fetch.attempts));
+            true
+        });
+        for (hash, source, attempt) in to_retry {
+            self.send_request(hash, source, attempt);
+        }
+    }
+
+    fn on_request(&mut self, request: BlockRequest<B>, requester: NodeIndex) {
+        if requester == self.node_id || requester.0 >= self.node_count.0 {
+            return;
+        }
+        if !self.request_limiter.try_serve(requester) {
+            debug!(target: "aleph-block-sync", "Not serving block {:?} to {:?}, it sent too many requests", request.hash, requester);
+            return;
+        }
+        let blocks = collect_branch(&*self.client, request.hash, request.finalized);
+        if blocks.is_empty() {
+            trace!(target: "aleph-block-sync", "Cannot serve block {:?} to {:?}", request.hash, requester);
+            return;
+        }
+        if let Err(e) = self
+            .messages_for_network
+            .send(BlockSyncData::Response(blocks), Recipient::Node(requester))
+        {
+            debug!(target: "aleph-block-sync", "Failed to send blocks to {:?}: {:?}", requester, e);
+        }
+    }
+
+    fn on_response(&mut self, blocks: Vec<B>) {
+        match blocks.first() {
+            Some(block) if self.pending_fetches.contains_key(&block.hash()) => (),
+            _ => return,
+        }
+        let blocks = match branch_to_import(blocks) {
+            Some(blocks) => blocks,
+            None => {
+                debug!(target: "aleph-block-sync", "Received blocks that do not form a branch, ignoring");
+                return;
+            }
+        };
+        for block in &blocks {
+            self.pending_fetches.remove(&block.hash());
+        }
+        trace!(target: "aleph-block-sync", "Importing {:?} fetched blocks", blocks.len());
+        self.importer.import(blocks);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        branch_to_import, collect_branch, RequestLimiter, MAX_REQUESTS_PER_PERIOD,
+        RATE_LIMIT_PERIOD,
+    };
+    use crate::testing::client_chain_builder::ClientChainBuilder;
+    use aleph_bft::NodeIndex;
+    use sp_runtime::traits::Block as BlockT;
+    use std::{sync::Arc, time::Instant};
+    use substrate_test_runtime_client::{
+        DefaultTestClientBuilderExt, TestClient, TestClientBuilder, TestClientBuilderExt,
+    };
+
+    fn prepare_client() -> (Arc<TestClient>, ClientChainBuilder) {
+        let client = Arc::new(TestClientBuilder::new().build());
+        let chain_builder =
+            ClientChainBuilder::new(client.clone(), Arc::new(TestClientBuilder::new().build()));
+        (client, chain_builder)
+    }
+
+    #[tokio::test]
+    async fn collects_branch_above_finalized() {
+        let (client, mut chain_builder) = prepare_client();
+        let blocks = chain_builder.initialize_single_branch_and_import(5).await;
+
+        let collected = collect_branch(&*client, blocks[4].hash(), 2);
+
+        let mut expected = blocks[2..].to_vec();
+        expected.reverse();
+        assert_eq!(collected, expected);
+    }
+
+    #[tokio::test]
+    async fn collects_nothing_for_unknown_block() {
+        let (client, mut chain_builder) = prepare_client();
+        let blocks = chain_builder.initialize_single_branch(3).await;
+
+        assert!(collect_branch(&*client, blocks[2].hash(), 0).is_empty());
+    }
+
+    #[tokio::test]
+    async fn orders_linked_response_for_import() {
+        let (_, mut chain_builder) = prepare_client();
+        let blocks = chain_builder.initialize_single_branch(4).await;
+        let mut response = blocks.clone();
+        response.reverse();
+
+        assert_eq!(branch_to_import(response), Some(blocks));
+    }
+
+    #[tokio::test]
+    async fn rejects_unlinked_response() {
+        let (_, mut chain_builder) = prepare_client();
+        let blocks = chain_builder.initialize_single_branch(4).await;
+        let response = vec![blocks[3].clone(), blocks[1].clone(), blocks[0].clone()];
+
+        assert_eq!(branch_to_import(response), None);
+    }
+
+    #[test]
+    fn limits_requests_per_member() {
+        let mut limiter = RequestLimiter::new();
+        for _ in 0..MAX_REQUESTS_PER_PERIOD {
+            assert!(limiter.try_serve(NodeIndex(1)));
+        }
+        assert!(!limiter.try_serve(NodeIndex(1)));
+        assert!(limiter.try_serve(NodeIndex(2)));
+    }
+
+    #[test]
+    fn request_limit_resets_after_period() {
+        let mut limiter = RequestLimiter::new();
+        for _ in 0..MAX_REQUESTS_PER_PERIOD {
+            assert!(limiter.try_serve(NodeIndex(1)));
+        }
+        limiter.period_start = Instant::now() - RATE_LIMIT_PERIOD;
+        assert!(limiter.try_serve(NodeIndex(1)));
+    }
+}
@@ -2,8 +2,9 @@ use crate::{
     data_io::{
         chain_info::{AuxFinalizationChainInfoProvider, CachedChainInfoProvider},
         status_provider::get_proposal_status,
-        AlephData, ChainInfoProvider,
+        AlephData, ChainInfoCacheConfig, ChainInfoProvider,
     },
+    select_chain::OrderedHead,
     BlockHashNum, SessionBoundaries,
 };
 use async_trait::async_trait;
@@ -11,7 +12,7 @@ use futures::channel::mpsc;
 use log::{debug, error, warn};
 use sc_client_api::HeaderBackend;
 use sp_runtime::traits::{Block as BlockT, NumberFor, One, Zero};
-use std::{default::Default, sync::Arc};
+use std::sync::Arc;
 
 type InterpretersChainInfoProvider<B, C> =
     CachedChainInfoProvider<B, AuxFinalizationChainInfoProvider<B, Arc<C>>>;
@@ -21,9 +22,11 @@ type InterpretersChainInfoProvider<B, C> =
 /// create multisignatures under the finalized blocks.
 pub struct OrderedDataInterpreter<B: BlockT, C: HeaderBackend<B>> {
     blocks_to_finalize_tx: mpsc::UnboundedSender<BlockHashNum<B>>,
+    ordered_head: OrderedHead<B>,
     chain_info_provider: InterpretersChainInfoProvider<B, C>,
     last_finalized_by_aleph: BlockHashNum<B>,
     session_boundaries: SessionBoundaries<B>,
+    max_branch_len: usize,
 }
 
 fn get_last_block_prev_session<B: BlockT, C: HeaderBackend<B>>(
@@ -47,21 +50,26 @@ fn get_last_block_prev_session<B: BlockT, C: HeaderBackend<B>>(
 impl<B: BlockT, C: HeaderBackend<B>> OrderedDataInterpreter<B, C> {
     pub fn new(
         blocks_to_finalize_tx: mpsc::UnboundedSender<BlockHashNum<B>>,
+        ordered_head: OrderedHead<B>,
         client: Arc<C>,
         session_boundaries: SessionBoundaries<B>,
+        max_branch_len: usize,
+        chain_info_cache_config: ChainInfoCacheConfig,
     ) -> Self {
         let last_finalized_by_aleph =
             get_last_block_prev_session(session_boundaries.clone(), client.clone());
         let chain_info_provider =
             AuxFinalizationChainInfoProvider::new(client, last_finalized_by_aleph.clone());
         let chain_info_provider =
-            CachedChainInfoProvider::new(chain_info_provider, Default::default());
+            CachedChainInfoProvider::new(chain_info_provider, chain_info_cache_config);
 
         OrderedDataInterpreter {
             blocks_to_finalize_tx,
+            ordered_head,
             chain_info_provider,
             last_finalized_by_aleph,
             session_boundaries,
+            max_branch_len,
         }
     }
 
@@ -69,8 +77,8 @@ impl<B: BlockT, C: HeaderBackend<B>> OrderedDataInterpreter<B, C> {
         match new_data {
             AlephData::Empty => None,
             AlephData::HeadProposal(unvalidated_proposal) => {
-                let proposal = if let Some(proposal) =
-                    unvalidated_proposal.validate_bounds(&self.session_boundaries)
+                let proposal = if let Some(proposal) = unvalidated_proposal
+                    .validate_bounds(&self.session_boundaries, self.max_branch_len)
                 {
                     proposal
                 } else {
@@ -108,6 +116,7 @@ impl<B: BlockT, C: HeaderBackend<B> + Send + 'static> aleph_bft::FinalizationHan
     async fn data_finalized(&mut self, data: AlephData<B>) {
         if let Some(block) = self.block_to_finalize_from_data(data) {
             self.last_finalized_by_aleph = block.clone();
+            self.ordered_head.update(block.clone());
             self.chain_info_provider
                 .inner()
                 .update_aux_finalized(block.clone());
@@ -1,5 +1,9 @@
 use crate::{
-    data_io::{proposal::UnvalidatedAlephProposal, AlephData, MAX_DATA_BRANCH_LEN},
+    data_io::{
+        fork_choice::{choose_branch, ObservedProposals},
+        proposal::UnvalidatedAlephProposal,
+        AlephData,
+    },
     metrics::Checkpoint,
     BlockHashNum, Metrics, SessionBoundaries,
 };
@@ -57,40 +61,45 @@ where
     }
 }
 
-pub fn get_proposal<B, C>(
+// Returns the hashes of the blocks from the one right above `finalized_block` up to `head`, or an
+// error if `head` does not descend from `finalized_block`.
+pub fn get_branch<B, C>(
     client: &C,
-    best_block: BlockHashNum<B>,
-    finalized_block: BlockHashNum<B>,
-) -> Result<AlephData<B>, ()>
+    head: BlockHashNum<B>,
+    finalized_block: &BlockHashNum<B>,
+) -> Result<Vec<B::Hash>, ()>
 where
     B: BlockT,
     C: HeaderBackend<B>,
 {
-    let mut curr_block = best_block;
+    let mut curr_block = head;
     let mut branch: Vec<B::Hash> = Vec::new();
     while curr_block.num > finalized_block.num {
-        if curr_block.num - finalized_block.num
-            <= <NumberFor<B>>::saturated_from(MAX_DATA_BRANCH_LEN)
-        {
-            branch.push(curr_block.hash);
-        }
-        curr_block = get_parent(client, &curr_block).expect("block of num >= 1 must have a parent")
+        branch.push(curr_block.hash);
+        curr_block = get_parent(client, &curr_block).ok_or(())?;
     }
     if curr_block.hash == finalized_block.hash {
-        let num_last = finalized_block.num + <NumberFor<B>>::saturated_from(branch.len());
         // The hashes in `branch` are ordered from top to bottom -- need to reverse.
         branch.reverse();
-        Ok(AlephData::HeadProposal(UnvalidatedAlephProposal::<B>::new(
-            branch, num_last,
-        )))
+        Ok(branch)
     } else {
-        // By backtracking from the best block we reached a block conflicting with best finalized.
-        // This is most likely a bug, or some extremely unlikely synchronization issue of the client.
-        warn!(target: "aleph-data-store", "Error computing proposal. Conflicting blocks: {:?}, finalized {:?}", curr_block, finalized_block);
         Err(())
     }
 }
 
+pub fn get_proposal<B: BlockT>(
+    mut branch: Vec<B::Hash>,
+    finalized_block: &BlockHashNum<B>,
+    max_branch_len: usize,
+) -> AlephData<B> {
+    branch.truncate(max_branch_len);
+    if branch.is_empty() {
+        return AlephData::Empty;
+    }
+    let num_last = finalized_block.num + <NumberFor<B>>::saturated_from(branch.len());
+    AlephData::HeadProposal(UnvalidatedAlephProposal::<B>::new(branch, num_last))
+}
+
 const DEFAULT_REFRESH_INTERVAL: Duration = Duration::from_millis(100);
 
 pub struct ChainTrackerConfig {
@@ -107,11 +116,13 @@ impl Default for ChainTrackerConfig {
 
 #[derive(PartialEq, Eq, Clone, Debug)]
 struct ChainInfo<B: BlockT> {
-    best_block_in_session: BlockHashNum<B>,
+    fork_heads: Vec<BlockHashNum<B>>,
+    observed_heads: Vec<BlockHashNum<B>>,
     highest_finalized: BlockHashNum<B>,
 }
 
-/// ChainTracker keeps track of the best_block in a given session and allows to generate `AlephData`.
+/// ChainTracker keeps track of the best_block and competing forks in a given session and allows to
+/// generate `AlephData`, taking into account the proposals of other nodes.
 /// Internally it frequently updates a `data_to_propose` field that is shared with a `DataProvider`, which
 /// in turn is a tiny wrapper around this single shared resource that outputs `data_to_propose` whenever
 /// `get_data` is called.
@@ -125,6 +136,8 @@ where
     client: Arc<C>,
     data_to_propose: Arc<Mutex<AlephData<B>>>,
     session_boundaries: SessionBoundaries<B>,
+    max_branch_len: usize,
+    observed_proposals: ObservedProposals<B>,
     prev_chain_info: Option<ChainInfo<B>>,
     config: ChainTrackerConfig,
 }
@@ -139,6 +152,8 @@ where
         select_chain: SC,
         client: Arc<C>,
         session_boundaries: SessionBoundaries<B>,
+        max_branch_len: usize,
+        observed_proposals: ObservedProposals<B>,
         config: ChainTrackerConfig,
         metrics: Option<Metrics<<B::Header as HeaderT>::Hash>>,
     ) -> (Self, impl aleph_bft::DataProvider<AlephData<B>>) {
@@ -149,6 +164,8 @@ where
                 client,
                 data_to_propose: data_to_propose.clone(),
                 session_boundaries,
+                max_branch_len,
+                observed_proposals,
                 prev_chain_info: None,
                 config,
             },
@@ -159,11 +176,58 @@ where
         )
     }
 
+    // Returns the heads of all the known forks reduced to the session, starting with the best block.
+    async fn get_fork_heads_in_session(
+        &self,
+        best_block_in_session: &BlockHashNum<B>,
+    ) -> Vec<BlockHashNum<B>> {
+        let mut fork_heads = vec![best_block_in_session.clone()];
+        let leaves = match self.select_chain.leaves().await {
+            Ok(leaves) => leaves,
+            Err(e) => {
+                warn!(target: "aleph-data-store", "Failed to get the chain leaves: {:?}", e);
+                return fork_heads;
+            }
+        };
+        for leaf in leaves {
+            let header = match self.client.header(BlockId::Hash(leaf)) {
+                Ok(Some(header)) => header,
+                _ => continue,
+            };
+            if header.number() < &self.session_boundaries.first_block() {
+                continue;
+            }
+            let header = if header.number() > &self.session_boundaries.last_block() {
+                reduce_header_to_num(&*self.client, header, self.session_boundaries.last_block())
+            } else {
+                header
+            };
+            let head = (header.hash(), *header.number()).into();
+            if !fork_heads.contains(&head) {
+                fork_heads.push(head);
+            }
+        }
+        fork_heads
+    }
+
+    // Returns the heads of the proposals of other nodes that we know of and that are above `finalized_block`.
+    fn get_observed_heads(&self, finalized_block: &BlockHashNum<B>) -> Vec<BlockHashNum<B>> {
+        self.observed_proposals
+            .heads()
+            .into_iter()
+            .filter(|head| {
+                head.num > finalized_block.num
+                    && head.num <= self.session_boundaries.last_block()
+                    && matches!(self.client.header(BlockId::Hash(head.hash)), Ok(Some(_)))
+            })
+            .collect()
+    }
+
     async fn update_data(&mut self, best_block_in_session: &BlockHashNum<B>) {
-        // We use best_block_in_session argument and the highest_finalized block from the client and compute
-        // the corresponding `AlephData<B>` in `data_to_propose` for AlephBFT. To not recompute this many
-        // times we remember these "inputs" in `prev_chain_info` and upon match we leave the old value
-        // of `data_to_propose` unaffected.
+        // We use the known forks in the session, the proposals of others and the highest_finalized block
+        // from the client and compute the corresponding `AlephData<B>` in `data_to_propose` for AlephBFT.
+        // To not recompute this many times we remember these "inputs" in `prev_chain_info` and upon
+        // match we leave the old value of `data_to_propose` unaffected.
 
         let client_info = self.client.info();
         let finalized_block: BlockHashNum<B> =
@@ -176,39 +240,59 @@ where
             return;
         }
 
-        if let Some(prev) = &self.prev_chain_info {
-            if prev.best_block_in_session == *best_block_in_session
-                && prev.highest_finalized == finalized_block
-            {
-                // This is exactly the same state that we processed last time in update_data.
-                // No point in recomputing.
-                return;
-            }
-        }
-        // Update the info for the next call of update data.
-        self.prev_chain_info = Some(ChainInfo {
-            best_block_in_session: best_block_in_session.clone(),
+        let fork_heads = self.get_fork_heads_in_session(best_block_in_session).await;
+        let observed_heads = self.get_observed_heads(&finalized_block);
+        let chain_info = ChainInfo {
+            fork_heads,
+            observed_heads,
             highest_finalized: finalized_block.clone(),
-        });
-
-        if best_block_in_session.num == finalized_block.num {
-            // We don't have anything to propose, we go ahead with an empty proposal.
-            *self.data_to_propose.lock().await = AlephData::Empty;
+        };
+        if self.prev_chain_info.as_ref() == Some(&chain_info) {
+            // This is exactly the same state that we processed last time in update_data.
+            // No point in recomputing.
             return;
         }
+        // Update the info for the next call of update data.
+        self.prev_chain_info = Some(chain_info.clone());
+
         if best_block_in_session.num < finalized_block.num {
             // Because of the client synchronization, in extremely rare cases this could happen.
             warn!(target: "aleph-data-store", "Error updating data. best_block {:?} is lower than finalized {:?}.", best_block_in_session, finalized_block);
             return;
         }
 
-        if let Ok(proposal) = get_proposal(
+        let best_branch = match get_branch(
             &*self.client,
             best_block_in_session.clone(),
-            finalized_block,
+            &finalized_block,
         ) {
-            *self.data_to_propose.lock().await = proposal;
-        }
+            Ok(branch) => branch,
+            Err(()) => {
+                // By backtracking from the best block we reached a block conflicting with best finalized.
+                // This is most likely a bug, or some extremely unlikely synchronization issue of the client.
+                warn!(target: "aleph-data-store", "Error computing proposal. Best block {:?} does not descend from finalized {:?}", best_block_in_session, finalized_block);
+                return;
+            }
+        };
+        // Forks and proposals that do not descend from the finalized block are dead, we skip them.
+        let forks: Vec<_> = std::iter::once(best_branch)
+            .chain(
+                chain_info
+                    .fork_heads
+                    .into_iter()
+                    .skip(1)
+                    .filter_map(|head| get_branch(&*self.client, head, &finalized_block).ok()),
+            )
+            .collect();
+        let proposals: Vec<_> = chain_info
+            .observed_heads
+            .into_iter()
+            .filter_map(|head| get_branch(&*self.client, head, &finalized_block).ok())
+            .collect();
+
+        let branch = choose_branch(&forks, &proposals, self.observed_proposals.node_count());
+        *self.data_to_propose.lock().await =
+            get_proposal(branch, &finalized_block, self.max_branch_len);
     }
 
     async fn get_best_header(&self) -> B::Header {
@@ -299,10 +383,13 @@ struct DataProvider<B: BlockT> {
 // 1. Let `best_block_in_session` be the highest ancestor of the current local view of `best_block` that
 //    belongs to session `k`. So either the global `best_block`, or `best_block` but reduced to the last
 //    block of session `k` by traversing parents down.
-// 2. If the node does not know of any block in session `k` or if `best_block` is equal to the last finalized block
-//    then the node proposes `Empty`, otherwise the node proposes a branch extending from one block above
-//    last finalized till `best_block` with the restriction that the branch must be truncated to length
-//    at most MAX_DATA_BRANCH_LEN.
+// 2. The heads of all the other known forks are reduced to session `k` in the same way. Among the forks
+//    descending from the last finalized block the node chooses the one containing the heads of most of the
+//    recently seen proposals of other nodes, preferring longer forks and then `best_block_in_session`. If
+//    some of the seen proposals conflict with the chosen fork, it is cut to the part common with all of them.
+// 3. If the node does not know of any block in session `k` or if the chosen branch is empty then the node
+//    proposes `Empty`, otherwise the node proposes the chosen branch extending from one block above last
+//    finalized, with the restriction that the branch must be truncated to length at most the maximum branch length of session `k`.
 #[async_trait]
 impl<B: BlockT> aleph_bft::DataProvider<AlephData<B>> for DataProvider<B> {
     async fn get_data(&mut self) -> AlephData<B> {
@@ -327,12 +414,14 @@ mod tests {
     use crate::{
         data_io::{
             data_provider::{ChainTracker, ChainTrackerConfig},
-            AlephData, MAX_DATA_BRANCH_LEN,
+            AlephData, ObservedProposals, MAX_DATA_BRANCH_LEN,
         },
         testing::{client_chain_builder::ClientChainBuilder, mocks::aleph_data_from_blocks},
-        SessionBoundaries, SessionId, SessionPeriod,
+        BlockHashNum, SessionBoundaries, SessionId, SessionPeriod,
     };
+    use aleph_bft::{NodeCount, NodeIndex};
     use futures::channel::oneshot;
+    use sp_runtime::traits::{Block as BlockT, Header as HeaderT};
     use std::{future::Future, sync::Arc, time::Duration};
     use substrate_test_runtime_client::{
         runtime::Block, DefaultTestClientBuilderExt, TestClientBuilder, TestClientBuilderExt,
@@ -349,20 +438,30 @@ mod tests {
         oneshot::Sender<()>,
         ClientChainBuilder,
         impl aleph_bft::DataProvider<AlephData<Block>>,
+        ObservedProposals<Block>,
     ) {
         let (client, select_chain) = TestClientBuilder::new().build_with_longest_chain();
         let client = Arc::new(client);
 
         let chain_builder =
             ClientChainBuilder::new(client.clone(), Arc::new(TestClientBuilder::new().build()));
-        let session_boundaries = SessionBoundaries::new(SessionId(0), SessionPeriod(SESSION_LEN));
+        let session_boundaries =
+            SessionBoundaries::new(SessionId(0), &SessionPeriod(SESSION_LEN).into());
 
         let config = ChainTrackerConfig {
             refresh_interval: REFRESH_INTERVAL,
         };
 
-        let (chain_tracker, data_provider) =
-            ChainTracker::new(select_chain, client, session_boundaries, config, None);
+        let observed_proposals = ObservedProposals::new(NodeCount(4));
+        let (chain_tracker, data_provider) = ChainTracker::new(
+            select_chain,
+            client,
+            session_boundaries,
+            MAX_DATA_BRANCH_LEN,
+            observed_proposals.clone(),
+            config,
+            None,
+        );
 
         let (exit_chain_tracker_tx, exit_chain_tracker_rx) = oneshot::channel();
         (
@@ -372,6 +471,7 @@ mod tests {
             exit_chain_tracker_tx,
             chain_builder,
             data_provider,
+            observed_proposals,
         )
     }
 
@@ -383,12 +483,17 @@ mod tests {
     async fn run_test<F, S>(scenario: S)
     where
         F: Future,
-        S: FnOnce(ClientChainBuilder, Box<dyn aleph_bft::DataProvider<AlephData<Block>>>) -> F,
+        S: FnOnce(
+            ClientChainBuilder,
+            Box<dyn aleph_bft::DataProvider<AlephData<Block>>>,
+            ObservedProposals<Block>,
+        ) -> F,
     {
-        let (task_handle, exit, chain_builder, data_provider) = prepare_chain_tracker_test();
+        let (task_handle, exit, chain_builder, data_provider, observed_proposals) =
+            prepare_chain_tracker_test();
         let chain_tracker_handle = tokio::spawn(task_handle);
 
-        scenario(chain_builder, Box::new(data_provider)).await;
+        scenario(chain_builder, Box::new(data_provider), observed_proposals).await;
 
         exit.send(()).unwrap();
         chain_tracker_handle
@@ -398,7 +503,7 @@ mod tests {
 
     #[tokio::test(flavor = "multi_thread")]
     async fn proposes_empty_and_nonempty_when_expected() {
-        run_test(|mut chain_builder, mut data_provider| async move {
+        run_test(|mut chain_builder, mut data_provider, _| async move {
             sleep_enough().await;
 
             assert_eq!(
@@ -422,7 +527,7 @@ mod tests {
 
     #[tokio::test(flavor = "multi_thread")]
     async fn proposal_changes_with_finalization() {
-        run_test(|mut chain_builder, mut data_provider| async move {
+        run_test(|mut chain_builder, mut data_provider, _| async move {
             let blocks = chain_builder
                 .initialize_single_branch_and_import(3 * MAX_DATA_BRANCH_LEN)
                 .await;
@@ -447,7 +552,7 @@ mod tests {
 
     #[tokio::test(flavor = "multi_thread")]
     async fn returns_empty_proposal_above_session_end() {
-        run_test(|mut chain_builder, mut data_provider| async move {
+        run_test(|mut chain_builder, mut data_provider, _| async move {
             let blocks = chain_builder
                 .initialize_single_branch_and_import(
                     (SESSION_LEN as usize) + 3 * MAX_DATA_BRANCH_LEN,
@@ -469,4 +574,89 @@ mod tests {
         })
         .await;
     }
+
+    fn head_of(blocks: &[Block]) -> BlockHashNum<Block> {
+        let header = blocks.last().unwrap().header();
+        (header.hash(), *header.number()).into()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn proposes_fork_supported_by_others() {
+        run_test(
+            |mut chain_builder, mut data_provider, observed_proposals| async move {
+                let common = chain_builder.initialize_single_branch_and_import(2).await;
+                let longer = chain_builder
+                    .build_and_import_branch_above(&common[1].header.hash(), 3)
+                    .await;
+                let supported = chain_builder
+                    .build_and_import_branch_above(&common[1].header.hash(), 2)
+                    .await;
+                sleep_enough().await;
+                assert_eq!(
+                    data_provider.get_data().await,
+                    aleph_data_from_blocks([common.clone(), longer].concat())
+                );
+
+                observed_proposals.observe(NodeIndex(1), head_of(&supported));
+                sleep_enough().await;
+                assert_eq!(
+                    data_provider.get_data().await,
+                    aleph_data_from_blocks([common, supported].concat())
+                );
+            },
+        )
+        .await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn single_conflicting_proposal_does_not_truncate() {
+        run_test(
+            |mut chain_builder, mut data_provider, observed_proposals| async move {
+                let common = chain_builder.initialize_single_branch_and_import(2).await;
+                let longer = chain_builder
+                    .build_and_import_branch_above(&common[1].header.hash(), 3)
+                    .await;
+                let shorter = chain_builder
+                    .build_and_import_branch_above(&common[1].header.hash(), 2)
+                    .await;
+
+                observed_proposals.observe(NodeIndex(1), head_of(&longer));
+                observed_proposals.observe(NodeIndex(2), head_of(&shorter));
+                sleep_enough().await;
+                assert_eq!(
+                    data_provider.get_data().await,
+                    aleph_data_from_blocks([common, longer].concat())
+                );
+            },
+        )
+        .await;
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn proposes_common_prefix_of_conflicting_forks() {
+        run_test(
+            |mut chain_builder, mut data_provider, observed_proposals| async move {
+                let common = chain_builder.initialize_single_branch_and_import(2).await;
+                let longer = chain_builder
+                    .build_and_import_branch_above(&common[1].header.hash(), 3)
+                    .await;
+                let shorter = chain_builder
+                    .build_and_import_branch_above(&common[1].header.hash(), 2)
+                    .await;
+                let other = chain_builder
+                    .build_and_import_branch_above(&common[1].header.hash(), 2)
+                    .await;
+
+                observed_proposals.observe(NodeIndex(1), head_of(&longer));
+                observed_proposals.observe(NodeIndex(2), head_of(&shorter));
+                observed_proposals.observe(NodeIndex(3), head_of(&other));
+                sleep_enough().await;
+                assert_eq!(
+                    data_provider.get_data().await,
+                    aleph_data_from_blocks(common)
+                );
+            },
+        )
+        .await;
+    }
 }
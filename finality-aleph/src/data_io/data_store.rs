@@ -1,12 +1,14 @@
 use crate::{
     data_io::{
         chain_info::{CachedChainInfoProvider, ChainInfoProvider},
+        fork_choice::ObservedProposals,
+        monitor::{DataStoreMonitor, DataStoreStatus, PendingProposalReport},
         proposal::{AlephProposal, ProposalStatus},
-        status_provider::get_proposal_status,
-        AlephNetworkMessage,
+        status_provider::{get_proposal_status, is_hopeless_fork},
+        AlephNetworkMessage, ChainInfoCacheConfig,
     },
     network::{ComponentNetwork, DataNetwork, ReceiverComponent, RequestBlocks, SimpleNetwork},
-    BlockHashNum, SessionBoundaries,
+    BlockHashNum, NodeIndex, SessionBoundaries,
 };
 use futures::{
     channel::{
@@ -19,7 +21,10 @@ use futures_timer::Delay;
 use log::{debug, error, info, trace, warn};
 use lru::LruCache;
 use sc_client_api::{BlockchainEvents, HeaderBackend};
-use sp_runtime::traits::{Block as BlockT, Header as HeaderT, NumberFor, One};
+use sp_runtime::{
+    traits::{Block as BlockT, Header as HeaderT, NumberFor, One},
+    SaturatedConversion,
+};
 use std::{
     collections::{hash_map::Entry::Occupied, BTreeMap, HashMap, HashSet},
     default::Default,
@@ -72,14 +77,17 @@ pub struct PendingProposalInfo<B: BlockT> {
     messages: HashSet<MessageId>,
     // When was the first message containing this data item encountered.
     first_occurrence: time::SystemTime,
+    // The committee member that sent us the first message containing this data item, if known.
+    source: Option<NodeIndex>,
     status: ProposalStatus<B>,
 }
 
 impl<B: BlockT> PendingProposalInfo<B> {
-    fn new(status: ProposalStatus<B>) -> Self {
+    fn new(status: ProposalStatus<B>, source: Option<NodeIndex>) -> Self {
         PendingProposalInfo {
             messages: HashSet::new(),
             first_occurrence: time::SystemTime::now(),
+            source,
             status,
         }
     }
@@ -101,6 +109,7 @@ impl<B: BlockT, M: AlephNetworkMessage<B>> PendingMessageInfo<B, M> {
     }
 }
 
+#[derive(Clone, Debug)]
 pub struct DataStoreConfig {
     pub max_triggers_pending: usize,
     pub max_proposals_pending: usize,
@@ -110,6 +119,7 @@ pub struct DataStoreConfig {
     // Specifies how much time must pass from receiving a given proposal for the first time, till we
     // perform a request for either a block or a justification required to let this proposal through.
     pub request_block_after: Duration,
+    pub chain_info_cache: ChainInfoCacheConfig,
 }
 
 impl Default for DataStoreConfig {
@@ -121,6 +131,7 @@ impl Default for DataStoreConfig {
             available_proposals_cache_capacity: 8000,
             periodic_maintenance_interval: Duration::from_secs(25),
             request_block_after: Duration::from_secs(20),
+            chain_info_cache: ChainInfoCacheConfig::default(),
         }
     }
 }
@@ -186,9 +197,12 @@ where
     num_triggers_registered_since_last_pruning: usize,
     highest_finalized_num: NumberFor<B>,
     session_boundaries: SessionBoundaries<B>,
+    max_branch_len: usize,
+    observed_proposals: ObservedProposals<B>,
     client: Arc<C>,
     block_requester: RB,
     config: DataStoreConfig,
+    monitor: DataStoreMonitor,
     messages_from_network: Arc<Mutex<R>>,
     messages_for_aleph: UnboundedSender<Message>,
 }
@@ -203,18 +217,23 @@ where
     R: ReceiverComponent<Message>,
 {
     /// Returns a struct to be run and a network that outputs messages filtered as appropriate
+    #[allow(clippy::too_many_arguments)]
     pub fn new<N: ComponentNetwork<Message, R = R>>(
         session_boundaries: SessionBoundaries<B>,
+        max_branch_len: usize,
+        observed_proposals: ObservedProposals<B>,
         client: Arc<C>,
         block_requester: RB,
         config: DataStoreConfig,
+        monitor: DataStoreMonitor,
         component_network: N,
     ) -> (Self, impl DataNetwork<Message>) {
         let (messages_for_aleph, messages_from_data_store) = mpsc::unbounded();
         let messages_to_network = component_network.sender().clone();
         let messages_from_network = component_network.receiver();
         let status = client.info();
-        let chain_info_provider = CachedChainInfoProvider::new(client.clone(), Default::default());
+        let chain_info_provider =
+            CachedChainInfoProvider::new(client.clone(), config.chain_info_cache.clone());
 
         let highest_finalized_num = status.finalized_number;
         (
@@ -228,9 +247,12 @@ where
                 num_triggers_registered_since_last_pruning: 0,
                 highest_finalized_num,
                 session_boundaries,
+                max_branch_len,
+                observed_proposals,
                 client,
                 block_requester,
                 config,
+                monitor,
                 messages_from_network,
                 messages_for_aleph,
             },
@@ -246,12 +268,12 @@ where
             self.prune_pending_messages();
             self.prune_triggers();
             tokio::select! {
-                Some(message) = async {
+                Some((message, sender)) = async {
                     let mut lock = self.messages_from_network.lock().await;
-                    lock.next().await
+                    lock.next_with_sender().await
                 } => {
-                    trace!(target: "aleph-data-store", "Received message at Data Store {:?}", message);
-                    self.on_message_received(message);
+                    trace!(target: "aleph-data-store", "Received message at Data Store {:?} from {:?}", message, sender);
+                    self.on_message_received(message, sender);
                 }
                 Some(block) = &mut import_stream.next() => {
                     trace!(target: "aleph-data-store", "Block import notification at Data Store for block {:?}", block);
@@ -286,7 +308,7 @@ where
         let proposals_with_timestamps: Vec<_> = self
             .pending_proposals
             .iter()
-            .map(|(proposal, info)| (proposal.clone(), info.first_occurrence))
+            .map(|(proposal, info)| (proposal.clone(), info.first_occurrence, info.source))
             .collect();
         match proposals_with_timestamps.len() {
             0 => {
@@ -301,7 +323,7 @@ where
         }
 
         let now = time::SystemTime::now();
-        for (proposal, first_occurrence) in proposals_with_timestamps {
+        for (proposal, first_occurrence, source) in proposals_with_timestamps {
             if self.bump_proposal(&proposal) {
                 continue;
             }
@@ -314,8 +336,15 @@ where
             let block = proposal.top_block();
             if !self.chain_info_provider.is_block_imported(&block) {
                 debug!(target: "aleph-data-store", "Requesting a stale block {:?} after it has been missing for {:?} secs.", block, time_waiting.as_secs());
-                self.block_requester
-                    .request_stale_block(block.hash, block.num);
+                match source {
+                    Some(member) => self
+                        .block_requester
+                        .request_stale_block_from(block.hash, block.num, member),
+                    None => self
+                        .block_requester
+                        .request_stale_block(block.hash, block.num),
+                }
+                self.monitor.report_block_request();
                 continue;
             }
             // The top block (thus the whole branch, in the honest case) has been imported. What's holding us
@@ -343,8 +372,40 @@ where
                         after it has been missing for {:?} secs.", parent_num, parent_hash, time_waiting.as_secs());
                 self.block_requester
                     .request_justification(&parent_hash, parent_num);
+                self.monitor.report_justification_request();
             }
         }
+        self.report_status();
+    }
+
+    fn report_status(&self) {
+        let now = time::SystemTime::now();
+        let mut pending_proposals: Vec<_> = self
+            .pending_proposals
+            .iter()
+            .map(|(proposal, info)| {
+                let top_block = proposal.top_block();
+                PendingProposalReport {
+                    top_block_hash: format!("{:?}", top_block.hash),
+                    top_block_number: top_block.num.saturated_into(),
+                    branch_len: proposal.len(),
+                    status: format!("{:?}", info.status),
+                    pending_for_ms: now
+                        .duration_since(info.first_occurrence)
+                        .unwrap_or_default()
+                        .as_millis() as u64,
+                    waiting_messages: info.messages.len(),
+                }
+            })
+            .collect();
+        pending_proposals.sort_by_key(|proposal| proposal.top_block_number);
+        self.monitor.report_status(DataStoreStatus {
+            session_first_block: self.session_boundaries.first_block().saturated_into(),
+            session_last_block: self.session_boundaries.last_block().saturated_into(),
+            highest_finalized: self.highest_finalized_num.saturated_into(),
+            pending_messages: self.pending_messages.len(),
+            pending_proposals,
+        });
     }
 
     fn register_block_import_trigger(
@@ -471,6 +532,11 @@ where
         let status = get_proposal_status(&mut self.chain_info_provider, proposal, old_status);
         match status {
             ProposalStatus::Finalize(_) | ProposalStatus::Ignore => {
+                if matches!(status, ProposalStatus::Ignore)
+                    && is_hopeless_fork(&mut self.chain_info_provider, proposal)
+                {
+                    self.monitor.report_hopeless_fork();
+                }
                 // We can cache only if the proposal is available. If it is pending, its
                 // status might change and we should not recover it from the cache.
                 self.available_proposals_cache
@@ -493,6 +559,7 @@ where
         proposal: &AlephProposal<B>,
         message_info: &mut PendingMessageInfo<B, Message>,
         id: MessageId,
+        source: Option<NodeIndex>,
     ) {
         if !self.pending_proposals.contains_key(proposal) {
             use crate::data_io::proposal::{PendingProposalStatus::*, ProposalStatus::*};
@@ -500,20 +567,20 @@ where
             match &status {
                 Pending(PendingTopBlock) => {
                     self.pending_proposals
-                        .insert(proposal.clone(), PendingProposalInfo::new(status));
+                        .insert(proposal.clone(), PendingProposalInfo::new(status, source));
                     self.register_block_import_trigger(proposal, &proposal.top_block());
                     self.register_next_finality_trigger(proposal);
                 }
                 Pending(TopBlockImportedButIncorrectBranch) => {
                     self.pending_proposals
-                        .insert(proposal.clone(), PendingProposalInfo::new(status));
+                        .insert(proposal.clone(), PendingProposalInfo::new(status, source));
                     // The only way this might ever get through is as a hopeless fork. So the only event that might
                     // change the status of this proposal is a finalization event, hence we register a trigger.
                     self.register_next_finality_trigger(proposal);
                 }
                 Pending(TopBlockImportedButNotFinalizedAncestor) => {
                     self.pending_proposals
-                        .insert(proposal.clone(), PendingProposalInfo::new(status));
+                        .insert(proposal.clone(), PendingProposalInfo::new(status, source));
 
                     self.register_next_finality_trigger(proposal);
                 }
@@ -627,16 +694,20 @@ where
         }
     }
 
-    fn on_message_received(&mut self, message: Message) {
+    fn on_message_received(&mut self, message: Message, sender: Option<NodeIndex>) {
         let mut proposals = Vec::new();
         for data in message.included_data() {
             use crate::data_io::AlephData::*;
             match data {
                 Empty => {}
                 HeadProposal(unvalidated_proposal) => {
-                    if let Some(proposal) =
-                        unvalidated_proposal.validate_bounds(&self.session_boundaries)
+                    if let Some(proposal) = unvalidated_proposal
+                        .validate_bounds(&self.session_boundaries, self.max_branch_len)
                     {
+                        if let Some(sender) = sender {
+                            self.observed_proposals
+                                .observe(sender, proposal.top_block());
+                        }
                         proposals.push(proposal);
                     } else {
                         warn!(target: "aleph-data-store", "Message {:?} dropped as it contains \
@@ -651,7 +722,7 @@ where
         let message_id = self.assign_fresh_message_id();
 
         for proposal in proposals {
-            self.add_message_proposal_dependency(&proposal, &mut message_info, message_id);
+            self.add_message_proposal_dependency(&proposal, &mut message_info, message_id, sender);
         }
         if message_info.pending_proposals.is_empty() {
             self.on_message_dependencies_resolved(message);
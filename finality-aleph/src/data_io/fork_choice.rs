@@ -0,0 +1,174 @@
+use crate::BlockHashNum;
+use aleph_bft::{NodeCount, NodeIndex};
+use parking_lot::Mutex;
+use sp_runtime::traits::Block as BlockT;
+use std::{collections::HashMap, sync::Arc};
+
+/// The top blocks of the most recent proposals received from other members of the committee, at
+/// most one per member. Filled by the `DataStore` and read by the `ChainTracker`, which uses them
+/// to choose what to propose when the chain forks. A proposal is attributed to the member that
+/// sent it to us, as authenticated by the network. The proposals are not validated in any way, so
+/// they are only used as hints.
+#[derive(Clone)]
+pub struct ObservedProposals<B: BlockT> {
+    node_count: NodeCount,
+    heads: Arc<Mutex<HashMap<NodeIndex, BlockHashNum<B>>>>,
+}
+
+impl<B: BlockT> ObservedProposals<B> {
+    pub fn new(node_count: NodeCount) -> Self {
+        ObservedProposals {
+            node_count,
+            heads: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Records the head of a proposal of the member, replacing its previous one.
+    pub fn observe(&self, member: NodeIndex, head: BlockHashNum<B>) {
+        if member.0 >= self.node_count.0 {
+            return;
+        }
+        self.heads.lock().insert(member, head);
+    }
+
+    pub fn heads(&self) -> Vec<BlockHashNum<B>> {
+        self.heads.lock().values().cloned().collect()
+    }
+
+    pub fn node_count(&self) -> NodeCount {
+        self.node_count
+    }
+}
+
+fn contains_head<H: PartialEq>(fork: &[H], proposal: &[H]) -> bool {
+    match proposal.last() {
+        Some(head) => fork.get(proposal.len() - 1) == Some(head),
+        None => false,
+    }
+}
+
+fn common_prefix_len<H: PartialEq>(a: &[H], b: &[H]) -> usize {
+    a.iter().zip(b.iter()).take_while(|(x, y)| x == y).count()
+}
+
+/// Chooses the branch to propose. All branches start right above the highest finalized block and
+/// `forks` are the branches leading to the known fork heads, starting with the best one.
+/// `proposals` are the branches proposed by other members of the committee of size `node_count`
+/// that we could follow locally, at most one per member.
+///
+/// We pick the fork containing the heads of the most proposals, preferring longer forks and then
+/// the best one in case of a tie. If more than a third of the committee proposes something that
+/// conflicts with the chosen fork, we only propose the part of it that is common with all but a
+/// third of them, as this is the part most likely to be agreed on. Fewer conflicting proposals might
+/// all come from malicious members, so they do not change what we propose.
+pub fn choose_branch<H: PartialEq + Clone>(
+    forks: &[Vec<H>],
+    proposals: &[Vec<H>],
+    node_count: NodeCount,
+) -> Vec<H> {
+    let chosen = match forks
+        .iter()
+        .enumerate()
+        .max_by_key(|(index, fork)| {
+            let support = proposals
+                .iter()
+                .filter(|proposal| contains_head(fork, proposal))
+                .count();
+            (support, fork.len(), std::cmp::Reverse(*index))
+        })
+        .map(|(_, fork)| fork)
+    {
+        Some(fork) => fork,
+        None => return Vec::new(),
+    };
+    let mut agreed_lens: Vec<_> = proposals
+        .iter()
+        .filter(|proposal| !proposal.is_empty() && !contains_head(chosen, proposal))
+        .map(|proposal| common_prefix_len(chosen, proposal))
+        .collect();
+    agreed_lens.sort_unstable();
+    // Truncating to this length leaves at most a third of the committee disagreeing with us.
+    match agreed_lens.get(node_count.0 / 3) {
+        // Not enough members disagree, or they do not agree even on the first block, so there is
+        // nothing to gain by waiting.
+        Some(0) | None => chosen.clone(),
+        Some(len) => chosen[..*len].to_vec(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{choose_branch, ObservedProposals};
+    use crate::BlockHashNum;
+    use aleph_bft::{NodeCount, NodeIndex};
+    use sp_core::hash::H256;
+    use substrate_test_runtime_client::runtime::Block;
+
+    const NODE_COUNT: NodeCount = NodeCount(4);
+
+    #[test]
+    fn without_proposals_chooses_longest_fork() {
+        let forks = vec![vec![1, 2], vec![1, 3, 4]];
+        assert_eq!(choose_branch(&forks, &[], NODE_COUNT), vec![1, 3, 4]);
+        let forks = vec![vec![1, 2], vec![1, 3]];
+        assert_eq!(choose_branch(&forks, &[], NODE_COUNT), vec![1, 2]);
+        assert_eq!(
+            choose_branch::<u32>(&[], &[], NODE_COUNT),
+            Vec::<u32>::new()
+        );
+    }
+
+    #[test]
+    fn chooses_fork_with_most_support() {
+        let forks = vec![vec![1, 2, 5], vec![1, 3]];
+        let proposals = vec![vec![1, 3], vec![1, 3], vec![1, 2]];
+        assert_eq!(choose_branch(&forks, &proposals, NODE_COUNT), vec![1, 3]);
+        let proposals = vec![vec![1, 3], vec![1]];
+        assert_eq!(choose_branch(&forks, &proposals, NODE_COUNT), vec![1, 3]);
+    }
+
+    #[test]
+    fn single_dissenting_proposal_does_not_truncate() {
+        let forks = vec![vec![1, 2, 3]];
+        let proposals = vec![vec![1, 2, 3], vec![1, 2, 3], vec![1, 4]];
+        assert_eq!(choose_branch(&forks, &proposals, NODE_COUNT), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn truncates_when_more_than_third_disagrees() {
+        let forks = vec![vec![1, 2, 3, 4]];
+        let proposals = vec![vec![1, 2, 3, 7], vec![1, 2, 5], vec![1, 6]];
+        assert_eq!(choose_branch(&forks, &proposals, NODE_COUNT), vec![1, 2]);
+    }
+
+    #[test]
+    fn shorter_proposals_on_chosen_fork_do_not_truncate() {
+        let forks = vec![vec![1, 2, 3, 4]];
+        let proposals = vec![vec![1], vec![1, 2]];
+        assert_eq!(
+            choose_branch(&forks, &proposals, NODE_COUNT),
+            vec![1, 2, 3, 4]
+        );
+    }
+
+    #[test]
+    fn conflicts_at_first_block_do_not_truncate() {
+        let forks = vec![vec![1, 2], vec![3]];
+        let proposals = vec![vec![1, 2], vec![1, 2], vec![3], vec![3]];
+        assert_eq!(choose_branch(&forks, &proposals, NODE_COUNT), vec![1, 2]);
+    }
+
+    #[test]
+    fn keeps_latest_proposal_per_member() {
+        let observed_proposals = ObservedProposals::<Block>::new(NODE_COUNT);
+        let head = |num: u64| -> BlockHashNum<Block> { (H256::repeat_byte(num as u8), num).into() };
+        observed_proposals.observe(NodeIndex(1), head(1));
+        observed_proposals.observe(NodeIndex(1), head(2));
+        observed_proposals.observe(NodeIndex(2), head(3));
+        observed_proposals.observe(NodeIndex(4), head(4));
+
+        let mut heads = observed_proposals.heads();
+        heads.sort_by_key(|head| head.num);
+        assert_eq!(heads, vec![head(2), head(3)]);
+    }
+}
@@ -5,21 +5,30 @@ use std::{
     hash::{Hash, Hasher},
 };
 
+mod block_sync;
 mod chain_info;
 mod data_interpreter;
 mod data_provider;
 mod data_store;
+mod fork_choice;
+mod monitor;
 mod proposal;
 mod status_provider;
 
+pub use block_sync::{BlockImporter, BlockSync, BlockSyncData};
 pub use chain_info::ChainInfoProvider;
 pub use data_interpreter::OrderedDataInterpreter;
 pub use data_provider::ChainTracker;
 pub use data_store::{DataStore, DataStoreConfig};
+pub use fork_choice::ObservedProposals;
+pub use monitor::{
+    DataStoreMonitor, DataStoreStatus, DataStoreStatusHandle, PendingProposalReport,
+};
 pub use proposal::UnvalidatedAlephProposal;
 
-// Maximum number of blocks above the last finalized allowed in an AlephBFT proposal.
-pub const MAX_DATA_BRANCH_LEN: usize = 7;
+// Default maximum number of blocks above the last finalized allowed in an AlephBFT proposal, used
+// when the runtime does not specify one.
+pub const MAX_DATA_BRANCH_LEN: usize = aleph_primitives::DEFAULT_MAX_DATA_BRANCH_LEN as usize;
 
 /// The data ordered by the Aleph consensus.
 #[derive(Clone, Debug, Encode, Decode)]
@@ -0,0 +1,165 @@
+use log::warn;
+use parking_lot::Mutex;
+use prometheus_endpoint::{register, Counter, Gauge, PrometheusError, Registry, U64};
+use serde::Serialize;
+use std::sync::Arc;
+
+/// A proposal the data store is waiting for, as reported by the `aleph_dataStoreStatus` RPC.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingProposalReport {
+    pub top_block_hash: String,
+    pub top_block_number: u64,
+    pub branch_len: usize,
+    pub status: String,
+    pub pending_for_ms: u64,
+    pub waiting_messages: usize,
+}
+
+/// A snapshot of the data store of the current session, taken during its periodic maintenance.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DataStoreStatus {
+    pub session_first_block: u64,
+    pub session_last_block: u64,
+    pub highest_finalized: u64,
+    pub pending_messages: usize,
+    pub pending_proposals: Vec<PendingProposalReport>,
+}
+
+/// A shared handle to the data store status. The data store keeps it up to date, anyone holding a
+/// clone can read the latest snapshot.
+#[derive(Clone, Default)]
+pub struct DataStoreStatusHandle(Arc<Mutex<DataStoreStatus>>);
+
+impl DataStoreStatusHandle {
+    /// Returns the latest snapshot of the data store status.
+    pub fn status(&self) -> DataStoreStatus {
+        self.0.lock().clone()
+    }
+
+    fn update(&self, status: DataStoreStatus) {
+        *self.0.lock() = status;
+    }
+}
+
+#[derive(Clone)]
+struct Metrics {
+    pending_messages: Gauge<U64>,
+    pending_proposals: Gauge<U64>,
+    oldest_pending_proposal_age: Gauge<U64>,
+    block_requests: Counter<U64>,
+    justification_requests: Counter<U64>,
+    hopeless_forks: Counter<U64>,
+}
+
+impl Metrics {
+    fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+        Ok(Metrics {
+            pending_messages: register(
+                Gauge::new(
+                    "aleph_data_store_pending_messages",
+                    "Number of messages waiting for their proposals to become available",
+                )?,
+                registry,
+            )?,
+            pending_proposals: register(
+                Gauge::new(
+                    "aleph_data_store_pending_proposals",
+                    "Number of proposals that are not available yet",
+                )?,
+                registry,
+            )?,
+            oldest_pending_proposal_age: register(
+                Gauge::new(
+                    "aleph_data_store_oldest_pending_proposal_age_ms",
+                    "How long the oldest pending proposal has been waiting, in milliseconds",
+                )?,
+                registry,
+            )?,
+            block_requests: register(
+                Counter::new(
+                    "aleph_data_store_block_requests",
+                    "Number of requests for blocks of pending proposals",
+                )?,
+                registry,
+            )?,
+            justification_requests: register(
+                Counter::new(
+                    "aleph_data_store_justification_requests",
+                    "Number of requests for justifications below pending proposals",
+                )?,
+                registry,
+            )?,
+            hopeless_forks: register(
+                Counter::new(
+                    "aleph_data_store_hopeless_forks",
+                    "Number of proposals ignored as forks of the finalized chain",
+                )?,
+                registry,
+            )?,
+        })
+    }
+}
+
+/// Reports the state of the data store as Prometheus metrics and as a status snapshot. It is
+/// shared by the data stores of all the sessions, so the metrics are only registered once.
+#[derive(Clone)]
+pub struct DataStoreMonitor {
+    metrics: Option<Metrics>,
+    status: DataStoreStatusHandle,
+}
+
+impl DataStoreMonitor {
+    pub fn new(status: DataStoreStatusHandle, registry: Option<Registry>) -> Self {
+        let metrics = registry.and_then(|registry| match Metrics::register(&registry) {
+            Ok(metrics) => Some(metrics),
+            Err(e) => {
+                warn!(target: "aleph-data-store", "Failed to register data store metrics: {:?}", e);
+                None
+            }
+        });
+        DataStoreMonitor { metrics, status }
+    }
+
+    pub(crate) fn report_status(&self, status: DataStoreStatus) {
+        if let Some(metrics) = &self.metrics {
+            let oldest_age = status
+                .pending_proposals
+                .iter()
+                .map(|proposal| proposal.pending_for_ms)
+                .max()
+                .unwrap_or(0);
+            metrics.pending_messages.set(status.pending_messages as u64);
+            metrics
+                .pending_proposals
+                .set(status.pending_proposals.len() as u64);
+            metrics.oldest_pending_proposal_age.set(oldest_age);
+        }
+        self.status.update(status);
+    }
+
+    pub(crate) fn report_block_request(&self) {
+        if let Some(metrics) = &self.metrics {
+            metrics.block_requests.inc();
+        }
+    }
+
+    pub(crate) fn report_justification_request(&self) {
+        if let Some(metrics) = &self.metrics {
+            metrics.justification_requests.inc();
+        }
+    }
+
+    pub(crate) fn report_hopeless_fork(&self) {
+        if let Some(metrics) = &self.metrics {
+            metrics.hopeless_forks.inc();
+        }
+    }
+}
+
+impl Default for DataStoreMonitor {
+    fn default() -> Self {
+        DataStoreMonitor::new(DataStoreStatusHandle::default(), None)
+    }
+}
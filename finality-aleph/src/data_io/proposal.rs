@@ -1,4 +1,4 @@
-use crate::{data_io::MAX_DATA_BRANCH_LEN, BlockHashNum, SessionBoundaries};
+use crate::{BlockHashNum, SessionBoundaries};
 use codec::{Decode, Encode};
 use sp_runtime::{
     traits::{Block as BlockT, NumberFor},
@@ -54,11 +54,14 @@ impl<B: BlockT> UnvalidatedAlephProposal<B> {
         }
     }
 
+    /// Checks that the branch is not longer than `max_branch_len`, the limit agreed on for the
+    /// session, and that it fits within the session boundaries.
     pub(crate) fn validate_bounds(
         &self,
         session_boundaries: &SessionBoundaries<B>,
+        max_branch_len: usize,
     ) -> Option<AlephProposal<B>> {
-        if self.branch.len() > MAX_DATA_BRANCH_LEN as usize {
+        if self.branch.len() > max_branch_len {
             return None;
         }
         if self.branch.is_empty() {
@@ -197,46 +200,77 @@ mod tests {
 
     #[test]
     fn too_long_proposal_is_invalid() {
-        let session_boundaries = SessionBoundaries::<Block>::new(SessionId(1), SessionPeriod(20));
+        let session_boundaries =
+            SessionBoundaries::<Block>::new(SessionId(1), &SessionPeriod(20).into());
         let session_end = session_boundaries.last_block();
         let branch = vec![H256::default(); MAX_DATA_BRANCH_LEN + 1];
         let proposal = UnvalidatedAlephProposal::new(branch, session_end);
-        assert_eq!(proposal.validate_bounds(&session_boundaries), None);
+        assert_eq!(
+            proposal.validate_bounds(&session_boundaries, MAX_DATA_BRANCH_LEN),
+            None
+        );
     }
 
     #[test]
     fn proposal_not_within_session_is_invalid() {
-        let session_boundaries = SessionBoundaries::<Block>::new(SessionId(1), SessionPeriod(20));
+        let session_boundaries =
+            SessionBoundaries::<Block>::new(SessionId(1), &SessionPeriod(20).into());
         let session_start = session_boundaries.first_block();
         let session_end = session_boundaries.last_block();
         let branch = vec![H256::default(); 2];
 
         let proposal = UnvalidatedAlephProposal::new(branch.clone(), session_start);
-        assert_eq!(proposal.validate_bounds(&session_boundaries), None);
+        assert_eq!(
+            proposal.validate_bounds(&session_boundaries, MAX_DATA_BRANCH_LEN),
+            None
+        );
 
         let proposal = UnvalidatedAlephProposal::new(branch, session_end + 1);
-        assert_eq!(proposal.validate_bounds(&session_boundaries), None);
+        assert_eq!(
+            proposal.validate_bounds(&session_boundaries, MAX_DATA_BRANCH_LEN),
+            None
+        );
     }
 
     #[test]
     fn proposal_starting_at_zero_block_is_invalid() {
-        let session_boundaries = SessionBoundaries::<Block>::new(SessionId(0), SessionPeriod(20));
+        let session_boundaries =
+            SessionBoundaries::<Block>::new(SessionId(0), &SessionPeriod(20).into());
         let branch = vec![H256::default(); 2];
 
         let proposal = UnvalidatedAlephProposal::new(branch, 1);
-        assert_eq!(proposal.validate_bounds(&session_boundaries), None);
+        assert_eq!(
+            proposal.validate_bounds(&session_boundaries, MAX_DATA_BRANCH_LEN),
+            None
+        );
     }
 
     #[test]
     fn valid_proposal_is_validated_positively() {
-        let session_boundaries = SessionBoundaries::<Block>::new(SessionId(0), SessionPeriod(20));
+        let session_boundaries =
+            SessionBoundaries::<Block>::new(SessionId(0), &SessionPeriod(20).into());
 
         let branch = vec![H256::default(); MAX_DATA_BRANCH_LEN];
         let proposal = UnvalidatedAlephProposal::new(branch, (MAX_DATA_BRANCH_LEN + 1) as u64);
-        assert!(proposal.validate_bounds(&session_boundaries).is_some());
+        assert!(proposal
+            .validate_bounds(&session_boundaries, MAX_DATA_BRANCH_LEN)
+            .is_some());
 
         let branch = vec![H256::default(); 1];
         let proposal = UnvalidatedAlephProposal::new(branch, (MAX_DATA_BRANCH_LEN + 1) as u64);
-        assert!(proposal.validate_bounds(&session_boundaries).is_some());
+        assert!(proposal
+            .validate_bounds(&session_boundaries, MAX_DATA_BRANCH_LEN)
+            .is_some());
+    }
+
+    #[test]
+    fn branch_length_limit_is_taken_from_the_session() {
+        let session_boundaries =
+            SessionBoundaries::<Block>::new(SessionId(0), &SessionPeriod(20).into());
+        let branch = vec![H256::default(); 12];
+        let proposal = UnvalidatedAlephProposal::new(branch, 15);
+
+        assert_eq!(proposal.validate_bounds(&session_boundaries, 11), None);
+        assert!(proposal.validate_bounds(&session_boundaries, 12).is_some());
     }
 }
@@ -71,7 +71,7 @@ where
     }
 }
 
-fn is_hopeless_fork<B, CIP>(chain_info_provider: &mut CIP, proposal: &AlephProposal<B>) -> bool
+pub fn is_hopeless_fork<B, CIP>(chain_info_provider: &mut CIP, proposal: &AlephProposal<B>) -> bool
 where
     B: BlockT,
     CIP: ChainInfoProvider<B>,
@@ -171,8 +171,10 @@ mod tests {
     fn proposal_from_headers(headers: Vec<Header>) -> AlephProposal<Block> {
         let unvalidated = unvalidated_proposal_from_headers(headers);
         let session_boundaries =
-            SessionBoundaries::new(SessionId(0), SessionPeriod(DUMMY_SESSION_LEN));
-        unvalidated.validate_bounds(&session_boundaries).unwrap()
+            SessionBoundaries::new(SessionId(0), &SessionPeriod(DUMMY_SESSION_LEN).into());
+        unvalidated
+            .validate_bounds(&session_boundaries, MAX_DATA_BRANCH_LEN)
+            .unwrap()
     }
 
     fn proposal_from_blocks(blocks: Vec<Block>) -> AlephProposal<Block> {
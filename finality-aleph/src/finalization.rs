@@ -1,12 +1,16 @@
 use core::result::Result;
 use std::{marker::PhantomData, sync::Arc};
 
+use aleph_primitives::ALEPH_ENGINE_ID;
+use codec::DecodeAll;
 use log::{debug, warn};
 use sc_client_api::{Backend, Finalizer, HeaderBackend, LockImportRun};
 use sp_api::{BlockId, NumberFor};
 use sp_blockchain::Error;
 use sp_runtime::{traits::Block, Justification};
 
+use crate::justification::AlephJustification;
+
 pub trait BlockFinalizer<B: Block> {
     fn finalize_block(
         &self,
@@ -70,3 +74,152 @@ where
         update_res
     }
 }
+
+/// Reacts to blocks finalized by Aleph, e.g. by notifying services running outside of the node.
+/// Hooks are called from the justification handler, so they should not block.
+pub trait FinalityHook<B: Block>: Send + Sync {
+    fn on_block_finalized(
+        &self,
+        hash: B::Hash,
+        block_number: NumberFor<B>,
+        justification: &AlephJustification,
+    );
+}
+
+/// Finalizes blocks using the wrapped finalizer and then passes every block finalized with an
+/// Aleph justification to all the hooks, in the order they were added.
+pub struct HookedFinalizer<B: Block, F: BlockFinalizer<B>> {
+    finalizer: F,
+    hooks: Vec<Arc<dyn FinalityHook<B>>>,
+}
+
+impl<B: Block, F: BlockFinalizer<B>> HookedFinalizer<B, F> {
+    pub(crate) fn new(finalizer: F, hooks: Vec<Arc<dyn FinalityHook<B>>>) -> Self {
+        HookedFinalizer { finalizer, hooks }
+    }
+
+    fn run_hooks(
+        &self,
+        hash: B::Hash,
+        block_number: NumberFor<B>,
+        justification: Option<Justification>,
+    ) {
+        let justification = match justification {
+            Some((ALEPH_ENGINE_ID, raw)) => {
+                match AlephJustification::decode_all(&mut raw.as_slice()) {
+                    Ok(justification) => justification,
+                    Err(e) => {
+                        warn!(target: "aleph-finality", "Failed to decode the justification of finalized block {:?} for finality hooks: {:?}", hash, e);
+                        return;
+                    }
+                }
+            }
+            _ => return,
+        };
+        for hook in &self.hooks {
+            hook.on_block_finalized(hash, block_number, &justification);
+        }
+    }
+}
+
+impl<B: Block, F: BlockFinalizer<B>> BlockFinalizer<B> for HookedFinalizer<B, F> {
+    fn finalize_block(
+        &self,
+        hash: B::Hash,
+        block_number: NumberFor<B>,
+        justification: Option<Justification>,
+    ) -> Result<(), Error> {
+        if self.hooks.is_empty() {
+            return self
+                .finalizer
+                .finalize_block(hash, block_number, justification);
+        }
+        self.finalizer
+            .finalize_block(hash, block_number, justification.clone())?;
+        self.run_hooks(hash, block_number, justification);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BlockFinalizer, FinalityHook, HookedFinalizer};
+    use crate::{
+        justification::AlephJustification,
+        testing::mocks::{MockedBlockFinalizer, TBlock, THash, TNumber},
+    };
+    use aleph_bft::{NodeCount, SignatureSet};
+    use aleph_primitives::ALEPH_ENGINE_ID;
+    use codec::Encode;
+    use parking_lot::Mutex;
+    use std::sync::Arc;
+
+    #[derive(Default)]
+    struct RecordingHook {
+        finalized: Mutex<Vec<(THash, TNumber, AlephJustification)>>,
+    }
+
+    impl FinalityHook<TBlock> for RecordingHook {
+        fn on_block_finalized(
+            &self,
+            hash: THash,
+            block_number: TNumber,
+            justification: &AlephJustification,
+        ) {
+            self.finalized
+                .lock()
+                .push((hash, block_number, justification.clone()));
+        }
+    }
+
+    fn justification() -> AlephJustification {
+        AlephJustification {
+            signature: SignatureSet::with_size(NodeCount(4)),
+        }
+    }
+
+    fn hooked_finalizer(
+        hooks: Vec<Arc<RecordingHook>>,
+    ) -> HookedFinalizer<TBlock, MockedBlockFinalizer> {
+        HookedFinalizer::new(
+            MockedBlockFinalizer::new(),
+            hooks
+                .into_iter()
+                .map(|hook| hook as Arc<dyn FinalityHook<TBlock>>)
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn passes_aleph_justification_to_all_hooks() {
+        let hooks = vec![
+            Arc::new(RecordingHook::default()),
+            Arc::new(RecordingHook::default()),
+        ];
+        let finalizer = hooked_finalizer(hooks.clone());
+        let hash = THash::repeat_byte(1);
+
+        finalizer
+            .finalize_block(hash, 7, Some((ALEPH_ENGINE_ID, justification().encode())))
+            .unwrap();
+
+        for hook in hooks {
+            assert_eq!(*hook.finalized.lock(), vec![(hash, 7, justification())]);
+        }
+    }
+
+    #[test]
+    fn skips_hooks_without_aleph_justification() {
+        let hook = Arc::new(RecordingHook::default());
+        let finalizer = hooked_finalizer(vec![hook.clone()]);
+
+        finalizer
+            .finalize_block(THash::repeat_byte(1), 7, None)
+            .unwrap();
+        finalizer
+            .finalize_block(THash::repeat_byte(2), 8, Some((*b"TEST", vec![1, 2, 3])))
+            .unwrap();
+
+        assert!(hook.finalized.lock().is_empty());
+    }
+}
@@ -1,5 +1,6 @@
 use crate::{
     aggregation::RmcNetworkData,
+    data_io::BlockSyncData,
     network::{AlephNetworkData, Split},
     session::{
         first_block_of_session, last_block_of_session, session_id_from_block_num,
@@ -12,8 +13,12 @@ use futures::{
     channel::{mpsc, oneshot},
     Future, TryFutureExt,
 };
-use sc_client_api::{backend::Backend, BlockchainEvents, Finalizer, LockImportRun, TransactionFor};
-use sc_consensus::BlockImport;
+use prometheus_endpoint::Registry;
+use sc_client_api::{
+    backend::Backend, AuxStore, BlockBackend, BlockchainEvents, Finalizer, LockImportRun,
+    TransactionFor,
+};
+use sc_consensus::{import_queue::ImportQueue, BlockImport};
 use sc_network::{ExHashT, NetworkService};
 use sc_service::SpawnTaskHandle;
 use sp_api::{NumberFor, ProvideRuntimeApi};
@@ -33,19 +38,30 @@ pub mod metrics;
 mod network;
 mod nodes;
 mod party;
+mod select_chain;
 mod session;
 mod session_map;
 #[cfg(test)]
 pub mod testing;
 
 pub use crate::metrics::Metrics;
+pub use aggregation::{AggregationPolicy, AggregatorConfig};
 pub use aleph_bft::default_config as default_aleph_config;
 pub use aleph_primitives::{AuthorityId, AuthorityPair, AuthoritySignature};
+pub use data_io::{
+    ChainInfoCacheConfig, DataStoreConfig, DataStoreStatus, DataStoreStatusHandle,
+    PendingProposalReport,
+};
+pub use finalization::FinalityHook;
 pub use import::AlephBlockImport;
-pub use justification::JustificationNotification;
-pub use network::Protocol;
+pub use justification::{AlephJustification, JustificationNotification};
+pub use network::{
+    AddressPolicy, NetworkStatus, NetworkStatusHandle, PeerReputationConfig, Protocol, SentryRole,
+    SentryRoleError,
+};
 pub use nodes::{run_nonvalidator_node, run_validator_node};
-pub use session::SessionPeriod;
+pub use select_chain::{AlephSelectChain, OrderedHead};
+pub use session::{constant_schedule, supports_session_schedule, SessionPeriod};
 
 #[derive(Clone, Debug, Encode, Decode)]
 enum Error {
@@ -61,6 +77,7 @@ pub fn peers_set_config(protocol: Protocol) -> sc_network::config::NonDefaultSet
         // max_notification_size should be larger than the maximum possible honest message size (in bytes).
         // Max size of alert is UNIT_SIZE * MAX_UNITS_IN_ALERT ~ 100 * 5000 = 50000 bytes
         // Max size of parents response UNIT_SIZE * N_MEMBERS ~ 100 * N_MEMBERS
+        // Block sync responses are limited to 512 * 1024 bytes.
         // When adding other (large) message types we need to make sure this limit is fine.
         1024 * 1024,
     );
@@ -84,7 +101,8 @@ pub struct MillisecsPerBlock(pub u64);
 #[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash, Ord, PartialOrd, Encode, Decode)]
 pub struct UnitCreationDelay(pub u64);
 
-pub(crate) type SplitData<B> = Split<AlephNetworkData<B>, RmcNetworkData<B>>;
+pub(crate) type SplitData<B> =
+    Split<AlephNetworkData<B>, Split<RmcNetworkData<B>, BlockSyncData<B>>>;
 
 pub trait ClientForAleph<B, BE>:
     LockImportRun<B, BE>
@@ -94,6 +112,8 @@ pub trait ClientForAleph<B, BE>:
     + HeaderBackend<B>
     + HeaderMetadata<B, Error = sp_blockchain::Error>
     + BlockchainEvents<B>
+    + BlockBackend<B>
+    + AuxStore
 where
     BE: Backend<B>,
     B: Block,
@@ -110,6 +130,8 @@ where
         + HeaderBackend<B>
         + HeaderMetadata<B, Error = sp_blockchain::Error>
         + BlockchainEvents<B>
+        + BlockBackend<B>
+        + AuxStore
         + BlockImport<B, Transaction = TransactionFor<BE, B>, Error = sp_consensus::Error>,
 {
 }
@@ -176,4 +198,15 @@ pub struct AlephConfig<B: Block, H: ExHashT, C, SC> {
     pub session_period: SessionPeriod,
     pub millisecs_per_block: MillisecsPerBlock,
     pub unit_creation_delay: UnitCreationDelay,
+    pub aggregator_config: AggregatorConfig,
+    pub data_store_config: DataStoreConfig,
+    pub peer_reputation: PeerReputationConfig,
+    pub address_policy: AddressPolicy,
+    pub sentry_role: SentryRole,
+    pub registry: Option<Registry>,
+    pub network_status: NetworkStatusHandle,
+    pub data_store_status: DataStoreStatusHandle,
+    pub ordered_head: OrderedHead<B>,
+    pub block_import_queue: Box<dyn ImportQueue<B>>,
+    pub finality_hooks: Vec<Arc<dyn FinalityHook<B>>>,
 }
@@ -1,5 +1,5 @@
 use crate::network::{Data, DataNetwork, SendError};
-use aleph_bft::Recipient;
+use aleph_bft::{NodeIndex, Recipient};
 use futures::{channel::mpsc, StreamExt};
 use std::{marker::PhantomData, sync::Arc};
 use tokio::sync::Mutex;
@@ -13,6 +13,12 @@ pub trait Sender<D: Data>: Sync + Send + Clone {
 #[async_trait::async_trait]
 pub trait Receiver<D: Data>: Sync + Send {
     async fn next(&mut self) -> Option<D>;
+
+    /// Like `next`, but also returns the committee member that sent the data, if the network could
+    /// authenticate it.
+    async fn next_with_sender(&mut self) -> Option<(D, Option<NodeIndex>)> {
+        self.next().await.map(|data| (data, None))
+    }
 }
 
 /// A bare version of network components.
@@ -0,0 +1,206 @@
+use crate::network::manager::Multiaddr;
+use ip_network::IpNetwork;
+use sc_network::{multiaddr::Protocol, Multiaddr as ScMultiaddr};
+use std::net::IpAddr;
+
+/// The maximal number of addresses we accept in a single remote authentication.
+pub const MAX_AUTHENTICATION_ADDRESSES: usize = 16;
+
+enum Host {
+    Ip(IpAddr),
+    Dns,
+}
+
+fn host(address: &ScMultiaddr) -> Option<Host> {
+    match address.iter().next()? {
+        Protocol::Ip4(ip) => Some(Host::Ip(ip.into())),
+        Protocol::Ip6(ip) => Some(Host::Ip(ip.into())),
+        Protocol::Dns(_) | Protocol::Dns4(_) | Protocol::Dns6(_) | Protocol::Dnsaddr(_) => {
+            Some(Host::Dns)
+        }
+        _ => None,
+    }
+}
+
+fn is_routable(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => !(ip.is_unspecified() || ip.is_multicast() || ip.is_broadcast()),
+        IpAddr::V6(ip) => !(ip.is_unspecified() || ip.is_multicast()),
+    }
+}
+
+/// Decides which of our own addresses get published to other validators.
+#[derive(Clone, Debug, Default)]
+pub struct AddressPolicy {
+    allowed: Vec<IpNetwork>,
+    denied: Vec<IpNetwork>,
+    public_addresses: Vec<ScMultiaddr>,
+    dns_only: bool,
+}
+
+impl AddressPolicy {
+    /// Creates a policy publishing only IP addresses contained in one of the allowed networks (any
+    /// network if none are given) and none of the denied ones. If `dns_only` is set, no IP
+    /// addresses are published at all. Nonempty `public_addresses` replace the addresses reported
+    /// by the network, without any filtering.
+    pub fn new(
+        allowed: Vec<IpNetwork>,
+        denied: Vec<IpNetwork>,
+        public_addresses: Vec<ScMultiaddr>,
+        dns_only: bool,
+    ) -> Self {
+        AddressPolicy {
+            allowed,
+            denied,
+            public_addresses,
+            dns_only,
+        }
+    }
+
+    fn allows(&self, address: &ScMultiaddr) -> bool {
+        match host(address) {
+            Some(Host::Dns) => true,
+            Some(Host::Ip(ip)) => {
+                !self.dns_only
+                    && is_routable(&ip)
+                    && !self.denied.iter().any(|network| network.contains(ip))
+                    && (self.allowed.is_empty()
+                        || self.allowed.iter().any(|network| network.contains(ip)))
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the subset of the given own addresses that should be published.
+    pub fn own_addresses(&self, addresses: Vec<ScMultiaddr>) -> Vec<ScMultiaddr> {
+        if !self.public_addresses.is_empty() {
+            return self.public_addresses.clone();
+        }
+        addresses
+            .into_iter()
+            .filter(|address| self.allows(address))
+            .collect()
+    }
+}
+
+/// Checks whether the addresses from a remote authentication look like something we could connect
+/// to. This is not a guarantee of reachability, it only weeds out obvious garbage.
+pub fn are_sane(addresses: &[Multiaddr]) -> bool {
+    !addresses.is_empty()
+        && addresses.len() <= MAX_AUTHENTICATION_ADDRESSES
+        && addresses.iter().all(|address| match host(&address.0) {
+            Some(Host::Dns) => true,
+            Some(Host::Ip(ip)) => is_routable(&ip),
+            None => false,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{are_sane, AddressPolicy, MAX_AUTHENTICATION_ADDRESSES};
+    use crate::network::manager::testing::address;
+    use sc_network::Multiaddr as ScMultiaddr;
+
+    const PEER: &str = "p2p/12D3KooWRkGLz4YbVmrsWK75VjFTs8NvaBu42xhAmQaP4KeJpw1L";
+
+    fn addresses(texts: &[&str]) -> Vec<ScMultiaddr> {
+        texts
+            .iter()
+            .map(|text| address(&format!("{}/{}", text, PEER)))
+            .collect()
+    }
+
+    fn own_addresses() -> Vec<ScMultiaddr> {
+        addresses(&[
+            "/ip4/127.0.0.1/tcp/30333",
+            "/ip4/172.17.0.2/tcp/30333",
+            "/ip4/81.6.39.166/tcp/30333",
+            "/ip6/::1/tcp/30333",
+            "/dns4/example.com/tcp/30333",
+        ])
+    }
+
+    #[test]
+    fn default_policy_publishes_everything() {
+        assert_eq!(
+            AddressPolicy::default().own_addresses(own_addresses()),
+            own_addresses()
+        );
+    }
+
+    #[test]
+    fn denied_networks_are_not_published() {
+        let policy = AddressPolicy::new(
+            Vec::new(),
+            vec![
+                "127.0.0.0/8".parse().unwrap(),
+                "172.16.0.0/12".parse().unwrap(),
+                "::1/128".parse().unwrap(),
+            ],
+            Vec::new(),
+            false,
+        );
+        assert_eq!(
+            policy.own_addresses(own_addresses()),
+            addresses(&["/ip4/81.6.39.166/tcp/30333", "/dns4/example.com/tcp/30333"])
+        );
+    }
+
+    #[test]
+    fn only_allowed_networks_are_published() {
+        let policy = AddressPolicy::new(
+            vec!["81.6.0.0/16".parse().unwrap()],
+            Vec::new(),
+            Vec::new(),
+            false,
+        );
+        assert_eq!(
+            policy.own_addresses(own_addresses()),
+            addresses(&["/ip4/81.6.39.166/tcp/30333", "/dns4/example.com/tcp/30333"])
+        );
+    }
+
+    #[test]
+    fn dns_only_policy_publishes_only_dns() {
+        let policy = AddressPolicy::new(Vec::new(), Vec::new(), Vec::new(), true);
+        assert_eq!(
+            policy.own_addresses(own_addresses()),
+            addresses(&["/dns4/example.com/tcp/30333"])
+        );
+    }
+
+    #[test]
+    fn public_addresses_override_own_addresses() {
+        let public_addresses = addresses(&["/dns4/validator.example.com/tcp/30333"]);
+        let policy = AddressPolicy::new(Vec::new(), Vec::new(), public_addresses.clone(), true);
+        assert_eq!(policy.own_addresses(own_addresses()), public_addresses);
+    }
+
+    #[test]
+    fn reasonable_addresses_are_sane() {
+        let addresses: Vec<_> = own_addresses().into_iter().map(|a| a.into()).collect();
+        assert!(are_sane(&addresses));
+    }
+
+    #[test]
+    fn garbage_addresses_are_not_sane() {
+        assert!(!are_sane(&[]));
+        for garbage in [
+            "/ip4/0.0.0.0/tcp/30333",
+            "/ip4/224.0.0.1/tcp/30333",
+            "/ip4/255.255.255.255/tcp/30333",
+            "/ip6/::/tcp/30333",
+            "/memory/1234",
+        ] {
+            assert!(!are_sane(&[address(garbage).into()]), "{}", garbage);
+        }
+    }
+
+    #[test]
+    fn too_many_addresses_are_not_sane() {
+        let addresses: Vec<_> = (0..=MAX_AUTHENTICATION_ADDRESSES)
+            .map(|port| address(&format!("/ip4/81.6.39.166/tcp/{}/{}", port, PEER)).into())
+            .collect();
+        assert!(!are_sane(&addresses));
+    }
+}
@@ -1,11 +1,17 @@
-use crate::{crypto::Signature, network::Data, NodeIndex, SessionId};
+use crate::{
+    crypto::Signature,
+    network::{Data, PeerId},
+    NodeIndex, SessionId,
+};
 use codec::{Decode, Encode};
 use sc_network::Multiaddr as ScMultiaddr;
 use std::convert::TryFrom;
 
+mod address_policy;
 mod addresses;
 mod connections;
 mod discovery;
+mod sentry;
 mod service;
 mod session;
 #[cfg(test)]
@@ -15,11 +21,14 @@ pub use service::{
     Config as ConnectionManagerConfig, Service as ConnectionManager, IO as ConnectionIO,
 };
 
+use address_policy::are_sane;
 use addresses::{add_matching_peer_id, get_common_peer_id, is_p2p};
 use connections::Connections;
 pub use discovery::{Discovery, DiscoveryMessage};
+pub use sentry::{SentryRole, SentryRoleError};
 pub use session::{Handler as SessionHandler, HandlerError as SessionHandlerError};
 
+pub use address_policy::AddressPolicy;
 pub use addresses::get_peer_id;
 pub use service::SessionCommand;
 
@@ -79,6 +88,11 @@ pub type Authentication = (AuthData, Signature);
 pub enum NetworkData<D: Data> {
     Meta(DiscoveryMessage),
     Data(D, SessionId),
+    /// Data a protected validator asks its sentry to pass on to the given peer.
+    Relay(D, SessionId, PeerId),
+    /// Data a sentry passes on to the validator it protects, together with the authority that sent
+    /// it.
+    Relayed(D, SessionId, NodeIndex),
 }
 
 #[cfg(test)]
@@ -0,0 +1,115 @@
+use crate::network::{
+    manager::{get_common_peer_id, Multiaddr},
+    PeerId,
+};
+use sc_network::Multiaddr as ScMultiaddr;
+use std::collections::HashSet;
+
+/// Errors when setting up a sentry role.
+#[derive(Debug, PartialEq)]
+pub enum SentryRoleError {
+    /// The addresses of the other side of the private link do not contain a unique PeerId.
+    NoUniquePeerId,
+}
+
+/// The part the node plays in the sentry architecture. The other side of the private link is
+/// identified by its addresses, which all have to contain the same PeerId.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SentryRole {
+    /// The node is not using sentries, validators connect to it directly.
+    None,
+    /// A validator hidden behind a sentry. It announces the addresses of the sentry instead of its
+    /// own and only connects to the sentry, which relays all the validator traffic. There is
+    /// exactly one sentry, so the validator is cut off from the network while the sentry is down.
+    Protected {
+        sentry: PeerId,
+        addresses: Vec<Multiaddr>,
+    },
+    /// A sentry relaying the validator traffic of the protected validator.
+    Sentry {
+        validator: PeerId,
+        addresses: Vec<Multiaddr>,
+    },
+}
+
+impl Default for SentryRole {
+    fn default() -> Self {
+        SentryRole::None
+    }
+}
+
+fn peer_and_addresses(
+    addresses: Vec<ScMultiaddr>,
+) -> Result<(PeerId, Vec<Multiaddr>), SentryRoleError> {
+    let addresses: Vec<_> = addresses.into_iter().map(Multiaddr).collect();
+    let peer_id = get_common_peer_id(&addresses).ok_or(SentryRoleError::NoUniquePeerId)?;
+    Ok((peer_id, addresses))
+}
+
+impl SentryRole {
+    /// The role of a validator hidden behind the sentry with the given addresses.
+    pub fn protected(sentry_addresses: Vec<ScMultiaddr>) -> Result<Self, SentryRoleError> {
+        let (sentry, addresses) = peer_and_addresses(sentry_addresses)?;
+        Ok(SentryRole::Protected { sentry, addresses })
+    }
+
+    /// The role of a sentry of the validator with the given addresses.
+    pub fn sentry(validator_addresses: Vec<ScMultiaddr>) -> Result<Self, SentryRoleError> {
+        let (validator, addresses) = peer_and_addresses(validator_addresses)?;
+        Ok(SentryRole::Sentry {
+            validator,
+            addresses,
+        })
+    }
+
+    pub fn is_sentry(&self) -> bool {
+        matches!(self, SentryRole::Sentry { .. })
+    }
+
+    pub fn is_protected(&self) -> bool {
+        matches!(self, SentryRole::Protected { .. })
+    }
+
+    /// The addresses of the other side of the private link, if any.
+    pub fn link_addresses(&self) -> Option<HashSet<ScMultiaddr>> {
+        match self {
+            SentryRole::None => None,
+            SentryRole::Protected { addresses, .. } | SentryRole::Sentry { addresses, .. } => {
+                Some(addresses.iter().map(|address| address.0.clone()).collect())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SentryRole, SentryRoleError};
+    use crate::network::manager::testing::address;
+
+    #[test]
+    fn accepts_addresses_with_common_peer_id() {
+        let addresses = vec![
+            address("/dns4/sentry.example.com/tcp/30333/p2p/12D3KooWRkGLz4YbVmrsWK75VjFTs8NvaBu42xhAmQaP4KeJpw1L"),
+            address("/ip4/10.0.0.2/tcp/30333/p2p/12D3KooWRkGLz4YbVmrsWK75VjFTs8NvaBu42xhAmQaP4KeJpw1L"),
+        ];
+        let role = SentryRole::protected(addresses.clone()).unwrap();
+        assert!(role.is_protected());
+        assert_eq!(role.link_addresses(), Some(addresses.into_iter().collect()));
+    }
+
+    #[test]
+    fn rejects_addresses_without_common_peer_id() {
+        let addresses = vec![
+            address("/dns4/sentry.example.com/tcp/30333/p2p/12D3KooWRkGLz4YbVmrsWK75VjFTs8NvaBu42xhAmQaP4KeJpw1L"),
+            address("/ip4/10.0.0.2/tcp/30333/p2p/12D3KooWFVXnvJdPuGnGYMPn5qLQAQYwmRBgo6SmEQsKZSrDoo2k"),
+        ];
+        assert_eq!(
+            SentryRole::sentry(addresses),
+            Err(SentryRoleError::NoUniquePeerId)
+        );
+        assert_eq!(
+            SentryRole::sentry(Vec::new()),
+            Err(SentryRoleError::NoUniquePeerId)
+        );
+    }
+}
@@ -2,10 +2,12 @@ use crate::{
     crypto::{AuthorityPen, AuthorityVerifier},
     network::{
         manager::{
-            add_matching_peer_id, get_peer_id, Connections, Discovery, DiscoveryMessage, Multiaddr,
-            NetworkData, SessionHandler, SessionHandlerError,
+            add_matching_peer_id, get_peer_id, AddressPolicy, Connections, Discovery,
+            DiscoveryMessage, Multiaddr, NetworkData, SentryRole, SessionHandler,
+            SessionHandlerError,
         },
-        ConnectionCommand, Data, DataCommand, NetworkIdentity, PeerId, Protocol,
+        status::{NetworkStatusHandle, SessionStatus},
+        ConnectionCommand, Data, DataCommand, Misbehavior, NetworkIdentity, PeerId, Protocol,
     },
     MillisecsPerBlock, NodeIndex, SessionId, SessionPeriod,
 };
@@ -21,24 +23,28 @@ use std::{
 };
 use tokio::time::interval;
 
-/// Commands for manipulating sessions, stopping them and starting both validator and non-validator
-/// sessions.
+/// Commands for manipulating sessions, stopping them and starting validator, non-validator and
+/// sentry sessions.
 pub enum SessionCommand<D: Data> {
     StartValidator(
         SessionId,
         AuthorityVerifier,
         NodeIndex,
         AuthorityPen,
-        Option<oneshot::Sender<mpsc::UnboundedReceiver<D>>>,
+        Option<oneshot::Sender<mpsc::UnboundedReceiver<(D, Option<NodeIndex>)>>>,
     ),
     StartNonvalidator(SessionId, AuthorityVerifier),
+    /// Like a non-validator session, but connects to the authorities to relay the traffic of the
+    /// protected validator.
+    StartSentry(SessionId, AuthorityVerifier),
     Stop(SessionId),
 }
 
 struct Session<D: Data> {
     handler: SessionHandler,
     discovery: Discovery,
-    data_for_user: Option<mpsc::UnboundedSender<D>>,
+    data_for_user: Option<mpsc::UnboundedSender<(D, Option<NodeIndex>)>>,
+    relaying: bool,
 }
 
 #[derive(Clone)]
@@ -53,6 +59,7 @@ struct PreValidatorSession {
 struct PreNonvalidatorSession {
     session_id: SessionId,
     verifier: AuthorityVerifier,
+    relaying: bool,
 }
 
 #[derive(Clone)]
@@ -71,10 +78,12 @@ impl PreSession {
 }
 
 /// Configuration for the session manager service. Controls how often the maintenance and
-/// rebroadcasts are triggerred.
+/// rebroadcasts are triggerred, which of our addresses get published and whether we use sentries.
 pub struct Config {
     discovery_cooldown: Duration,
     maintenance_period: Duration,
+    address_policy: AddressPolicy,
+    sentry_role: SentryRole,
 }
 
 impl Config {
@@ -82,9 +91,23 @@ impl Config {
         Config {
             discovery_cooldown,
             maintenance_period,
+            address_policy: AddressPolicy::default(),
+            sentry_role: SentryRole::default(),
         }
     }
 
+    /// Sets the policy deciding which of our addresses get published.
+    pub fn with_address_policy(mut self, address_policy: AddressPolicy) -> Self {
+        self.address_policy = address_policy;
+        self
+    }
+
+    /// Sets the part we play in the sentry architecture.
+    pub fn with_sentry_role(mut self, sentry_role: SentryRole) -> Self {
+        self.sentry_role = sentry_role;
+        self
+    }
+
     /// Returns a configuration that triggers maintenance about 5 times per session.
     pub fn with_session_period(
         session_period: &SessionPeriod,
@@ -115,6 +138,8 @@ pub struct Service<NI: NetworkIdentity, D: Data> {
     )>,
     discovery_cooldown: Duration,
     maintenance_period: Duration,
+    address_policy: AddressPolicy,
+    sentry_role: SentryRole,
 }
 
 impl<NI: NetworkIdentity, D: Data> Service<NI, D> {
@@ -123,6 +148,8 @@ impl<NI: NetworkIdentity, D: Data> Service<NI, D> {
         let Config {
             discovery_cooldown,
             maintenance_period,
+            address_policy,
+            sentry_role,
         } = config;
         Service {
             network_identity,
@@ -131,6 +158,8 @@ impl<NI: NetworkIdentity, D: Data> Service<NI, D> {
             to_retry: Vec::new(),
             discovery_cooldown,
             maintenance_period,
+            address_policy,
+            sentry_role,
         }
     }
 
@@ -183,8 +212,16 @@ impl<NI: NetworkIdentity, D: Data> Service<NI, D> {
     }
 
     fn addresses(&self) -> Vec<Multiaddr> {
+        // A protected validator is only reachable through its sentry.
+        if let SentryRole::Protected { addresses, .. } = &self.sentry_role {
+            return addresses.clone();
+        }
         let (addresses, peer_id) = self.network_identity.identity();
         debug!(target: "aleph-network", "Got addresses:\n{:?}\n and peer_id:{:?}", addresses, peer_id);
+        let addresses = self.address_policy.own_addresses(addresses);
+        if addresses.is_empty() {
+            warn!(target: "aleph-network", "The address policy filtered out all our addresses.");
+        }
         addresses
             .into_iter()
             .map(Multiaddr)
@@ -199,7 +236,7 @@ impl<NI: NetworkIdentity, D: Data> Service<NI, D> {
     ) -> Result<
         (
             Vec<(NetworkData<D>, DataCommand)>,
-            mpsc::UnboundedReceiver<D>,
+            mpsc::UnboundedReceiver<(D, Option<NodeIndex>)>,
         ),
         SessionHandlerError,
     > {
@@ -220,6 +257,7 @@ impl<NI: NetworkIdentity, D: Data> Service<NI, D> {
                 handler,
                 discovery,
                 data_for_user,
+                relaying: false,
             },
         );
         Ok((self.discover_authorities(&session_id), data_from_network))
@@ -232,7 +270,7 @@ impl<NI: NetworkIdentity, D: Data> Service<NI, D> {
         (
             Option<ConnectionCommand>,
             Vec<(NetworkData<D>, DataCommand)>,
-            mpsc::UnboundedReceiver<D>,
+            mpsc::UnboundedReceiver<(D, Option<NodeIndex>)>,
         ),
         SessionHandlerError,
     > {
@@ -251,13 +289,15 @@ impl<NI: NetworkIdentity, D: Data> Service<NI, D> {
             node_id,
             pen,
         } = pre_session;
-        let peers_to_stay = session
+        let addresses_to_stay = session
             .handler
             .update(Some((node_id, pen)), verifier, addresses)
-            .await?
-            .iter()
-            .flat_map(get_peer_id)
-            .collect();
+            .await?;
+        let peers_to_stay = match self.sentry_role.is_protected() {
+            // All our traffic goes through the sentry, which we are always connected to.
+            true => HashSet::new(),
+            false => addresses_to_stay.iter().flat_map(get_peer_id).collect(),
+        };
         let maybe_command = Self::delete_reserved(
             self.connections
                 .remove_session(session_id)
@@ -278,7 +318,7 @@ impl<NI: NetworkIdentity, D: Data> Service<NI, D> {
     async fn handle_validator_presession(
         &mut self,
         pre_session: PreValidatorSession,
-        result_for_user: Option<oneshot::Sender<mpsc::UnboundedReceiver<D>>>,
+        result_for_user: Option<oneshot::Sender<mpsc::UnboundedReceiver<(D, Option<NodeIndex>)>>>,
     ) -> Result<
         (
             Option<ConnectionCommand>,
@@ -311,6 +351,7 @@ impl<NI: NetworkIdentity, D: Data> Service<NI, D> {
         let PreNonvalidatorSession {
             session_id,
             verifier,
+            relaying,
         } = pre_session;
         let handler = SessionHandler::new(None, verifier, session_id, addresses).await?;
         let discovery = Discovery::new(self.discovery_cooldown);
@@ -320,6 +361,7 @@ impl<NI: NetworkIdentity, D: Data> Service<NI, D> {
                 handler,
                 discovery,
                 data_for_user: None,
+                relaying,
             },
         );
         Ok(())
@@ -338,6 +380,7 @@ impl<NI: NetworkIdentity, D: Data> Service<NI, D> {
                     .await;
             }
         };
+        session.relaying = pre_session.relaying;
         session
             .handler
             .update(None, pre_session.verifier, addresses)
@@ -387,6 +430,19 @@ impl<NI: NetworkIdentity, D: Data> Service<NI, D> {
                 let pre_session = PreNonvalidatorSession {
                     session_id,
                     verifier,
+                    relaying: false,
+                };
+                self.handle_nonvalidator_presession(pre_session).await?;
+                Ok((None, Vec::new()))
+            }
+            StartSentry(session_id, verifier) => {
+                if !self.sentry_role.is_sentry() {
+                    warn!(target: "aleph-network", "Asked to start a sentry session {:?}, but we are not a sentry.", session_id);
+                }
+                let pre_session = PreNonvalidatorSession {
+                    session_id,
+                    verifier,
+                    relaying: self.sentry_role.is_sentry(),
                 };
                 self.handle_nonvalidator_presession(pre_session).await?;
                 Ok((None, Vec::new()))
@@ -402,6 +458,30 @@ impl<NI: NetworkIdentity, D: Data> Service<NI, D> {
         message: D,
         session_id: SessionId,
         recipient: Recipient,
+    ) -> Vec<(NetworkData<D>, DataCommand)> {
+        let messages = self.direct_messages(message, session_id, recipient);
+        match &self.sentry_role {
+            SentryRole::Protected { sentry, .. } => messages
+                .into_iter()
+                .filter_map(|message| match message {
+                    (NetworkData::Data(data, session_id), DataCommand::SendTo(peer_id, _)) => {
+                        Some((
+                            NetworkData::Relay(data, session_id, peer_id),
+                            DataCommand::SendTo(*sentry, Protocol::Validator),
+                        ))
+                    }
+                    _ => None,
+                })
+                .collect(),
+            _ => messages,
+        }
+    }
+
+    fn direct_messages(
+        &self,
+        message: D,
+        session_id: SessionId,
+        recipient: Recipient,
     ) -> Vec<(NetworkData<D>, DataCommand)> {
         if let Some(handler) = self
             .sessions
@@ -449,10 +529,17 @@ impl<NI: NetworkIdentity, D: Data> Service<NI, D> {
         let session_id = message.session_id();
         match self.sessions.get_mut(&session_id) {
             Some(Session {
-                handler, discovery, ..
+                handler,
+                discovery,
+                relaying,
+                ..
             }) => {
                 let (addresses, responses) = discovery.handle_message(message, handler);
-                let maybe_command = match !addresses.is_empty() && handler.is_validator() {
+                let connect = match self.sentry_role {
+                    SentryRole::Protected { .. } => false,
+                    _ => handler.is_validator() || *relaying,
+                };
+                let maybe_command = match !addresses.is_empty() && connect {
                     true => {
                         debug!(target: "aleph-network", "Adding addresses for session {:?} to reserved: {:?}", session_id, addresses);
                         self.connections
@@ -475,8 +562,148 @@ impl<NI: NetworkIdentity, D: Data> Service<NI, D> {
         }
     }
 
-    /// Sends the data to the identified session.
-    pub fn send_session_data(&self, session_id: &SessionId, data: D) -> Result<(), Error> {
+    /// Returns the status of all the sessions we currently handle.
+    pub fn status(&self) -> Vec<SessionStatus> {
+        self.sessions
+            .values()
+            .map(|session| session.handler.status())
+            .collect()
+    }
+
+    /// Checks whether messages for the session are evidence of misbehavior, because the session
+    /// is neither handled by us nor adjacent to one we handle. Around session boundaries honest
+    /// nodes send messages for the sessions we have not started yet or have already stopped.
+    pub fn is_unexpected_session(&self, session_id: &SessionId) -> bool {
+        !self.sessions.is_empty()
+            && self.sessions.keys().all(|handled| {
+                handled.0.saturating_sub(session_id.0) > 1
+                    || session_id.0.saturating_sub(handled.0) > 1
+            })
+    }
+
+    /// Checks whether the discovery message is evidence of misbehavior of the peer that sent it.
+    /// Note that honest nodes only rebroadcast authentications they verified.
+    pub fn discovery_misbehavior(&self, message: &DiscoveryMessage) -> Option<Misbehavior> {
+        use DiscoveryMessage::*;
+        let session_id = message.session_id();
+        let handler = match self.sessions.get(&session_id) {
+            Some(Session { handler, .. }) => handler,
+            None => {
+                return match self.is_unexpected_session(&session_id) {
+                    true => Some(Misbehavior::UnknownSession),
+                    false => None,
+                }
+            }
+        };
+        match message {
+            AuthenticationBroadcast(authentication) | Authentication(authentication) => {
+                match handler.verify_authentication(authentication) {
+                    true => None,
+                    false => Some(Misbehavior::InvalidAuthentication),
+                }
+            }
+        }
+    }
+
+    /// Returns a command connecting us to the other side of the sentry link, if we have one.
+    pub fn sentry_link(&self) -> Option<ConnectionCommand> {
+        self.sentry_role
+            .link_addresses()
+            .map(ConnectionCommand::AddReserved)
+    }
+
+    /// Returns the protected validator, if we relay its traffic in the given session.
+    pub fn relay_target(&self, session_id: &SessionId) -> Option<PeerId> {
+        match (&self.sentry_role, self.sessions.get(session_id)) {
+            (SentryRole::Sentry { validator, .. }, Some(session)) if session.relaying => {
+                Some(*validator)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns a rebroadcast of the authentication of the validator we protect, if the message
+    /// contains a valid one. We have to do this explicitly, because the authentication contains our
+    /// own addresses, so our session handler ignores it.
+    pub fn relay_authentication(
+        &self,
+        message: &DiscoveryMessage,
+        sender: PeerId,
+    ) -> Option<(NetworkData<D>, DataCommand)> {
+        let session_id = message.session_id();
+        if self.relay_target(&session_id) != Some(sender) {
+            return None;
+        }
+        match message {
+            DiscoveryMessage::AuthenticationBroadcast(authentication)
+                if self.sessions[&session_id]
+                    .handler
+                    .verify_authentication(authentication) =>
+            {
+                Some((NetworkData::Meta(message.clone()), DataCommand::Broadcast))
+            }
+            _ => None,
+        }
+    }
+
+    /// Passes on data the peer asked us to relay, as long as it is the validator we protect.
+    pub fn relay_from_validator(
+        &self,
+        data: D,
+        session_id: SessionId,
+        recipient: PeerId,
+        sender: PeerId,
+    ) -> Result<(NetworkData<D>, DataCommand), Misbehavior> {
+        match self.relay_target(&session_id) {
+            Some(validator) if validator == sender => Ok((
+                NetworkData::Data(data, session_id),
+                DataCommand::SendTo(recipient, Protocol::Validator),
+            )),
+            _ => Err(Misbehavior::UnauthorizedRelay),
+        }
+    }
+
+    /// Passes on data for the validator we protect, as long as it comes from an authority that
+    /// authenticated in the session. The validator learns which authority sent it.
+    pub fn relay_to_validator(
+        &self,
+        data: D,
+        session_id: SessionId,
+        sender: PeerId,
+    ) -> Result<(NetworkData<D>, DataCommand), Misbehavior> {
+        match (
+            self.relay_target(&session_id),
+            self.session_member(&session_id, &sender),
+        ) {
+            (Some(validator), Some(node_id)) => Ok((
+                NetworkData::Relayed(data, session_id, node_id),
+                DataCommand::SendTo(validator, Protocol::Validator),
+            )),
+            _ => Err(Misbehavior::UnauthorizedRelay),
+        }
+    }
+
+    /// Returns whether the peer is the sentry we are protected by, so we can trust it to tell us
+    /// who sent the data it relays.
+    pub fn is_own_sentry(&self, peer_id: &PeerId) -> bool {
+        matches!(&self.sentry_role, SentryRole::Protected { sentry, .. } if sentry == peer_id)
+    }
+
+    /// Returns the authority the peer authenticated as in the given session, if any.
+    pub fn session_member(&self, session_id: &SessionId, peer_id: &PeerId) -> Option<NodeIndex> {
+        self.sessions
+            .get(session_id)
+            .and_then(|session| session.handler.node_id(peer_id))
+    }
+
+    /// Sends the data to the identified session, together with the authority that sent it, if
+    /// known.
+    pub fn send_session_data(
+        &self,
+        session_id: &SessionId,
+        data: D,
+        sender: Option<NodeIndex>,
+    ) -> Result<(), Error> {
         match self
             .sessions
             .get(session_id)
@@ -484,7 +711,7 @@ impl<NI: NetworkIdentity, D: Data> Service<NI, D> {
             .flatten()
         {
             Some(data_for_user) => data_for_user
-                .unbounded_send(data)
+                .unbounded_send((data, sender))
                 .map_err(|_| Error::UserSend),
             None => Err(Error::NoSession),
         }
@@ -525,7 +752,8 @@ pub struct IO<D: Data> {
     messages_for_network: mpsc::UnboundedSender<(NetworkData<D>, DataCommand)>,
     commands_from_user: mpsc::UnboundedReceiver<SessionCommand<D>>,
     messages_from_user: mpsc::UnboundedReceiver<(D, SessionId, Recipient)>,
-    messages_from_network: mpsc::UnboundedReceiver<NetworkData<D>>,
+    messages_from_network: mpsc::UnboundedReceiver<(NetworkData<D>, PeerId)>,
+    status: NetworkStatusHandle,
 }
 
 /// Errors that can happen during the network service operations.
@@ -548,7 +776,8 @@ impl<D: Data> IO<D> {
         messages_for_network: mpsc::UnboundedSender<(NetworkData<D>, DataCommand)>,
         commands_from_user: mpsc::UnboundedReceiver<SessionCommand<D>>,
         messages_from_user: mpsc::UnboundedReceiver<(D, SessionId, Recipient)>,
-        messages_from_network: mpsc::UnboundedReceiver<NetworkData<D>>,
+        messages_from_network: mpsc::UnboundedReceiver<(NetworkData<D>, PeerId)>,
+        status: NetworkStatusHandle,
     ) -> IO<D> {
         IO {
             commands_for_network,
@@ -556,6 +785,7 @@ impl<D: Data> IO<D> {
             commands_from_user,
             messages_from_user,
             messages_from_network,
+            status,
         }
     }
 
@@ -587,15 +817,59 @@ impl<D: Data> IO<D> {
         Ok(())
     }
 
+    fn report_misbehavior(&self, peer_id: PeerId, misbehavior: Misbehavior) -> Result<(), Error> {
+        self.send_command(ConnectionCommand::ReportMisbehavior(peer_id, misbehavior))
+    }
+
     fn on_network_message<NI: NetworkIdentity>(
         &self,
         service: &mut Service<NI, D>,
         message: NetworkData<D>,
+        peer_id: PeerId,
     ) -> Result<(), Error> {
         use NetworkData::*;
         match message {
-            Meta(message) => self.send(service.on_discovery_message(message)),
-            Data(data, session_id) => service.send_session_data(&session_id, data),
+            Meta(message) => {
+                if let Some(misbehavior) = service.discovery_misbehavior(&message) {
+                    self.report_misbehavior(peer_id, misbehavior)?;
+                }
+                if let Some(to_send) = service.relay_authentication(&message, peer_id) {
+                    self.send_data(to_send)?;
+                }
+                self.send(service.on_discovery_message(message))?;
+                self.status.update_sessions(service.status());
+                Ok(())
+            }
+            Data(data, session_id) => {
+                if service.relay_target(&session_id).is_some() {
+                    return match service.relay_to_validator(data, session_id, peer_id) {
+                        Ok(to_send) => self.send_data(to_send),
+                        Err(misbehavior) => self.report_misbehavior(peer_id, misbehavior),
+                    };
+                }
+                let sender = service.session_member(&session_id, &peer_id);
+                match service.send_session_data(&session_id, data, sender) {
+                    Err(Error::NoSession) => {
+                        if service.is_unexpected_session(&session_id) {
+                            self.report_misbehavior(peer_id, Misbehavior::UnknownSession)?;
+                        }
+                        Err(Error::NoSession)
+                    }
+                    result => result,
+                }
+            }
+            Relayed(data, session_id, sender) => {
+                if !service.is_own_sentry(&peer_id) {
+                    return self.report_misbehavior(peer_id, Misbehavior::UnauthorizedRelay);
+                }
+                service.send_session_data(&session_id, data, Some(sender))
+            }
+            Relay(data, session_id, recipient) => {
+                match service.relay_from_validator(data, session_id, recipient, peer_id) {
+                    Ok(to_send) => self.send_data(to_send),
+                    Err(misbehavior) => self.report_misbehavior(peer_id, misbehavior),
+                }
+            }
         }
     }
 
@@ -605,15 +879,21 @@ impl<D: Data> IO<D> {
         mut service: Service<NI, D>,
     ) -> Result<(), Error> {
         let mut maintenance = interval(service.maintenance_period);
+        if let Some(command) = service.sentry_link() {
+            self.send_command(command)?;
+        }
         loop {
             trace!(target: "aleph-network", "Manager Loop started a next iteration");
             tokio::select! {
                 maybe_command = self.commands_from_user.next() => {
                     trace!(target: "aleph-network", "Manager received a command from user");
                     match maybe_command {
-                        Some(command) => match service.on_command(command).await {
-                            Ok(to_send) => self.send(to_send)?,
-                            Err(e) => warn!(target: "aleph-network", "Failed to update handler: {:?}", e),
+                        Some(command) => {
+                            match service.on_command(command).await {
+                                Ok(to_send) => self.send(to_send)?,
+                                Err(e) => warn!(target: "aleph-network", "Failed to update handler: {:?}", e),
+                            }
+                            self.status.update_sessions(service.status());
                         },
                         None => return Err(Error::CommandsChannel),
                     }
@@ -630,7 +910,7 @@ impl<D: Data> IO<D> {
                 maybe_message = self.messages_from_network.next() => {
                     trace!(target: "aleph-network", "Manager received a message from network");
                     match maybe_message {
-                        Some(message) => if let Err(e) = self.on_network_message(&mut service, message) {
+                        Some((message, peer_id)) => if let Err(e) = self.on_network_message(&mut service, message, peer_id) {
                             match e {
                                 Error::UserSend => trace!(target: "aleph-network", "Failed to send to user in session."),
                                 Error::NoSession => trace!(target: "aleph-network", "Received message for unknown session."),
@@ -649,6 +929,7 @@ impl<D: Data> IO<D> {
                     for to_send in service.discovery() {
                         self.send_data(to_send)?;
                     }
+                    self.status.update_sessions(service.status());
                 },
             }
         }
@@ -657,19 +938,23 @@ impl<D: Data> IO<D> {
 
 #[cfg(test)]
 mod tests {
-    use super::{Config, Error, Service, SessionCommand};
+    use super::{Config, Error, Service, SessionCommand, IO};
     use crate::{
         network::{
             manager::{
                 testing::{crypto_basics, MockNetworkIdentity},
-                DiscoveryMessage, NetworkData,
+                DiscoveryMessage, NetworkData, SentryRole,
             },
-            ConnectionCommand, DataCommand, Protocol,
+            status::NetworkStatusHandle,
+            ConnectionCommand, DataCommand, Misbehavior, NetworkIdentity, Protocol,
         },
-        SessionId,
+        NodeIndex, SessionId,
     };
     use aleph_bft::Recipient;
-    use futures::{channel::oneshot, StreamExt};
+    use futures::{
+        channel::{mpsc, oneshot},
+        StreamExt,
+    };
     use std::time::Duration;
 
     const NUM_NODES: usize = 7;
@@ -683,6 +968,39 @@ mod tests {
         )
     }
 
+    fn build_with_sentry_role(
+        network_identity: MockNetworkIdentity,
+        sentry_role: SentryRole,
+    ) -> Service<MockNetworkIdentity, i32> {
+        Service::new(
+            network_identity,
+            Config::new(MAINTENANCE_PERIOD, DISCOVERY_PERIOD).with_sentry_role(sentry_role),
+        )
+    }
+
+    type IOHandles = (
+        IO<i32>,
+        mpsc::UnboundedReceiver<ConnectionCommand>,
+        mpsc::UnboundedReceiver<(NetworkData<i32>, DataCommand)>,
+    );
+
+    fn build_io() -> IOHandles {
+        let (commands_for_network, commands_from_io) = mpsc::unbounded();
+        let (messages_for_network, messages_from_io) = mpsc::unbounded();
+        let (_, commands_from_user) = mpsc::unbounded();
+        let (_, messages_from_user) = mpsc::unbounded();
+        let (_, messages_from_network) = mpsc::unbounded();
+        let io = IO::new(
+            commands_for_network,
+            messages_for_network,
+            commands_from_user,
+            messages_from_user,
+            messages_from_network,
+            NetworkStatusHandle::default(),
+        );
+        (io, commands_from_io, messages_from_io)
+    }
+
     #[tokio::test]
     async fn starts_nonvalidator_session() {
         let mut service = build();
@@ -695,7 +1013,7 @@ mod tests {
         assert!(maybe_command.is_none());
         assert!(data_commands.is_empty());
         assert_eq!(
-            service.send_session_data(&session_id, -43),
+            service.send_session_data(&session_id, -43, None),
             Err(Error::NoSession)
         );
     }
@@ -723,7 +1041,7 @@ mod tests {
             .iter()
             .all(|(_, command)| command == &DataCommand::Broadcast));
         let _data_from_network = result_from_service.await.unwrap();
-        assert_eq!(service.send_session_data(&session_id, -43), Ok(()));
+        assert_eq!(service.send_session_data(&session_id, -43, None), Ok(()));
     }
 
     #[tokio::test]
@@ -748,9 +1066,9 @@ mod tests {
         assert!(data_commands
             .iter()
             .all(|(_, command)| command == &DataCommand::Broadcast));
-        assert_eq!(service.send_session_data(&session_id, -43), Ok(()));
+        assert_eq!(service.send_session_data(&session_id, -43, None), Ok(()));
         let mut data_from_network = result_from_service.await.unwrap();
-        assert_eq!(data_from_network.next().await, Some(-43));
+        assert_eq!(data_from_network.next().await, Some((-43, None)));
         let (maybe_command, data_commands) = service
             .on_command(SessionCommand::Stop(session_id))
             .await
@@ -758,7 +1076,7 @@ mod tests {
         assert!(maybe_command.is_none());
         assert!(data_commands.is_empty());
         assert_eq!(
-            service.send_session_data(&session_id, -43),
+            service.send_session_data(&session_id, -43, None),
             Err(Error::NoSession)
         );
         assert!(data_from_network.next().await.is_none());
@@ -815,6 +1133,82 @@ mod tests {
             .any(|(_, command)| matches!(command, &DataCommand::SendTo(_, _))));
     }
 
+    #[tokio::test]
+    async fn detects_discovery_misbehavior() {
+        let mut service = build();
+        let (validator_data, verifier) = crypto_basics(NUM_NODES).await;
+        let (node_id, pen) = validator_data[0].clone();
+        let session_id = SessionId(43);
+        let (_, own_data_commands) = service
+            .on_command(SessionCommand::StartValidator(
+                session_id,
+                verifier.clone(),
+                node_id,
+                pen,
+                None,
+            ))
+            .await
+            .unwrap();
+        let mut other_service = build();
+        let (node_id, pen) = validator_data[1].clone();
+        let (_, data_commands) = other_service
+            .on_command(SessionCommand::StartValidator(
+                session_id, verifier, node_id, pen, None,
+            ))
+            .await
+            .unwrap();
+        let authentication = match data_commands[0].clone() {
+            (NetworkData::Meta(DiscoveryMessage::AuthenticationBroadcast(authentication)), _) => {
+                authentication
+            }
+            _ => panic!(
+                "Expected an authentication broadcast, got: {:?}",
+                data_commands[0]
+            ),
+        };
+        let own_signature = match own_data_commands[0].clone() {
+            (NetworkData::Meta(DiscoveryMessage::AuthenticationBroadcast((_, signature))), _) => {
+                signature
+            }
+            _ => panic!(
+                "Expected an authentication broadcast, got: {:?}",
+                own_data_commands[0]
+            ),
+        };
+        let broadcast = DiscoveryMessage::AuthenticationBroadcast(authentication.clone());
+        assert_eq!(service.discovery_misbehavior(&broadcast), None);
+        assert_eq!(build().discovery_misbehavior(&broadcast), None);
+        let forged = DiscoveryMessage::Authentication((authentication.0, own_signature));
+        assert_eq!(
+            service.discovery_misbehavior(&forged),
+            Some(Misbehavior::InvalidAuthentication)
+        );
+    }
+
+    #[tokio::test]
+    async fn tolerates_sessions_adjacent_to_handled_ones() {
+        let mut service = build();
+        assert!(!service.is_unexpected_session(&SessionId(43)));
+        let (validator_data, verifier) = crypto_basics(NUM_NODES).await;
+        let (node_id, pen) = validator_data[0].clone();
+        service
+            .on_command(SessionCommand::StartValidator(
+                SessionId(43),
+                verifier,
+                node_id,
+                pen,
+                None,
+            ))
+            .await
+            .unwrap();
+        for session_id in [42, 43, 44] {
+            assert!(!service.is_unexpected_session(&SessionId(session_id)));
+        }
+        for session_id in [0, 41, 45] {
+            assert!(service.is_unexpected_session(&SessionId(session_id)));
+        }
+    }
+
     #[tokio::test]
     async fn sends_user_data() {
         let mut service = build();
@@ -856,4 +1250,288 @@ mod tests {
         ));
         assert_eq!(network_data, &NetworkData::Data(2137, session_id));
     }
+
+    #[tokio::test]
+    async fn protected_validator_sends_everything_through_sentry() {
+        let (sentry_addresses, sentry_peer_id) = MockNetworkIdentity::new().identity();
+        let mut service = build_with_sentry_role(
+            MockNetworkIdentity::new(),
+            SentryRole::protected(sentry_addresses.clone()).unwrap(),
+        );
+        assert_eq!(
+            service.sentry_link(),
+            Some(ConnectionCommand::AddReserved(
+                sentry_addresses.iter().cloned().collect()
+            ))
+        );
+        let (validator_data, verifier) = crypto_basics(NUM_NODES).await;
+        let (node_id, pen) = validator_data[0].clone();
+        let session_id = SessionId(43);
+        let (_, data_commands) = service
+            .on_command(SessionCommand::StartValidator(
+                session_id,
+                verifier.clone(),
+                node_id,
+                pen,
+                None,
+            ))
+            .await
+            .unwrap();
+        match &data_commands[0] {
+            (
+                NetworkData::Meta(DiscoveryMessage::AuthenticationBroadcast((auth_data, _))),
+                DataCommand::Broadcast,
+            ) => assert_eq!(
+                auth_data
+                    .addresses()
+                    .into_iter()
+                    .map(|address| address.0)
+                    .collect::<Vec<_>>(),
+                sentry_addresses
+            ),
+            _ => panic!(
+                "Expected an authentication broadcast, got: {:?}",
+                data_commands[0]
+            ),
+        };
+        let mut other_service = build();
+        let (node_id, pen) = validator_data[1].clone();
+        let (_, data_commands) = other_service
+            .on_command(SessionCommand::StartValidator(
+                session_id, verifier, node_id, pen, None,
+            ))
+            .await
+            .unwrap();
+        let broadcast = match data_commands[0].clone() {
+            (NetworkData::Meta(broadcast), DataCommand::Broadcast) => broadcast,
+            _ => panic!(
+                "Expected discovery massage broadcast, got: {:?}",
+                data_commands[0]
+            ),
+        };
+        let (maybe_command, _) = service.on_discovery_message(broadcast);
+        assert!(maybe_command.is_none());
+        let messages = service.on_user_message(2137, session_id, Recipient::Everyone);
+        assert_eq!(messages.len(), 1);
+        match &messages[0] {
+            (
+                NetworkData::Relay(2137, relay_session_id, _),
+                DataCommand::SendTo(peer_id, Protocol::Validator),
+            ) => {
+                assert_eq!(relay_session_id, &session_id);
+                assert_eq!(peer_id, &sentry_peer_id);
+            }
+            _ => panic!("Expected data to relay, got: {:?}", messages[0]),
+        }
+    }
+
+    #[tokio::test]
+    async fn sentry_relays_only_for_protected_validator() {
+        let validator_identity = MockNetworkIdentity::new();
+        let (validator_addresses, validator_peer_id) = validator_identity.identity();
+        let sentry_identity = MockNetworkIdentity::new();
+        let (sentry_addresses, _) = sentry_identity.identity();
+        let mut sentry = build_with_sentry_role(
+            sentry_identity,
+            SentryRole::sentry(validator_addresses).unwrap(),
+        );
+        let mut validator = build_with_sentry_role(
+            validator_identity,
+            SentryRole::protected(sentry_addresses).unwrap(),
+        );
+        let (validator_data, verifier) = crypto_basics(NUM_NODES).await;
+        let session_id = SessionId(43);
+        sentry
+            .on_command(SessionCommand::StartSentry(session_id, verifier.clone()))
+            .await
+            .unwrap();
+        assert_eq!(sentry.relay_target(&session_id), Some(validator_peer_id));
+
+        let (node_id, pen) = validator_data[0].clone();
+        let (_, data_commands) = validator
+            .on_command(SessionCommand::StartValidator(
+                session_id,
+                verifier.clone(),
+                node_id,
+                pen,
+                None,
+            ))
+            .await
+            .unwrap();
+        let broadcast = match data_commands[0].clone() {
+            (NetworkData::Meta(broadcast), DataCommand::Broadcast) => broadcast,
+            _ => panic!(
+                "Expected discovery massage broadcast, got: {:?}",
+                data_commands[0]
+            ),
+        };
+        let (_, other_peer_id) = MockNetworkIdentity::new().identity();
+        assert_eq!(
+            sentry.relay_authentication(&broadcast, validator_peer_id),
+            Some((NetworkData::Meta(broadcast.clone()), DataCommand::Broadcast))
+        );
+        assert!(sentry
+            .relay_authentication(&broadcast, other_peer_id)
+            .is_none());
+
+        assert_eq!(
+            sentry.relay_from_validator(2137, session_id, other_peer_id, validator_peer_id),
+            Ok((
+                NetworkData::Data(2137, session_id),
+                DataCommand::SendTo(other_peer_id, Protocol::Validator)
+            ))
+        );
+        assert_eq!(
+            sentry.relay_from_validator(2137, session_id, validator_peer_id, other_peer_id),
+            Err(Misbehavior::UnauthorizedRelay)
+        );
+
+        let mut other_service = build();
+        let (node_id, pen) = validator_data[1].clone();
+        let (_, data_commands) = other_service
+            .on_command(SessionCommand::StartValidator(
+                session_id, verifier, node_id, pen, None,
+            ))
+            .await
+            .unwrap();
+        let broadcast = match data_commands[0].clone() {
+            (NetworkData::Meta(broadcast), DataCommand::Broadcast) => broadcast,
+            _ => panic!(
+                "Expected discovery massage broadcast, got: {:?}",
+                data_commands[0]
+            ),
+        };
+        let (maybe_command, _) = sentry.on_discovery_message(broadcast);
+        assert!(matches!(
+            maybe_command,
+            Some(ConnectionCommand::AddReserved(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn sentry_relays_data_only_from_authenticated_peers() {
+        let (validator_addresses, validator_peer_id) = MockNetworkIdentity::new().identity();
+        let mut sentry = build_with_sentry_role(
+            MockNetworkIdentity::new(),
+            SentryRole::sentry(validator_addresses).unwrap(),
+        );
+        let (validator_data, verifier) = crypto_basics(NUM_NODES).await;
+        let session_id = SessionId(43);
+        sentry
+            .on_command(SessionCommand::StartSentry(session_id, verifier.clone()))
+            .await
+            .unwrap();
+
+        let authority_identity = MockNetworkIdentity::new();
+        let (_, authority_peer_id) = authority_identity.identity();
+        let mut authority = Service::new(
+            authority_identity,
+            Config::new(MAINTENANCE_PERIOD, DISCOVERY_PERIOD),
+        );
+        let (node_id, pen) = validator_data[1].clone();
+        let (_, data_commands) = authority
+            .on_command(SessionCommand::StartValidator(
+                session_id, verifier, node_id, pen, None,
+            ))
+            .await
+            .unwrap();
+        let broadcast = match data_commands[0].clone() {
+            (NetworkData::Meta(broadcast), DataCommand::Broadcast) => broadcast,
+            _ => panic!(
+                "Expected discovery massage broadcast, got: {:?}",
+                data_commands[0]
+            ),
+        };
+
+        let (io, mut commands_from_io, mut messages_from_io) = build_io();
+        io.on_network_message(&mut sentry, NetworkData::Meta(broadcast), authority_peer_id)
+            .unwrap();
+        assert!(matches!(
+            commands_from_io.try_next(),
+            Ok(Some(ConnectionCommand::AddReserved(_)))
+        ));
+        while let Ok(Some(_)) = messages_from_io.try_next() {}
+
+        io.on_network_message(
+            &mut sentry,
+            NetworkData::Data(2137, session_id),
+            authority_peer_id,
+        )
+        .unwrap();
+        assert_eq!(
+            messages_from_io.try_next().unwrap(),
+            Some((
+                NetworkData::Relayed(2137, session_id, node_id),
+                DataCommand::SendTo(validator_peer_id, Protocol::Validator)
+            ))
+        );
+
+        let (_, unknown_peer_id) = MockNetworkIdentity::new().identity();
+        io.on_network_message(
+            &mut sentry,
+            NetworkData::Data(2137, session_id),
+            unknown_peer_id,
+        )
+        .unwrap();
+        assert!(messages_from_io.try_next().is_err());
+        assert_eq!(
+            commands_from_io.try_next().unwrap(),
+            Some(ConnectionCommand::ReportMisbehavior(
+                unknown_peer_id,
+                Misbehavior::UnauthorizedRelay
+            ))
+        );
+    }
+
+    #[tokio::test]
+    async fn protected_validator_accepts_relayed_data_only_from_sentry() {
+        let (sentry_addresses, sentry_peer_id) = MockNetworkIdentity::new().identity();
+        let mut validator = build_with_sentry_role(
+            MockNetworkIdentity::new(),
+            SentryRole::protected(sentry_addresses).unwrap(),
+        );
+        let (validator_data, verifier) = crypto_basics(NUM_NODES).await;
+        let (node_id, pen) = validator_data[0].clone();
+        let session_id = SessionId(43);
+        let (result_for_user, result_from_service) = oneshot::channel();
+        validator
+            .on_command(SessionCommand::StartValidator(
+                session_id,
+                verifier,
+                node_id,
+                pen,
+                Some(result_for_user),
+            ))
+            .await
+            .unwrap();
+        let mut data_from_network = result_from_service.await.unwrap();
+
+        let (io, mut commands_from_io, _messages_from_io) = build_io();
+        io.on_network_message(
+            &mut validator,
+            NetworkData::Relayed(2137, session_id, NodeIndex(1)),
+            sentry_peer_id,
+        )
+        .unwrap();
+        assert_eq!(
+            data_from_network.try_next().unwrap(),
+            Some((2137, Some(NodeIndex(1))))
+        );
+
+        let (_, other_peer_id) = MockNetworkIdentity::new().identity();
+        io.on_network_message(
+            &mut validator,
+            NetworkData::Relayed(2137, session_id, NodeIndex(1)),
+            other_peer_id,
+        )
+        .unwrap();
+        assert!(data_from_network.try_next().is_err());
+        assert_eq!(
+            commands_from_io.try_next().unwrap(),
+            Some(ConnectionCommand::ReportMisbehavior(
+                other_peer_id,
+                Misbehavior::UnauthorizedRelay
+            ))
+        );
+    }
 }
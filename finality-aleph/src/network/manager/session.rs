@@ -1,7 +1,8 @@
 use crate::{
     crypto::{AuthorityPen, AuthorityVerifier},
     network::{
-        manager::{get_common_peer_id, is_p2p, AuthData, Authentication, Multiaddr},
+        manager::{are_sane, get_common_peer_id, is_p2p, AuthData, Authentication, Multiaddr},
+        status::{AuthorityStatus, SessionStatus},
         PeerId,
     },
     NodeIndex, SessionId,
@@ -137,6 +138,18 @@ impl Handler {
             .collect()
     }
 
+    /// Checks whether the authentication is for this session, has sane addresses with a consistent
+    /// PeerId and is correctly signed. Does not update any mappings.
+    pub fn verify_authentication(&self, authentication: &Authentication) -> bool {
+        let (auth_data, signature) = authentication;
+        auth_data.session_id == self.session_id()
+            && are_sane(&auth_data.addresses)
+            && get_common_peer_id(&auth_data.addresses).is_some()
+            && self
+                .authority_verifier
+                .verify(&auth_data.encode(), signature, auth_data.node_id)
+    }
+
     /// Verifies the authentication, uses it to update mappings, and returns whether we should
     /// remain connected to the multiaddresses.
     pub fn handle_authentication(&mut self, authentication: Authentication) -> bool {
@@ -145,6 +158,11 @@ impl Handler {
         }
         let (auth_data, signature) = &authentication;
 
+        // We don't want to waste connection attempts on garbage addresses.
+        if !are_sane(&auth_data.addresses) {
+            return false;
+        }
+
         // The auth is completely useless if it doesn't have a consistent PeerId.
         let peer_id = match get_common_peer_id(&auth_data.addresses) {
             Some(peer_id) => peer_id,
@@ -174,6 +192,37 @@ impl Handler {
         self.peers_by_node.get(node_id).copied()
     }
 
+    /// Returns the NodeIndex of the authority the peer authenticated as, if any.
+    pub fn node_id(&self, peer_id: &PeerId) -> Option<NodeIndex> {
+        self.peers_by_node
+            .iter()
+            .find(|(_, known)| *known == peer_id)
+            .map(|(node_id, _)| *node_id)
+    }
+
+    /// Returns a summary of what we know about the authorities of this session.
+    pub fn status(&self) -> SessionStatus {
+        let mut known_authorities: Vec<_> = self
+            .peers_by_node
+            .iter()
+            .map(|(node_id, peer_id)| {
+                let addresses = self
+                    .authentications
+                    .get(peer_id)
+                    .map(|((auth_data, _), _)| auth_data.addresses())
+                    .unwrap_or_default();
+                AuthorityStatus::new(*node_id, *peer_id, addresses)
+            })
+            .collect();
+        known_authorities.sort_by_key(|authority| authority.node_index);
+        SessionStatus::new(
+            self.session_id(),
+            self.index(),
+            known_authorities,
+            self.missing_nodes(),
+        )
+    }
+
     /// Updates the handler with the given keychain and set of own addresses.
     /// Returns an error if the set of addresses is not valid.
     /// All authentications will be rechecked, invalid ones purged and cached ones that turn out to
@@ -453,6 +502,44 @@ mod tests {
         assert_eq!(handler0.peer_id(&NodeIndex(1)), peer_id1);
     }
 
+    #[tokio::test]
+    async fn reports_status_of_known_authorities() {
+        let crypto_basics = crypto_basics(NUM_NODES).await;
+        let mut handler0 = Handler::new(
+            Some(crypto_basics.0[0].clone()),
+            crypto_basics.1.clone(),
+            SessionId(43),
+            correct_addresses_0(),
+        )
+        .await
+        .unwrap();
+        let handler1 = Handler::new(
+            Some(crypto_basics.0[1].clone()),
+            crypto_basics.1.clone(),
+            SessionId(43),
+            correct_addresses_1(),
+        )
+        .await
+        .unwrap();
+        assert!(handler0.handle_authentication(handler1.authentication().unwrap()));
+        let status = handler0.status();
+        assert_eq!(status.session_id, 43);
+        assert_eq!(status.node_index, Some(0));
+        assert_eq!(status.known_authorities.len(), 1);
+        let authority = &status.known_authorities[0];
+        assert_eq!(authority.node_index, 1);
+        assert_eq!(
+            authority.peer_id,
+            get_common_peer_id(&correct_addresses_1())
+                .unwrap()
+                .0
+                .to_base58()
+        );
+        assert_eq!(authority.addresses.len(), correct_addresses_1().len());
+        let expected_missing: Vec<_> = (2..NUM_NODES).collect();
+        assert_eq!(status.missing_authorities, expected_missing);
+    }
+
     #[tokio::test]
     async fn non_validator_accepts_correct_authentication() {
         let crypto_basics = crypto_basics(NUM_NODES).await;
@@ -508,6 +595,66 @@ mod tests {
         assert_eq!(missing_nodes, expected_missing);
     }
 
+    #[tokio::test]
+    async fn ignores_authentication_with_garbage_addresses() {
+        let crypto_basics = crypto_basics(NUM_NODES).await;
+        let mut handler0 = Handler::new(
+            Some(crypto_basics.0[0].clone()),
+            crypto_basics.1.clone(),
+            SessionId(43),
+            correct_addresses_0(),
+        )
+        .await
+        .unwrap();
+        let handler1 = Handler::new(
+            Some(crypto_basics.0[1].clone()),
+            crypto_basics.1.clone(),
+            SessionId(43),
+            vec![address(
+                "/ip4/0.0.0.0/tcp/30333/p2p/12D3KooWFVXnvJdPuGnGYMPn5qLQAQYwmRBgo6SmEQsKZSrDoo2k",
+            )
+            .into()],
+        )
+        .await
+        .unwrap();
+        let authentication = handler1.authentication().unwrap();
+        assert!(!handler0.verify_authentication(&authentication));
+        assert!(!handler0.handle_authentication(authentication));
+        let missing_nodes = handler0.missing_nodes();
+        let expected_missing: Vec<_> = (1..NUM_NODES).map(NodeIndex).collect();
+        assert_eq!(missing_nodes, expected_missing);
+    }
+
+    #[tokio::test]
+    async fn verifies_authentications_without_using_them() {
+        let crypto_basics = crypto_basics(NUM_NODES).await;
+        let handler0 = Handler::new(
+            Some(crypto_basics.0[0].clone()),
+            crypto_basics.1.clone(),
+            SessionId(43),
+            correct_addresses_0(),
+        )
+        .await
+        .unwrap();
+        let handler1 = Handler::new(
+            Some(crypto_basics.0[1].clone()),
+            crypto_basics.1.clone(),
+            SessionId(43),
+            correct_addresses_1(),
+        )
+        .await
+        .unwrap();
+        let authentication = handler1.authentication().unwrap();
+        assert!(handler0.verify_authentication(&authentication));
+        assert!(handler0.verify_authentication(&handler0.authentication().unwrap()));
+        let mut badly_signed_authentication = authentication;
+        badly_signed_authentication.1 = handler0.authentication().unwrap().1;
+        assert!(!handler0.verify_authentication(&badly_signed_authentication));
+        let missing_nodes = handler0.missing_nodes();
+        let expected_missing: Vec<_> = (1..NUM_NODES).map(NodeIndex).collect();
+        assert_eq!(missing_nodes, expected_missing);
+    }
+
     #[tokio::test]
     async fn ignores_wrong_session_authentication() {
         let crypto_basics = crypto_basics(NUM_NODES).await;
@@ -1,5 +1,6 @@
 use crate::network::{
-    ConnectionCommand, Data, DataCommand, Network, NetworkEventStream, NetworkSender, PeerId, IO,
+    ConnectionCommand, Data, DataCommand, Network, NetworkEventStream, NetworkSender,
+    NetworkStatusHandle, PeerId, IO,
 };
 use async_trait::async_trait;
 use futures::{
@@ -7,7 +8,7 @@ use futures::{
     StreamExt,
 };
 use parking_lot::Mutex;
-use sc_network::{Event, Multiaddr};
+use sc_network::{Event, Multiaddr, ReputationChange};
 use std::{
     borrow::Cow,
     collections::{HashSet, VecDeque},
@@ -47,7 +48,7 @@ impl<T> Channel<T> {
 
 pub struct MockIO<D: Data> {
     pub messages_for_user: mpsc::UnboundedSender<(D, DataCommand)>,
-    pub messages_from_user: mpsc::UnboundedReceiver<D>,
+    pub messages_from_user: mpsc::UnboundedReceiver<(D, PeerId)>,
     pub commands_for_manager: mpsc::UnboundedSender<ConnectionCommand>,
 }
 
@@ -62,7 +63,12 @@ impl<D: Data> MockIO<D> {
                 messages_from_user: mock_messages_from_user,
                 commands_for_manager: mock_commands_for_manager,
             },
-            IO::new(messages_from_user, messages_for_user, commands_from_manager),
+            IO::new(
+                messages_from_user,
+                messages_for_user,
+                commands_from_manager,
+                NetworkStatusHandle::default(),
+            ),
         )
     }
 }
@@ -98,6 +104,7 @@ impl<D: Data> NetworkSender for MockNetworkSender<D> {
 pub struct MockNetwork<D: Data> {
     pub add_reserved: Channel<(HashSet<Multiaddr>, Cow<'static, str>)>,
     pub remove_reserved: Channel<(HashSet<PeerId>, Cow<'static, str>)>,
+    pub report_peer: Channel<(PeerId, ReputationChange)>,
     pub send_message: Channel<(D, PeerId, Cow<'static, str>)>,
     pub event_sinks: Arc<Mutex<Vec<mpsc::UnboundedSender<Event>>>>,
     event_stream_taken_oneshot: Arc<Mutex<Option<oneshot::Sender<()>>>>,
@@ -161,6 +168,10 @@ impl<D: Data> Network for MockNetwork<D> {
     fn remove_reserved(&self, peers: HashSet<PeerId>, protocol: Cow<'static, str>) {
         self.remove_reserved.send((peers, protocol));
     }
+
+    fn report_peer(&self, peer_id: PeerId, change: ReputationChange) {
+        self.report_peer.send((peer_id, change));
+    }
 }
 
 impl<D: Data> MockNetwork<D> {
@@ -168,6 +179,7 @@ impl<D: Data> MockNetwork<D> {
         MockNetwork {
             add_reserved: Channel::new(),
             remove_reserved: Channel::new(),
+            report_peer: Channel::new(),
             send_message: Channel::new(),
             event_sinks: Arc::new(Mutex::new(vec![])),
             event_stream_taken_oneshot: Arc::new(Mutex::new(Some(oneshot_sender))),
@@ -187,6 +199,7 @@ impl<D: Data> MockNetwork<D> {
         self.event_sinks.lock().clear();
         assert!(self.add_reserved.close().await.is_none());
         assert!(self.remove_reserved.close().await.is_none());
+        assert!(self.report_peer.close().await.is_none());
         assert!(self.send_message.close().await.is_none());
     }
 }
@@ -1,8 +1,8 @@
-use aleph_bft::Recipient;
+use aleph_bft::{NodeIndex, Recipient};
 use async_trait::async_trait;
 use codec::{Codec, Decode, Encode};
 use futures::stream::Stream;
-use sc_network::{Event, Multiaddr, PeerId as ScPeerId};
+use sc_network::{Event, Multiaddr, PeerId as ScPeerId, ReputationChange};
 use sp_api::NumberFor;
 use sp_runtime::traits::Block;
 use std::{borrow::Cow, collections::HashSet, convert::TryFrom, pin::Pin};
@@ -12,9 +12,13 @@ mod component;
 mod manager;
 #[cfg(test)]
 mod mock;
+mod reputation;
 mod service;
 mod session;
+#[cfg(test)]
+mod simulation;
 mod split;
+mod status;
 mod substrate;
 
 use manager::SessionCommand;
@@ -24,10 +28,15 @@ pub use component::{
     Network as ComponentNetwork, Receiver as ReceiverComponent, Sender as SenderComponent,
     SimpleNetwork,
 };
-pub use manager::{get_peer_id, ConnectionIO, ConnectionManager, ConnectionManagerConfig};
+pub use manager::{
+    get_peer_id, AddressPolicy, ConnectionIO, ConnectionManager, ConnectionManagerConfig,
+    SentryRole, SentryRoleError,
+};
+pub use reputation::{Config as PeerReputationConfig, Misbehavior};
 pub use service::{Service, IO};
 pub use session::{Manager as SessionManager, ManagerError, Network as SessionNetwork};
 pub use split::{split, Split};
+pub use status::{AuthorityStatus, NetworkStatus, NetworkStatusHandle, SessionStatus};
 
 #[cfg(test)]
 pub mod testing {
@@ -37,6 +46,7 @@ pub mod testing {
             Authentication, DiscoveryMessage, NetworkData, SessionHandler,
         },
         mock::MockNetwork,
+        simulation::{LinkConfig, SimulatedNetwork, SimulatedNetworkHub, Topology},
     };
 }
 
@@ -144,6 +154,9 @@ pub trait Network: Clone + Send + Sync + 'static {
 
     /// Remove peers from one of the reserved sets.
     fn remove_reserved(&self, peers: HashSet<PeerId>, protocol: Cow<'static, str>);
+
+    /// Report a change of the reputation of the peer.
+    fn report_peer(&self, peer_id: PeerId, change: ReputationChange);
 }
 
 /// Abstraction for requesting own network addresses and PeerId.
@@ -160,6 +173,12 @@ pub trait RequestBlocks<B: Block>: Clone + Send + Sync + 'static {
     /// Request the given block -- this is supposed to be used only for "old forks".
     fn request_stale_block(&self, hash: B::Hash, number: NumberFor<B>);
 
+    /// Request the given block, preferably from the given member of the committee, which referenced
+    /// it so presumably has it.
+    fn request_stale_block_from(&self, hash: B::Hash, number: NumberFor<B>, _member: NodeIndex) {
+        self.request_stale_block(hash, number)
+    }
+
     /// Clear all pending justification requests. We need this function in case
     /// we requested a justification for a block, which will never get it.
     fn clear_justification_requests(&self);
@@ -173,11 +192,12 @@ pub enum DataCommand {
     SendTo(PeerId, Protocol),
 }
 
-/// Commands for manipulating the reserved peers set.
+/// Commands for manipulating the reserved peers set and reporting misbehaving peers.
 #[derive(Debug, PartialEq)]
 pub enum ConnectionCommand {
     AddReserved(HashSet<Multiaddr>),
     DelReserved(HashSet<PeerId>),
+    ReportMisbehavior(PeerId, Misbehavior),
 }
 
 /// Returned when something went wrong when sending data using a DataNetwork.
@@ -0,0 +1,287 @@
+use crate::network::PeerId;
+use log::{debug, warn};
+use prometheus_endpoint::{register, CounterVec, Gauge, Opts, PrometheusError, Registry, U64};
+use sc_network::ReputationChange;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// Kinds of peer misbehavior we penalize on the Aleph protocols.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Misbehavior {
+    /// The peer sent data that could not be decoded.
+    UndecodableMessage,
+    /// The peer sent data for a session we know nothing about.
+    UnknownSession,
+    /// The peer sent an authentication that failed verification.
+    InvalidAuthentication,
+    /// The peer asked us to relay data, but we are not its sentry.
+    UnauthorizedRelay,
+}
+
+impl Misbehavior {
+    fn name(&self) -> &'static str {
+        use Misbehavior::*;
+        match self {
+            UndecodableMessage => "undecodable_message",
+            UnknownSession => "unknown_session",
+            InvalidAuthentication => "invalid_authentication",
+            UnauthorizedRelay => "unauthorized_relay",
+        }
+    }
+
+    /// The penalty we apply to the local score of the peer.
+    fn cost(&self) -> i32 {
+        use Misbehavior::*;
+        match self {
+            UndecodableMessage => 100,
+            // Might legitimately happen around session boundaries, so we are lenient.
+            UnknownSession => 5,
+            // Might legitimately happen when keys change, but we are not aware of it yet.
+            InvalidAuthentication => 50,
+            UnauthorizedRelay => 100,
+        }
+    }
+
+    /// The reputation change reported to the underlying network.
+    pub fn reputation_change(&self) -> ReputationChange {
+        use Misbehavior::*;
+        match self {
+            UndecodableMessage => ReputationChange::new(-(1 << 12), "Aleph: undecodable message"),
+            UnknownSession => ReputationChange::new(-(1 << 4), "Aleph: unknown session"),
+            InvalidAuthentication => {
+                ReputationChange::new(-(1 << 10), "Aleph: invalid authentication")
+            }
+            UnauthorizedRelay => ReputationChange::new(-(1 << 12), "Aleph: unauthorized relay"),
+        }
+    }
+}
+
+/// Configuration of the peer reputation system. Scores decay linearly over time, a peer is banned
+/// once its score exceeds the threshold.
+#[derive(Clone, Debug)]
+pub struct Config {
+    ban_threshold: i32,
+    ban_duration: Duration,
+    decay_per_minute: i32,
+}
+
+impl Config {
+    pub fn new(ban_threshold: i32, ban_duration: Duration, decay_per_minute: i32) -> Self {
+        Config {
+            ban_threshold,
+            ban_duration,
+            decay_per_minute,
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config::new(1000, Duration::from_secs(600), 50)
+    }
+}
+
+#[derive(Clone)]
+struct Metrics {
+    misbehaviors: CounterVec<U64>,
+    banned_peers: Gauge<U64>,
+}
+
+impl Metrics {
+    fn register(registry: &Registry) -> Result<Self, PrometheusError> {
+        Ok(Metrics {
+            misbehaviors: register(
+                CounterVec::new(
+                    Opts::new(
+                        "aleph_network_peer_misbehaviors",
+                        "Number of reported misbehaviors of peers on Aleph protocols",
+                    ),
+                    &["kind"],
+                )?,
+                registry,
+            )?,
+            banned_peers: register(
+                Gauge::new(
+                    "aleph_network_banned_peers",
+                    "Number of peers currently banned on Aleph protocols",
+                )?,
+                registry,
+            )?,
+        })
+    }
+}
+
+struct Score {
+    value: i32,
+    last_update: Instant,
+}
+
+/// Keeps local scores of peers misbehaving on the Aleph protocols and a list of peers banned
+/// because of that.
+pub struct PeerReputation {
+    config: Config,
+    scores: HashMap<PeerId, Score>,
+    banned: HashMap<PeerId, Instant>,
+    metrics: Option<Metrics>,
+}
+
+impl PeerReputation {
+    pub fn new(config: Config, registry: Option<Registry>) -> Self {
+        let metrics = registry.and_then(|registry| match Metrics::register(&registry) {
+            Ok(metrics) => Some(metrics),
+            Err(e) => {
+                warn!(target: "aleph-network", "Failed to register peer reputation metrics: {:?}", e);
+                None
+            }
+        });
+        PeerReputation {
+            config,
+            scores: HashMap::new(),
+            banned: HashMap::new(),
+            metrics,
+        }
+    }
+
+    // Only whole minutes decay the score, the rest of the elapsed time is carried forward.
+    fn decayed(&self, score: &Score, now: Instant) -> Score {
+        let minutes = now.saturating_duration_since(score.last_update).as_secs() / 60;
+        let decay = (minutes as i32).saturating_mul(self.config.decay_per_minute);
+        Score {
+            value: score.value.saturating_sub(decay).max(0),
+            last_update: score.last_update + Duration::from_secs(minutes * 60),
+        }
+    }
+
+    fn update_banned_metric(&self) {
+        if let Some(metrics) = &self.metrics {
+            metrics.banned_peers.set(self.banned.len() as u64);
+        }
+    }
+
+    /// Records the misbehavior of the peer. Returns whether the peer got banned because of it.
+    pub fn report(&mut self, peer_id: PeerId, misbehavior: Misbehavior) -> bool {
+        self.report_at(peer_id, misbehavior, Instant::now())
+    }
+
+    fn report_at(&mut self, peer_id: PeerId, misbehavior: Misbehavior, now: Instant) -> bool {
+        debug!(target: "aleph-network", "Peer {:?} misbehaved: {:?}", peer_id, misbehavior);
+        if let Some(metrics) = &self.metrics {
+            metrics
+                .misbehaviors
+                .with_label_values(&[misbehavior.name()])
+                .inc();
+        }
+        let score = match self.scores.get(&peer_id) {
+            Some(score) => self.decayed(score, now),
+            None => Score {
+                value: 0,
+                last_update: now,
+            },
+        };
+        let value = score.value.saturating_add(misbehavior.cost());
+        if value < self.config.ban_threshold {
+            self.scores.insert(
+                peer_id,
+                Score {
+                    value,
+                    last_update: score.last_update,
+                },
+            );
+            return false;
+        }
+        self.scores.remove(&peer_id);
+        let newly_banned = self
+            .banned
+            .insert(peer_id, now + self.config.ban_duration)
+            .is_none();
+        if newly_banned {
+            warn!(target: "aleph-network", "Banning peer {:?} for {:?}.", peer_id, self.config.ban_duration);
+        }
+        self.update_banned_metric();
+        newly_banned
+    }
+
+    /// Checks whether the peer is currently banned, lifting expired bans.
+    pub fn is_banned(&mut self, peer_id: &PeerId) -> bool {
+        match self.banned.get(peer_id) {
+            Some(until) if Instant::now() < *until => true,
+            Some(_) => {
+                debug!(target: "aleph-network", "Ban of peer {:?} expired.", peer_id);
+                self.banned.remove(peer_id);
+                self.update_banned_metric();
+                false
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Config, Misbehavior, PeerReputation};
+    use crate::network::PeerId;
+    use sc_network::PeerId as ScPeerId;
+    use std::{
+        thread::sleep,
+        time::{Duration, Instant},
+    };
+
+    fn reputation(ban_threshold: i32, ban_duration: Duration) -> PeerReputation {
+        PeerReputation::new(Config::new(ban_threshold, ban_duration, 0), None)
+    }
+
+    #[test]
+    fn does_not_ban_below_threshold() {
+        let mut reputation = reputation(1000, Duration::from_secs(60));
+        let peer_id = PeerId(ScPeerId::random());
+        assert!(!reputation.report(peer_id, Misbehavior::InvalidAuthentication));
+        assert!(!reputation.is_banned(&peer_id));
+    }
+
+    #[test]
+    fn bans_after_repeated_misbehavior() {
+        let mut reputation = reputation(250, Duration::from_secs(60));
+        let peer_id = PeerId(ScPeerId::random());
+        let other_peer_id = PeerId(ScPeerId::random());
+        assert!(!reputation.report(peer_id, Misbehavior::UndecodableMessage));
+        assert!(!reputation.report(peer_id, Misbehavior::UndecodableMessage));
+        assert!(reputation.report(peer_id, Misbehavior::UndecodableMessage));
+        assert!(reputation.is_banned(&peer_id));
+        assert!(!reputation.is_banned(&other_peer_id));
+    }
+
+    #[test]
+    fn does_not_report_ban_twice() {
+        let mut reputation = reputation(100, Duration::from_secs(60));
+        let peer_id = PeerId(ScPeerId::random());
+        assert!(reputation.report(peer_id, Misbehavior::UndecodableMessage));
+        assert!(!reputation.report(peer_id, Misbehavior::UndecodableMessage));
+        assert!(reputation.is_banned(&peer_id));
+    }
+
+    #[test]
+    fn decays_score_of_peer_reported_more_often_than_every_minute() {
+        let mut reputation =
+            PeerReputation::new(Config::new(1000, Duration::from_secs(60), 10), None);
+        let peer_id = PeerId(ScPeerId::random());
+        let start = Instant::now();
+        for i in 0..100 {
+            let now = start + Duration::from_secs(30 * i);
+            assert!(!reputation.report_at(peer_id, Misbehavior::UnknownSession, now));
+        }
+        assert!(reputation.scores.get(&peer_id).unwrap().value <= 10);
+        assert!(!reputation.is_banned(&peer_id));
+    }
+
+    #[test]
+    fn lifts_ban_after_duration() {
+        let mut reputation = reputation(100, Duration::from_millis(50));
+        let peer_id = PeerId(ScPeerId::random());
+        assert!(reputation.report(peer_id, Misbehavior::UndecodableMessage));
+        assert!(reputation.is_banned(&peer_id));
+        sleep(Duration::from_millis(60));
+        assert!(!reputation.is_banned(&peer_id));
+    }
+}
@@ -1,9 +1,12 @@
 use crate::network::{
+    reputation::{Config as ReputationConfig, Misbehavior, PeerReputation},
+    status::NetworkStatusHandle,
     ConnectionCommand, Data, DataCommand, Network, NetworkSender, PeerId, Protocol,
     ALEPH_PROTOCOL_NAME, ALEPH_VALIDATOR_PROTOCOL_NAME,
 };
 use futures::{channel::mpsc, StreamExt};
 use log::{debug, error, trace, warn};
+use prometheus_endpoint::Registry;
 use sc_network::{multiaddr, Event};
 use sc_service::SpawnTaskHandle;
 use sc_utils::mpsc::{tracing_unbounded, TracingUnboundedReceiver, TracingUnboundedSender};
@@ -20,37 +23,44 @@ use std::{
 /// 1. Incoming network events
 ///   1. Messages are forwarded to the user.
 ///   2. Various forms of (dis)connecting, keeping track of all currently connected nodes.
-/// 2. Commands from the network manager, modifying the reserved peer set.
+/// 2. Commands from the network manager, modifying the reserved peer set and reporting
+///    misbehaving peers.
 /// 3. Outgoing messages, sending them out, using 1.2. to broadcast.
+/// Messages from peers banned for misbehavior are dropped.
 pub struct Service<N: Network, D: Data> {
     network: N,
     messages_from_user: mpsc::UnboundedReceiver<(D, DataCommand)>,
-    messages_for_user: mpsc::UnboundedSender<D>,
+    messages_for_user: mpsc::UnboundedSender<(D, PeerId)>,
     commands_from_manager: mpsc::UnboundedReceiver<ConnectionCommand>,
     generic_connected_peers: HashSet<PeerId>,
     validator_connected_peers: HashSet<PeerId>,
     generic_peer_senders: HashMap<PeerId, TracingUnboundedSender<D>>,
     validator_peer_senders: HashMap<PeerId, TracingUnboundedSender<D>>,
+    reputation: PeerReputation,
+    status: NetworkStatusHandle,
     spawn_handle: SpawnTaskHandle,
 }
 
 /// Input/output channels for the network service.
 pub struct IO<D: Data> {
     messages_from_user: mpsc::UnboundedReceiver<(D, DataCommand)>,
-    messages_for_user: mpsc::UnboundedSender<D>,
+    messages_for_user: mpsc::UnboundedSender<(D, PeerId)>,
     commands_from_manager: mpsc::UnboundedReceiver<ConnectionCommand>,
+    status: NetworkStatusHandle,
 }
 
 impl<D: Data> IO<D> {
     pub fn new(
         messages_from_user: mpsc::UnboundedReceiver<(D, DataCommand)>,
-        messages_for_user: mpsc::UnboundedSender<D>,
+        messages_for_user: mpsc::UnboundedSender<(D, PeerId)>,
         commands_from_manager: mpsc::UnboundedReceiver<ConnectionCommand>,
+        status: NetworkStatusHandle,
     ) -> IO<D> {
         IO {
             messages_from_user,
             messages_for_user,
             commands_from_manager,
+            status,
         }
     }
 }
@@ -62,11 +72,18 @@ enum SendError {
 }
 
 impl<N: Network, D: Data> Service<N, D> {
-    pub fn new(network: N, spawn_handle: SpawnTaskHandle, io: IO<D>) -> Service<N, D> {
+    pub fn new(
+        network: N,
+        spawn_handle: SpawnTaskHandle,
+        io: IO<D>,
+        reputation_config: ReputationConfig,
+        registry: Option<Registry>,
+    ) -> Service<N, D> {
         let IO {
             messages_from_user,
             messages_for_user,
             commands_from_manager,
+            status,
         } = io;
         Service {
             network,
@@ -78,6 +95,8 @@ impl<N: Network, D: Data> Service<N, D> {
             validator_connected_peers: HashSet::new(),
             generic_peer_senders: HashMap::new(),
             validator_peer_senders: HashMap::new(),
+            reputation: PeerReputation::new(reputation_config, registry),
+            status,
         }
     }
 
@@ -147,6 +166,9 @@ impl<N: Network, D: Data> Service<N, D> {
 
     fn broadcast(&mut self, data: D) {
         for peer in self.generic_connected_peers.clone() {
+            if self.reputation.is_banned(&peer) {
+                continue;
+            }
             // We only broadcast authentication information in this sense, so we use the generic
             // Protocol.
             if let Err(e) = self.send_to_peer(data.clone(), peer, Protocol::Generic) {
@@ -155,7 +177,18 @@ impl<N: Network, D: Data> Service<N, D> {
         }
     }
 
-    fn handle_network_event(&mut self, event: Event) -> Result<(), mpsc::TrySendError<D>> {
+    fn report_misbehavior(&mut self, peer_id: PeerId, misbehavior: Misbehavior) {
+        self.network
+            .report_peer(peer_id, misbehavior.reputation_change());
+        if self.reputation.report(peer_id, misbehavior) {
+            debug!(target: "aleph-network", "Peer {:?} got banned, dropping its messages from now on.", peer_id);
+        }
+    }
+
+    fn handle_network_event(
+        &mut self,
+        event: Event,
+    ) -> Result<(), mpsc::TrySendError<(D, PeerId)>> {
         match event {
             Event::SyncConnected { remote } => {
                 trace!(target: "aleph-network", "SyncConnected event for peer {:?}", remote);
@@ -196,6 +229,8 @@ impl<N: Network, D: Data> Service<N, D> {
                     );
                     self.validator_connected_peers.insert(remote.into());
                     self.validator_peer_senders.insert(remote.into(), tx);
+                    self.status
+                        .update_validator_connections(&self.validator_connected_peers);
                 }
                 Err(_) => {
                     //Other protocols are irrelevant to us
@@ -212,23 +247,30 @@ impl<N: Network, D: Data> Service<N, D> {
                         trace!(target: "aleph-network", "NotificationStreamClosed event for peer {:?} and protocol {:?}", remote, protocol);
                         self.validator_connected_peers.remove(&remote.into());
                         self.validator_peer_senders.remove(&remote.into());
+                        self.status
+                            .update_validator_connections(&self.validator_connected_peers);
                     }
                     Err(_) => {
                         //Other protocols are irrelevant to us
                     }
                 }
             }
-            Event::NotificationsReceived {
-                remote: _,
-                messages,
-            } => {
+            Event::NotificationsReceived { remote, messages } => {
+                let peer_id = remote.into();
+                if self.reputation.is_banned(&peer_id) {
+                    trace!(target: "aleph-network", "Dropping messages from banned peer {:?}", peer_id);
+                    return Ok(());
+                }
                 for (protocol, data) in messages.into_iter() {
                     if protocol == ALEPH_PROTOCOL_NAME || protocol == ALEPH_VALIDATOR_PROTOCOL_NAME
                     {
                         match D::decode(&mut &data[..]) {
-                            Ok(message) => self.messages_for_user.unbounded_send(message)?,
+                            Ok(message) => {
+                                self.messages_for_user.unbounded_send((message, peer_id))?
+                            }
                             Err(e) => {
-                                warn!(target: "aleph-network", "Error decoding message: {}", e)
+                                warn!(target: "aleph-network", "Error decoding message from peer {:?}: {}", peer_id, e);
+                                self.report_misbehavior(peer_id, Misbehavior::UndecodableMessage);
                             }
                         }
                     }
@@ -240,7 +282,7 @@ impl<N: Network, D: Data> Service<N, D> {
         Ok(())
     }
 
-    fn on_manager_command(&self, command: ConnectionCommand) {
+    fn on_manager_command(&mut self, command: ConnectionCommand) {
         use ConnectionCommand::*;
         match command {
             AddReserved(addresses) => {
@@ -250,6 +292,9 @@ impl<N: Network, D: Data> Service<N, D> {
             DelReserved(peers) => self
                 .network
                 .remove_reserved(peers, Cow::Borrowed(ALEPH_VALIDATOR_PROTOCOL_NAME)),
+            ReportMisbehavior(peer_id, misbehavior) => {
+                self.report_misbehavior(peer_id, misbehavior)
+            }
         }
     }
 
@@ -304,7 +349,8 @@ mod tests {
     use crate::network::{
         manager::testing::MockNetworkIdentity,
         mock::{MockIO, MockNetwork, MockSenderError},
-        NetworkIdentity, Protocol, ALEPH_PROTOCOL_NAME, ALEPH_VALIDATOR_PROTOCOL_NAME,
+        Misbehavior, NetworkIdentity, PeerReputationConfig, Protocol, ALEPH_PROTOCOL_NAME,
+        ALEPH_VALIDATOR_PROTOCOL_NAME,
     };
     use codec::Encode;
     use futures::{channel::oneshot, StreamExt};
@@ -335,7 +381,13 @@ mod tests {
             // Prepare service
             let (event_stream_oneshot_tx, event_stream_oneshot_rx) = oneshot::channel();
             let network = MockNetwork::new(event_stream_oneshot_tx);
-            let service = Service::new(network.clone(), task_manager.spawn_handle(), io);
+            let service = Service::new(
+                network.clone(),
+                task_manager.spawn_handle(),
+                io,
+                PeerReputationConfig::default(),
+                None,
+            );
             let (exit_tx, exit_rx) = oneshot::channel();
             let task_handle = async move {
                 tokio::select! {
@@ -1211,7 +1263,71 @@ mod tests {
                 .next()
                 .await
                 .expect("Should receive message"),
-            message
+            (message, identity.1)
+        );
+
+        test_data.cleanup().await
+    }
+
+    #[tokio::test]
+    async fn test_undecodable_notification_reports_peer() {
+        let mut test_data = TestData::prepare().await;
+
+        let identity = MockNetworkIdentity::new().identity();
+
+        // A compact-encoded length of 1 without the following byte.
+        let undecodable_message: Vec<u8> = vec![4];
+
+        test_data.network.emit_event(Event::NotificationsReceived {
+            remote: identity.1.into(),
+            messages: vec![(
+                Cow::Borrowed(ALEPH_PROTOCOL_NAME),
+                undecodable_message.into(),
+            )],
+        });
+
+        assert_eq!(
+            test_data
+                .network
+                .report_peer
+                .next()
+                .await
+                .expect("Should receive report"),
+            (
+                identity.1,
+                Misbehavior::UndecodableMessage.reputation_change()
+            )
+        );
+
+        test_data.cleanup().await
+    }
+
+    #[tokio::test]
+    async fn test_command_report_misbehavior() {
+        let mut test_data = TestData::prepare().await;
+
+        let identity = MockNetworkIdentity::new().identity();
+
+        test_data
+            .mock_io
+            .commands_for_manager
+            .unbounded_send(ConnectionCommand::ReportMisbehavior(
+                identity.1,
+                Misbehavior::InvalidAuthentication,
+            ))
+            .unwrap();
+
+        assert_eq!(
+            test_data
+                .network
+                .report_peer
+                .next()
+                .await
+                .expect("Should receive report"),
+            (
+                identity.1,
+                Misbehavior::InvalidAuthentication.reputation_change()
+            )
         );
 
         test_data.cleanup().await
@@ -1,10 +1,15 @@
 use crate::{
     crypto::{AuthorityPen, AuthorityVerifier},
-    network::{ComponentNetwork, Data, SendError, SenderComponent, SessionCommand},
+    network::{
+        ComponentNetwork, Data, ReceiverComponent, SendError, SenderComponent, SessionCommand,
+    },
     NodeIndex, SessionId,
 };
 use aleph_bft::Recipient;
-use futures::channel::{mpsc, oneshot};
+use futures::{
+    channel::{mpsc, oneshot},
+    StreamExt,
+};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
@@ -23,15 +28,32 @@ impl<D: Data> SenderComponent<D> for Sender<D> {
     }
 }
 
+/// Receives data within a single session, together with the committee member that sent it, if it
+/// is known.
+pub struct Receiver<D: Data> {
+    data_from_network: mpsc::UnboundedReceiver<(D, Option<NodeIndex>)>,
+}
+
+#[async_trait::async_trait]
+impl<D: Data> ReceiverComponent<D> for Receiver<D> {
+    async fn next(&mut self) -> Option<D> {
+        self.next_with_sender().await.map(|(data, _)| data)
+    }
+
+    async fn next_with_sender(&mut self) -> Option<(D, Option<NodeIndex>)> {
+        self.data_from_network.next().await
+    }
+}
+
 /// Sends and receives data within a single session.
 pub struct Network<D: Data> {
     sender: Sender<D>,
-    receiver: Arc<Mutex<mpsc::UnboundedReceiver<D>>>,
+    receiver: Arc<Mutex<Receiver<D>>>,
 }
 
 impl<D: Data> ComponentNetwork<D> for Network<D> {
     type S = Sender<D>;
-    type R = mpsc::UnboundedReceiver<D>;
+    type R = Receiver<D>;
     fn sender(&self) -> &Self::S {
         &self.sender
     }
@@ -77,6 +99,18 @@ impl<D: Data> Manager<D> {
             .map_err(|_| ManagerError::CommandSendFailed)
     }
 
+    /// Start relaying the traffic of the protected validator or update the verifier in the given
+    /// session. Only makes sense if this node is configured as a sentry.
+    pub fn start_sentry_session(
+        &self,
+        session_id: SessionId,
+        verifier: AuthorityVerifier,
+    ) -> Result<(), ManagerError> {
+        self.commands_for_service
+            .unbounded_send(SessionCommand::StartSentry(session_id, verifier))
+            .map_err(|_| ManagerError::CommandSendFailed)
+    }
+
     /// Start participating or update the information about the given session where you are a
     /// validator. Returns a session network to be used for sending and receiving data within the
     /// session.
@@ -106,7 +140,7 @@ impl<D: Data> Manager<D> {
                 session_id,
                 messages_for_network,
             },
-            receiver: Arc::new(Mutex::new(data_from_network)),
+            receiver: Arc::new(Mutex::new(Receiver { data_from_network })),
         })
     }
 
@@ -0,0 +1,539 @@
+use crate::network::{
+    get_peer_id, Network, NetworkEventStream, NetworkIdentity, NetworkSender, PeerId, Protocol,
+};
+use async_trait::async_trait;
+use futures::channel::mpsc;
+use parking_lot::Mutex;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use sc_network::{
+    config::identity::{ed25519, PublicKey},
+    multiaddr::Protocol as ScProtocol,
+    Event, Multiaddr, ObservedRole, ReputationChange,
+};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    fmt,
+    net::Ipv4Addr,
+    sync::Arc,
+    time::Duration,
+};
+
+/// Properties of a directed link between two simulated nodes.
+#[derive(Clone, Copy, Debug)]
+pub struct LinkConfig {
+    latency: Duration,
+    drop_rate: f64,
+}
+
+impl LinkConfig {
+    /// A link delaying every message by `latency` and dropping it with probability `drop_rate`.
+    pub fn new(latency: Duration, drop_rate: f64) -> Self {
+        LinkConfig { latency, drop_rate }
+    }
+}
+
+impl Default for LinkConfig {
+    fn default() -> Self {
+        LinkConfig::new(Duration::ZERO, 0.0)
+    }
+}
+
+/// A change of the topology of the simulated network.
+#[derive(Clone, Debug)]
+pub enum Topology {
+    /// Only nodes within the same group can communicate, nodes not in any group are isolated.
+    Partitioned(Vec<HashSet<PeerId>>),
+    /// All nodes can communicate.
+    Healed,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub enum SimulatedSenderError {
+    NotConnected,
+}
+
+impl fmt::Display for SimulatedSenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SimulatedSenderError::NotConnected => write!(f, "Not connected to the peer"),
+        }
+    }
+}
+
+impl std::error::Error for SimulatedSenderError {}
+
+type Stream = (PeerId, PeerId, Cow<'static, str>);
+
+struct Inner {
+    // The nodes with their indices, in the order they were added, so that the events are always
+    // emitted in the same order.
+    nodes: Vec<(PeerId, u8)>,
+    sinks: HashMap<PeerId, Vec<mpsc::UnboundedSender<Event>>>,
+    reserved: HashMap<(PeerId, Cow<'static, str>), HashSet<PeerId>>,
+    open: HashSet<Stream>,
+    partition: Option<Vec<HashSet<PeerId>>>,
+    links: HashMap<(PeerId, PeerId), LinkConfig>,
+    default_link: LinkConfig,
+    seed: u64,
+    // Every directed link has its own generator, so that the messages dropped on it do not depend
+    // on the order in which the nodes happen to send over other links.
+    link_rngs: HashMap<(PeerId, PeerId), StdRng>,
+    reports: Vec<(PeerId, ReputationChange)>,
+}
+
+impl Inner {
+    fn peers(&self) -> Vec<PeerId> {
+        self.nodes.iter().map(|(peer_id, _)| *peer_id).collect()
+    }
+
+    fn index(&self, peer_id: &PeerId) -> Option<u8> {
+        self.nodes
+            .iter()
+            .find(|(node, _)| node == peer_id)
+            .map(|(_, index)| *index)
+    }
+
+    // Sorts the peers by their node indices, unknown peers last.
+    fn in_node_order(&self, peers: impl IntoIterator<Item = PeerId>) -> Vec<PeerId> {
+        let mut peers: Vec<_> = peers.into_iter().collect();
+        peers.sort_by_key(|peer_id| self.index(peer_id).map_or(u16::MAX, u16::from));
+        peers
+    }
+
+    fn should_drop(&mut self, from: PeerId, to: PeerId, drop_rate: f64) -> bool {
+        let seed = self.seed;
+        let link_seed = match (self.index(&from), self.index(&to)) {
+            (Some(from), Some(to)) => ((from as u64) << 8) | to as u64,
+            _ => 0,
+        };
+        self.link_rngs
+            .entry((from, to))
+            .or_insert_with(|| StdRng::seed_from_u64(seed.wrapping_add(link_seed)))
+            .gen_bool(drop_rate)
+    }
+
+    fn emit(&self, peer_id: &PeerId, event: Event) {
+        if let Some(sinks) = self.sinks.get(peer_id) {
+            for sink in sinks {
+                // The receiving node might have already been shut down.
+                let _ = sink.unbounded_send(event.clone());
+            }
+        }
+    }
+
+    fn separated(&self, first: &PeerId, second: &PeerId) -> bool {
+        match &self.partition {
+            Some(groups) => !groups
+                .iter()
+                .any(|group| group.contains(first) && group.contains(second)),
+            None => false,
+        }
+    }
+
+    fn reserves(&self, peer_id: PeerId, protocol: &Cow<'static, str>, other: &PeerId) -> bool {
+        self.reserved
+            .get(&(peer_id, protocol.clone()))
+            .map_or(false, |peers| peers.contains(other))
+    }
+
+    fn should_be_open(&self, first: PeerId, second: PeerId, protocol: &Cow<'static, str>) -> bool {
+        if first == second || self.separated(&first, &second) {
+            return false;
+        }
+        let first_reserves = self.reserves(first, protocol, &second);
+        let second_reserves = self.reserves(second, protocol, &first);
+        // The validator protocol does not accept non-reserved peers.
+        match *protocol == Protocol::Validator.name() {
+            true => first_reserves && second_reserves,
+            false => first_reserves || second_reserves,
+        }
+    }
+
+    fn refresh(&mut self, first: PeerId, second: PeerId, protocol: Cow<'static, str>) {
+        let is_open = self.open.contains(&(first, second, protocol.clone()));
+        match (self.should_be_open(first, second, &protocol), is_open) {
+            (true, false) => {
+                self.open.insert((first, second, protocol.clone()));
+                self.open.insert((second, first, protocol.clone()));
+                for (peer_id, remote) in [(first, second), (second, first)] {
+                    self.emit(
+                        &peer_id,
+                        Event::NotificationStreamOpened {
+                            remote: remote.into(),
+                            protocol: protocol.clone(),
+                            negotiated_fallback: None,
+                            role: ObservedRole::Authority,
+                        },
+                    );
+                }
+            }
+            (false, true) => {
+                self.open.remove(&(first, second, protocol.clone()));
+                self.open.remove(&(second, first, protocol.clone()));
+                for (peer_id, remote) in [(first, second), (second, first)] {
+                    self.emit(
+                        &peer_id,
+                        Event::NotificationStreamClosed {
+                            remote: remote.into(),
+                            protocol: protocol.clone(),
+                        },
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn refresh_all_protocols(&mut self, first: PeerId, second: PeerId) {
+        for protocol in [Protocol::Generic.name(), Protocol::Validator.name()] {
+            self.refresh(first, second, protocol);
+        }
+    }
+
+    fn sync_connected(&self, first: PeerId, second: PeerId) {
+        self.emit(
+            &first,
+            Event::SyncConnected {
+                remote: second.into(),
+            },
+        );
+        self.emit(
+            &second,
+            Event::SyncConnected {
+                remote: first.into(),
+            },
+        );
+    }
+
+    fn sync_disconnected(&self, first: PeerId, second: PeerId) {
+        self.emit(
+            &first,
+            Event::SyncDisconnected {
+                remote: second.into(),
+            },
+        );
+        self.emit(
+            &second,
+            Event::SyncDisconnected {
+                remote: first.into(),
+            },
+        );
+    }
+
+    fn pairs(&self) -> Vec<(PeerId, PeerId)> {
+        let peers = self.peers();
+        let mut result = Vec::new();
+        for (i, first) in peers.iter().enumerate() {
+            for second in peers.iter().skip(i + 1) {
+                result.push((*first, *second));
+            }
+        }
+        result
+    }
+
+    fn set_topology(&mut self, topology: Topology) {
+        let previously_separated: HashSet<_> = self
+            .pairs()
+            .into_iter()
+            .filter(|(first, second)| self.separated(first, second))
+            .collect();
+        self.partition = match topology {
+            Topology::Partitioned(groups) => Some(groups),
+            Topology::Healed => None,
+        };
+        for (first, second) in self.pairs() {
+            let was_separated = previously_separated.contains(&(first, second));
+            match (was_separated, self.separated(&first, &second)) {
+                (false, true) => {
+                    self.refresh_all_protocols(first, second);
+                    self.sync_disconnected(first, second);
+                }
+                (true, false) => {
+                    self.sync_connected(first, second);
+                    self.refresh_all_protocols(first, second);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn link(&self, from: PeerId, to: PeerId) -> LinkConfig {
+        self.links
+            .get(&(from, to))
+            .copied()
+            .unwrap_or(self.default_link)
+    }
+}
+
+/// An in-process network connecting several simulated nodes. Links between nodes can have latency
+/// and drop messages, and the network can be partitioned and healed. Node identities are derived
+/// from their indices and, given the seed, the messages dropped on a link depend only on the
+/// messages sent over it. Delivery with latency relies on tokio timers, so tests should run with
+/// the time paused to be reproducible.
+/// Substrate-like behavior is simulated: nodes get `SyncConnected` for every reachable node,
+/// generic protocol streams open when either side reserves the other, validator protocol streams
+/// only when both do.
+#[derive(Clone)]
+pub struct SimulatedNetworkHub {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl SimulatedNetworkHub {
+    /// Creates a network where all links have the given properties. The seed determines which
+    /// messages get dropped.
+    pub fn new(default_link: LinkConfig, seed: u64) -> Self {
+        SimulatedNetworkHub {
+            inner: Arc::new(Mutex::new(Inner {
+                nodes: Vec::new(),
+                sinks: HashMap::new(),
+                reserved: HashMap::new(),
+                open: HashSet::new(),
+                partition: None,
+                links: HashMap::new(),
+                default_link,
+                seed,
+                link_rngs: HashMap::new(),
+                reports: Vec::new(),
+            })),
+        }
+    }
+
+    /// Adds a new node to the network, with a PeerId and an address derived from the given index,
+    /// which has to be unique.
+    pub fn add_node(&self, index: u8) -> SimulatedNetwork {
+        let peer_id = simulated_peer_id(index);
+        let address = Multiaddr::empty()
+            .with(ScProtocol::Ip4(Ipv4Addr::new(10, 0, 0, index)))
+            .with(ScProtocol::Tcp(30333))
+            .with(ScProtocol::P2p(peer_id.0.into()));
+        let mut inner = self.inner.lock();
+        assert!(
+            inner.index(&peer_id).is_none(),
+            "Node {} was already added",
+            index
+        );
+        inner.nodes.push((peer_id, index));
+        inner.sinks.insert(peer_id, Vec::new());
+        drop(inner);
+        SimulatedNetwork {
+            hub: self.clone(),
+            peer_id,
+            address,
+        }
+    }
+
+    /// Overrides the properties of the directed link between the two nodes.
+    pub fn set_link(&self, from: PeerId, to: PeerId, link: LinkConfig) {
+        self.inner.lock().links.insert((from, to), link);
+    }
+
+    /// Waits until every node listens for network events, i.e. its network service started.
+    pub async fn wait_for_listeners(&self) {
+        while self
+            .inner
+            .lock()
+            .sinks
+            .values()
+            .any(|sinks| sinks.is_empty())
+        {
+            tokio::task::yield_now().await;
+        }
+    }
+
+    /// Emits `SyncConnected` events between all pairs of nodes that can reach each other. Should
+    /// be called once all the nodes started listening for events.
+    pub fn connect_all(&self) {
+        let inner = self.inner.lock();
+        for (first, second) in inner.pairs() {
+            if !inner.separated(&first, &second) {
+                inner.sync_connected(first, second);
+            }
+        }
+    }
+
+    /// Changes the topology of the network, closing and reopening connections as needed.
+    pub fn set_topology(&self, topology: Topology) {
+        self.inner.lock().set_topology(topology);
+    }
+
+    /// Applies the topology changes one by one, waiting the given time before each of them.
+    pub async fn run_schedule(&self, schedule: Vec<(Duration, Topology)>) {
+        for (delay, topology) in schedule {
+            tokio::time::sleep(delay).await;
+            self.set_topology(topology);
+        }
+    }
+
+    /// All the reputation changes reported so far, by reported peer.
+    pub fn reports(&self) -> Vec<(PeerId, ReputationChange)> {
+        self.inner.lock().reports.clone()
+    }
+
+    fn deliver(&self, from: PeerId, to: PeerId, protocol: Cow<'static, str>, data: Vec<u8>) {
+        let inner = self.inner.lock();
+        // The connection might have been closed while the message was in flight.
+        if !inner.open.contains(&(from, to, protocol.clone())) {
+            return;
+        }
+        inner.emit(
+            &to,
+            Event::NotificationsReceived {
+                remote: from.into(),
+                messages: vec![(protocol, data.into())],
+            },
+        );
+    }
+
+    fn send(
+        &self,
+        from: PeerId,
+        to: PeerId,
+        protocol: Cow<'static, str>,
+        data: Vec<u8>,
+    ) -> Result<(), SimulatedSenderError> {
+        let link = {
+            let mut inner = self.inner.lock();
+            if !inner.open.contains(&(from, to, protocol.clone())) {
+                return Err(SimulatedSenderError::NotConnected);
+            }
+            let link = inner.link(from, to);
+            if inner.should_drop(from, to, link.drop_rate) {
+                return Ok(());
+            }
+            link
+        };
+        if link.latency.is_zero() {
+            self.deliver(from, to, protocol, data);
+            return Ok(());
+        }
+        let hub = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(link.latency).await;
+            hub.deliver(from, to, protocol, data);
+        });
+        Ok(())
+    }
+}
+
+fn simulated_peer_id(index: u8) -> PeerId {
+    let mut secret = [index; 32];
+    let secret = ed25519::SecretKey::from_bytes(&mut secret)
+        .expect("32 bytes are a valid ed25519 secret key");
+    PeerId(PublicKey::Ed25519(ed25519::Keypair::from(secret).public()).to_peer_id())
+}
+
+/// The view of the simulated network of a single node.
+#[derive(Clone)]
+pub struct SimulatedNetwork {
+    hub: SimulatedNetworkHub,
+    peer_id: PeerId,
+    address: Multiaddr,
+}
+
+impl SimulatedNetwork {
+    pub fn peer_id(&self) -> PeerId {
+        self.peer_id
+    }
+}
+
+pub struct SimulatedNetworkSender {
+    hub: SimulatedNetworkHub,
+    from: PeerId,
+    to: PeerId,
+    protocol: Cow<'static, str>,
+}
+
+#[async_trait]
+impl NetworkSender for SimulatedNetworkSender {
+    type SenderError = SimulatedSenderError;
+
+    async fn send<'a>(
+        &'a self,
+        data: impl Into<Vec<u8>> + Send + Sync + 'static,
+    ) -> Result<(), SimulatedSenderError> {
+        self.hub
+            .send(self.from, self.to, self.protocol.clone(), data.into())
+    }
+}
+
+impl Network for SimulatedNetwork {
+    type SenderError = SimulatedSenderError;
+    type NetworkSender = SimulatedNetworkSender;
+
+    fn event_stream(&self) -> NetworkEventStream {
+        let (tx, rx) = mpsc::unbounded();
+        self.hub
+            .inner
+            .lock()
+            .sinks
+            .entry(self.peer_id)
+            .or_default()
+            .push(tx);
+        Box::pin(rx)
+    }
+
+    fn sender(
+        &self,
+        peer_id: PeerId,
+        protocol: Cow<'static, str>,
+    ) -> Result<Self::NetworkSender, Self::SenderError> {
+        if !self
+            .hub
+            .inner
+            .lock()
+            .open
+            .contains(&(self.peer_id, peer_id, protocol.clone()))
+        {
+            return Err(SimulatedSenderError::NotConnected);
+        }
+        Ok(SimulatedNetworkSender {
+            hub: self.hub.clone(),
+            from: self.peer_id,
+            to: peer_id,
+            protocol,
+        })
+    }
+
+    fn add_reserved(&self, addresses: HashSet<Multiaddr>, protocol: Cow<'static, str>) {
+        let mut inner = self.hub.inner.lock();
+        let peer_ids = inner.in_node_order(
+            addresses
+                .into_iter()
+                .filter_map(|address| get_peer_id(&address.into())),
+        );
+        for peer_id in peer_ids {
+            if peer_id == self.peer_id {
+                continue;
+            }
+            inner
+                .reserved
+                .entry((self.peer_id, protocol.clone()))
+                .or_default()
+                .insert(peer_id);
+            if inner.sinks.contains_key(&peer_id) {
+                inner.refresh(self.peer_id, peer_id, protocol.clone());
+            }
+        }
+    }
+
+    fn remove_reserved(&self, peers: HashSet<PeerId>, protocol: Cow<'static, str>) {
+        let mut inner = self.hub.inner.lock();
+        for peer_id in inner.in_node_order(peers) {
+            if let Some(reserved) = inner.reserved.get_mut(&(self.peer_id, protocol.clone())) {
+                reserved.remove(&peer_id);
+            }
+            inner.refresh(self.peer_id, peer_id, protocol.clone());
+        }
+    }
+
+    fn report_peer(&self, peer_id: PeerId, change: ReputationChange) {
+        self.hub.inner.lock().reports.push((peer_id, change));
+    }
+}
+
+impl NetworkIdentity for SimulatedNetwork {
+    fn identity(&self) -> (Vec<Multiaddr>, PeerId) {
+        (vec![self.address.clone()], self.peer_id)
+    }
+}
@@ -2,7 +2,7 @@
 // typesystem (perhaps somewhat with macros?). Be very careful to change all the occurences if you
 // are modyfing this file.
 use crate::network::{ComponentNetwork, Data, ReceiverComponent, SendError, SenderComponent};
-use aleph_bft::Recipient;
+use aleph_bft::{NodeIndex, Recipient};
 use codec::{Decode, Encode};
 use futures::channel::mpsc;
 use log::{trace, warn};
@@ -51,9 +51,9 @@ struct LeftReceiver<
     R: ReceiverComponent<Split<LeftData, RightData>>,
 > {
     receiver: Arc<Mutex<R>>,
-    translated_receiver: mpsc::UnboundedReceiver<LeftData>,
-    left_sender: mpsc::UnboundedSender<LeftData>,
-    right_sender: mpsc::UnboundedSender<RightData>,
+    translated_receiver: mpsc::UnboundedReceiver<(LeftData, Option<NodeIndex>)>,
+    left_sender: mpsc::UnboundedSender<(LeftData, Option<NodeIndex>)>,
+    right_sender: mpsc::UnboundedSender<(RightData, Option<NodeIndex>)>,
 }
 
 struct RightReceiver<
@@ -62,9 +62,9 @@ struct RightReceiver<
     R: ReceiverComponent<Split<LeftData, RightData>>,
 > {
     receiver: Arc<Mutex<R>>,
-    translated_receiver: mpsc::UnboundedReceiver<RightData>,
-    left_sender: mpsc::UnboundedSender<LeftData>,
-    right_sender: mpsc::UnboundedSender<RightData>,
+    translated_receiver: mpsc::UnboundedReceiver<(RightData, Option<NodeIndex>)>,
+    left_sender: mpsc::UnboundedSender<(LeftData, Option<NodeIndex>)>,
+    right_sender: mpsc::UnboundedSender<(RightData, Option<NodeIndex>)>,
 }
 
 async fn forward_or_wait<
@@ -73,18 +73,18 @@ async fn forward_or_wait<
     R: ReceiverComponent<Split<LeftData, RightData>>,
 >(
     receiver: &Arc<Mutex<R>>,
-    left_sender: &mpsc::UnboundedSender<LeftData>,
-    right_sender: &mpsc::UnboundedSender<RightData>,
+    left_sender: &mpsc::UnboundedSender<(LeftData, Option<NodeIndex>)>,
+    right_sender: &mpsc::UnboundedSender<(RightData, Option<NodeIndex>)>,
 ) -> bool {
-    match receiver.lock().await.next().await {
-        Some(Split::Left(data)) => {
-            if left_sender.unbounded_send(data).is_err() {
+    match receiver.lock().await.next_with_sender().await {
+        Some((Split::Left(data), sender)) => {
+            if left_sender.unbounded_send((data, sender)).is_err() {
                 warn!(target: "aleph-network", "Failed send despite controlling receiver, this shouldn't've happened.");
             }
             true
         }
-        Some(Split::Right(data)) => {
-            if right_sender.unbounded_send(data).is_err() {
+        Some((Split::Right(data), sender)) => {
+            if right_sender.unbounded_send((data, sender)).is_err() {
                 warn!(target: "aleph-network", "Failed send despite controlling receiver, this shouldn't've happened.");
             }
             true
@@ -103,6 +103,10 @@ impl<LeftData: Data, RightData: Data, R: ReceiverComponent<Split<LeftData, Right
     ReceiverComponent<LeftData> for LeftReceiver<LeftData, RightData, R>
 {
     async fn next(&mut self) -> Option<LeftData> {
+        self.next_with_sender().await.map(|(data, _)| data)
+    }
+
+    async fn next_with_sender(&mut self) -> Option<(LeftData, Option<NodeIndex>)> {
         loop {
             tokio::select! {
                 data = self.translated_receiver.next() => {
@@ -123,6 +127,10 @@ impl<LeftData: Data, RightData: Data, R: ReceiverComponent<Split<LeftData, Right
     ReceiverComponent<RightData> for RightReceiver<LeftData, RightData, R>
 {
     async fn next(&mut self) -> Option<RightData> {
+        self.next_with_sender().await.map(|(data, _)| data)
+    }
+
+    async fn next_with_sender(&mut self) -> Option<(RightData, Option<NodeIndex>)> {
         loop {
             tokio::select! {
                 data = self.translated_receiver.next() => {
@@ -0,0 +1,91 @@
+use crate::{
+    network::{manager::Multiaddr, PeerId},
+    NodeIndex, SessionId,
+};
+use parking_lot::Mutex;
+use serde::Serialize;
+use std::{collections::HashSet, sync::Arc};
+
+/// The resolved network identity of a single authority.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthorityStatus {
+    pub node_index: usize,
+    pub peer_id: String,
+    pub addresses: Vec<String>,
+}
+
+impl AuthorityStatus {
+    pub fn new(node_index: NodeIndex, peer_id: PeerId, addresses: Vec<Multiaddr>) -> Self {
+        AuthorityStatus {
+            node_index: node_index.0,
+            peer_id: peer_id.0.to_base58(),
+            addresses: addresses
+                .into_iter()
+                .map(|address| address.0.to_string())
+                .collect(),
+        }
+    }
+}
+
+/// What the connection manager knows about a single session.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionStatus {
+    pub session_id: u32,
+    pub node_index: Option<usize>,
+    pub known_authorities: Vec<AuthorityStatus>,
+    pub missing_authorities: Vec<usize>,
+}
+
+impl SessionStatus {
+    pub fn new(
+        session_id: SessionId,
+        node_index: Option<NodeIndex>,
+        known_authorities: Vec<AuthorityStatus>,
+        missing_authorities: Vec<NodeIndex>,
+    ) -> Self {
+        SessionStatus {
+            session_id: session_id.0,
+            node_index: node_index.map(|node_index| node_index.0),
+            known_authorities,
+            missing_authorities: missing_authorities
+                .into_iter()
+                .map(|node_index| node_index.0)
+                .collect(),
+        }
+    }
+}
+
+/// A snapshot of the state of the Aleph network, as reported by the `aleph_networkStatus` RPC.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkStatus {
+    pub sessions: Vec<SessionStatus>,
+    pub validator_connections: Vec<String>,
+}
+
+/// A shared handle to the network status. The connection manager and the network service keep it
+/// up to date, anyone holding a clone can read the current snapshot.
+#[derive(Clone, Default)]
+pub struct NetworkStatusHandle(Arc<Mutex<NetworkStatus>>);
+
+impl NetworkStatusHandle {
+    /// Returns the current snapshot of the network status.
+    pub fn status(&self) -> NetworkStatus {
+        self.0.lock().clone()
+    }
+
+    /// Replaces the status of all the active sessions.
+    pub fn update_sessions(&self, mut sessions: Vec<SessionStatus>) {
+        sessions.sort_by_key(|session| session.session_id);
+        self.0.lock().sessions = sessions;
+    }
+
+    /// Replaces the set of peers we have open validator protocol connections with.
+    pub fn update_validator_connections(&self, peers: &HashSet<PeerId>) {
+        let mut connections: Vec<_> = peers.iter().map(|peer_id| peer_id.0.to_base58()).collect();
+        connections.sort();
+        self.0.lock().validator_connections = connections;
+    }
+}
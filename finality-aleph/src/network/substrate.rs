@@ -3,7 +3,9 @@ use crate::network::{
 };
 use async_trait::async_trait;
 use log::error;
-use sc_network::{ExHashT, Multiaddr, NetworkService, NetworkStateInfo, NotificationSender};
+use sc_network::{
+    ExHashT, Multiaddr, NetworkService, NetworkStateInfo, NotificationSender, ReputationChange,
+};
 use sp_api::NumberFor;
 use sp_runtime::traits::Block;
 use std::{borrow::Cow, collections::HashSet, fmt, sync::Arc};
@@ -120,6 +122,10 @@ impl<B: Block, H: ExHashT> Network for Arc<NetworkService<B, H>> {
         let addresses = peers.into_iter().map(|peer_id| peer_id.0).collect();
         self.remove_peers_from_reserved_set(protocol, addresses);
     }
+
+    fn report_peer(&self, peer_id: PeerId, change: ReputationChange) {
+        NetworkService::report_peer(self, peer_id.into(), change)
+    }
 }
 
 impl<B: Block, H: ExHashT> NetworkIdentity for Arc<NetworkService<B, H>> {
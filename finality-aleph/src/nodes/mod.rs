@@ -6,12 +6,13 @@ pub use validator_node::run_validator_node;
 
 use crate::{
     crypto::AuthorityVerifier,
-    finalization::AlephFinalizer,
+    finalization::{AlephFinalizer, FinalityHook, HookedFinalizer},
     justification::{
         JustificationHandler, JustificationRequestSchedulerImpl, SessionInfo, SessionInfoProvider,
     },
     last_block_of_session, mpsc,
     mpsc::UnboundedSender,
+    session::SharedSessionSchedule,
     session_id_from_block_num,
     session_map::ReadOnlySessionMap,
     JustificationNotification, Metrics, MillisecsPerBlock, SessionPeriod,
@@ -32,18 +33,23 @@ struct JustificationParams<B: Block, H: ExHashT, C> {
     pub session_period: SessionPeriod,
     pub millisecs_per_block: MillisecsPerBlock,
     pub session_map: ReadOnlySessionMap,
+    pub session_schedule: SharedSessionSchedule,
+    pub finality_hooks: Vec<Arc<dyn FinalityHook<B>>>,
 }
 
 struct SessionInfoProviderImpl {
     session_authorities: ReadOnlySessionMap,
-    session_period: SessionPeriod,
+    session_schedule: SharedSessionSchedule,
 }
 
 impl SessionInfoProviderImpl {
-    fn new(session_authorities: ReadOnlySessionMap, session_period: SessionPeriod) -> Self {
+    fn new(
+        session_authorities: ReadOnlySessionMap,
+        session_schedule: SharedSessionSchedule,
+    ) -> Self {
         Self {
             session_authorities,
-            session_period,
+            session_schedule,
         }
     }
 }
@@ -51,8 +57,9 @@ impl SessionInfoProviderImpl {
 #[async_trait::async_trait]
 impl<B: Block> SessionInfoProvider<B, AuthorityVerifier> for SessionInfoProviderImpl {
     async fn for_block_num(&self, number: NumberFor<B>) -> SessionInfo<B, AuthorityVerifier> {
-        let current_session = session_id_from_block_num::<B>(number, self.session_period);
-        let last_block_height = last_block_of_session::<B>(current_session, self.session_period);
+        let session_schedule = self.session_schedule.get();
+        let current_session = session_id_from_block_num::<B>(number, &session_schedule);
+        let last_block_height = last_block_of_session::<B>(current_session, &session_schedule);
         let verifier = self
             .session_authorities
             .get(current_session)
@@ -88,13 +95,15 @@ where
         session_period,
         millisecs_per_block,
         session_map,
+        session_schedule,
+        finality_hooks,
     } = just_params;
 
     let handler = JustificationHandler::new(
-        SessionInfoProviderImpl::new(session_map, session_period),
+        SessionInfoProviderImpl::new(session_map, session_schedule),
         network,
         client.clone(),
-        AlephFinalizer::new(client),
+        HookedFinalizer::new(AlephFinalizer::new(client), finality_hooks),
         JustificationRequestSchedulerImpl::new(&session_period, &millisecs_per_block, MAX_ATTEMPTS),
         metrics,
         Default::default(),
@@ -1,6 +1,8 @@
 use crate::{
     nodes::{setup_justification_handler, JustificationParams},
-    session_map::{AuthorityProviderImpl, FinalityNotificatorImpl, SessionMapUpdater},
+    session_map::{
+        AuthorityProviderImpl, AuxSessionAuthorityStore, FinalityNotificatorImpl, SessionMapUpdater,
+    },
     AlephConfig,
 };
 use log::{debug, error};
@@ -8,6 +10,7 @@ use sc_client_api::Backend;
 use sc_network::ExHashT;
 use sp_consensus::SelectChain;
 use sp_runtime::traits::Block;
+use std::sync::Arc;
 
 pub async fn run_nonvalidator_node<B, H, C, BE, SC>(aleph_config: AlephConfig<B, H, C, SC>)
 where
@@ -26,16 +29,20 @@ where
         millisecs_per_block,
         justification_rx,
         spawn_handle,
+        finality_hooks,
         ..
     } = aleph_config;
     let map_updater = SessionMapUpdater::<_, _, B>::new(
         AuthorityProviderImpl::new(client.clone()),
         FinalityNotificatorImpl::new(client.clone()),
+        Arc::new(AuxSessionAuthorityStore::new(client.clone())),
+        session_period,
     );
     let session_authorities = map_updater.readonly_session_map();
+    let session_schedule = map_updater.session_schedule();
     spawn_handle.spawn("aleph/updater", None, async move {
         debug!(target: "aleph-party", "SessionMapUpdater has started.");
-        map_updater.run(session_period).await
+        map_updater.run().await
     });
     let (_, handler_task) = setup_justification_handler(JustificationParams {
         justification_rx,
@@ -45,6 +52,8 @@ where
         session_period,
         millisecs_per_block,
         session_map: session_authorities,
+        session_schedule,
+        finality_hooks,
     });
 
     debug!(target: "aleph-party", "JustificationHandler has started.");
@@ -1,4 +1,5 @@
 use crate::{
+    data_io::{BlockImporter, DataStoreMonitor},
     mpsc,
     network::{
         ConnectionIO, ConnectionManager, ConnectionManagerConfig, Service as NetworkService,
@@ -6,7 +7,9 @@ use crate::{
     },
     nodes::{setup_justification_handler, JustificationParams},
     party::{ConsensusParty, ConsensusPartyParams},
-    session_map::{AuthorityProviderImpl, FinalityNotificatorImpl, SessionMapUpdater},
+    session_map::{
+        AuthorityProviderImpl, AuxSessionAuthorityStore, FinalityNotificatorImpl, SessionMapUpdater,
+    },
     AlephConfig,
 };
 use log::{debug, error};
@@ -14,6 +17,7 @@ use sc_client_api::Backend;
 use sc_network::ExHashT;
 use sp_consensus::SelectChain;
 use sp_runtime::traits::Block;
+use std::sync::Arc;
 
 pub async fn run_validator_node<B, H, C, BE, SC>(aleph_config: AlephConfig<B, H, C, SC>)
 where
@@ -32,21 +36,39 @@ where
         keystore,
         metrics,
         unit_creation_delay,
+        aggregator_config,
+        data_store_config,
         session_period,
         millisecs_per_block,
         justification_rx,
+        peer_reputation,
+        address_policy,
+        sentry_role,
+        registry,
+        network_status,
+        data_store_status,
+        ordered_head,
+        block_import_queue,
+        finality_hooks,
         ..
     } = aleph_config;
 
+    let data_store_monitor = DataStoreMonitor::new(data_store_status, registry.clone());
+
     let block_requester = network.clone();
+    let (block_importer, block_import_task) = BlockImporter::new(block_import_queue);
+    spawn_handle.spawn("aleph/block_import", None, block_import_task);
     let map_updater = SessionMapUpdater::<_, _, B>::new(
         AuthorityProviderImpl::new(client.clone()),
         FinalityNotificatorImpl::new(client.clone()),
+        Arc::new(AuxSessionAuthorityStore::new(client.clone())),
+        session_period,
     );
     let session_authorities = map_updater.readonly_session_map();
+    let session_schedule = map_updater.session_schedule();
     spawn_handle.spawn("aleph/updater", None, async move {
         debug!(target: "aleph-party", "SessionMapUpdater has started.");
-        map_updater.run(session_period).await
+        map_updater.run().await
     });
 
     let (authority_justification_tx, handler_task) =
@@ -58,6 +80,8 @@ where
             session_period,
             millisecs_per_block,
             session_map: session_authorities.clone(),
+            session_schedule: session_schedule.clone(),
+            finality_hooks,
         });
 
     // Prepare and start the network
@@ -73,16 +97,26 @@ where
         commands_from_user,
         commands_from_manager,
         messages_from_network,
+        network_status.clone(),
     );
     let connection_manager = ConnectionManager::new(
         network.clone(),
-        ConnectionManagerConfig::with_session_period(&session_period, &millisecs_per_block),
+        ConnectionManagerConfig::with_session_period(&session_period, &millisecs_per_block)
+            .with_address_policy(address_policy)
+            .with_sentry_role(sentry_role.clone()),
     );
     let session_manager = SessionManager::new(commands_for_service, messages_for_service);
     let network = NetworkService::new(
         network.clone(),
         spawn_handle.clone(),
-        NetworkIO::new(messages_from_user, messages_for_user, commands_from_io),
+        NetworkIO::new(
+            messages_from_user,
+            messages_for_user,
+            commands_from_io,
+            network_status,
+        ),
+        peer_reputation,
+        registry,
     );
 
     let network_manager_task = async move {
@@ -104,15 +138,21 @@ where
     let party = ConsensusParty::new(ConsensusPartyParams {
         session_manager,
         session_authorities,
-        session_period,
+        session_schedule,
         spawn_handle: spawn_handle.into(),
         client,
         select_chain,
         keystore,
         block_requester,
+        block_importer,
         metrics,
         authority_justification_tx,
         unit_creation_delay,
+        aggregator_config,
+        data_store_config,
+        data_store_monitor,
+        ordered_head,
+        sentry: sentry_role.is_sentry(),
     });
 
     debug!(target: "aleph-party", "Consensus party has started.");
@@ -1,5 +1,8 @@
 use crate::{
-    aggregation::{BlockSignatureAggregator, RmcNetworkData, SignableHash, IO as AggregatorIO},
+    aggregation::{
+        AggregatorConfig, BlockSignatureAggregator, Multicast, MulticastVariant, RmcMulticast,
+        RmcNetworkData, ThresholdMulticast, IO as AggregatorIO,
+    },
     crypto::{KeyBox, Signature},
     justification::{AlephJustification, JustificationNotification},
     metrics::Checkpoint,
@@ -7,15 +10,14 @@ use crate::{
     party::{AuthoritySubtaskCommon, Task},
     BlockHashNum, Metrics, SessionBoundaries,
 };
-use aleph_bft::{KeyBox as BftKeyBox, SignatureSet, SpawnHandle};
-use aleph_bft_rmc::{DoublingDelayScheduler, ReliableMulticast};
+use aleph_bft::{SignatureSet, SpawnHandle};
 use futures::{
     channel::{mpsc, oneshot},
     StreamExt,
 };
 use log::{debug, error, trace};
 use sc_client_api::HeaderBackend;
-use sp_runtime::traits::{Block, Header};
+use sp_runtime::traits::{Block, Header, NumberFor, Zero};
 use std::sync::Arc;
 
 /// IO channels used by the aggregator task.
@@ -24,23 +26,16 @@ pub struct IO<B: Block> {
     pub justifications_for_chain: mpsc::UnboundedSender<JustificationNotification<B>>,
 }
 
-type SignableBlockHash<B> = SignableHash<<B as Block>::Hash>;
-type Rmc<'a, B> = ReliableMulticast<'a, SignableBlockHash<B>, KeyBox>;
-
-async fn process_new_block_data<B, N>(
-    aggregator: &mut AggregatorIO<
-        B::Hash,
-        RmcNetworkData<B>,
-        N,
-        SignatureSet<Signature>,
-        Rmc<'_, B>,
-    >,
+async fn process_new_block_data<B, N, M>(
+    aggregator: &mut AggregatorIO<B::Hash, RmcNetworkData<B>, N, SignatureSet<Signature>, M>,
     block: BlockHashNum<B>,
     session_boundaries: &SessionBoundaries<B>,
+    aggregator_config: &AggregatorConfig,
     metrics: &Option<Metrics<<B::Header as Header>::Hash>>,
 ) where
     B: Block,
     N: DataNetwork<RmcNetworkData<B>>,
+    M: Multicast<B::Hash, SignatureSet<Signature>>,
     <B as Block>::Hash: AsRef<[u8]>,
 {
     trace!(target: "aleph-party", "Received unit {:?} in aggregator.", block);
@@ -48,8 +43,13 @@ async fn process_new_block_data<B, N>(
         metrics.report_block(block.hash, std::time::Instant::now(), Checkpoint::Ordered);
     }
 
-    aggregator.start_aggregation(block.hash).await;
-    if block.num == session_boundaries.last_block() {
+    let last_block = block.num == session_boundaries.last_block();
+    let periodic_block = aggregator_config.keep_every != 0
+        && (block.num % NumberFor::<B>::from(aggregator_config.keep_every)).is_zero();
+    aggregator
+        .start_aggregation(block.hash, last_block || periodic_block)
+        .await;
+    if last_block {
         aggregator.notify_last_hash();
     }
 }
@@ -77,23 +77,19 @@ fn process_hash<B, C>(
     }
 }
 
-async fn run_aggregator<B, C, N>(
-    mut aggregator: AggregatorIO<
-        B::Hash,
-        RmcNetworkData<B>,
-        N,
-        SignatureSet<Signature>,
-        Rmc<'_, B>,
-    >,
+async fn run_aggregator<B, C, N, M>(
+    mut aggregator: AggregatorIO<B::Hash, RmcNetworkData<B>, N, SignatureSet<Signature>, M>,
     io: IO<B>,
     client: Arc<C>,
     session_boundaries: &SessionBoundaries<B>,
+    aggregator_config: AggregatorConfig,
     metrics: Option<Metrics<<B::Header as Header>::Hash>>,
     mut exit_rx: oneshot::Receiver<()>,
 ) where
     B: Block,
     C: HeaderBackend<B> + Send + Sync + 'static,
     N: DataNetwork<RmcNetworkData<B>>,
+    M: Multicast<B::Hash, SignatureSet<Signature>>,
     <B as Block>::Hash: AsRef<[u8]>,
 {
     let IO {
@@ -109,6 +105,7 @@ async fn run_aggregator<B, C, N>(
                         &mut aggregator,
                         block,
                         session_boundaries,
+                        &aggregator_config,
                         &metrics
                     ).await;
                 } else {
@@ -142,6 +139,8 @@ pub fn task<B, C, N>(
     client: Arc<C>,
     io: IO<B>,
     session_boundaries: SessionBoundaries<B>,
+    aggregator_config: AggregatorConfig,
+    multicast_variant: MulticastVariant,
     metrics: Option<Metrics<<B::Header as Header>::Hash>>,
     multikeychain: KeyBox,
     rmc_network: N,
@@ -160,32 +159,59 @@ where
         async move {
             let (messages_for_rmc, messages_from_network) = mpsc::unbounded();
             let (messages_for_network, messages_from_rmc) = mpsc::unbounded();
-            let scheduler = DoublingDelayScheduler::new(tokio::time::Duration::from_millis(500));
-            let rmc = ReliableMulticast::new(
-                messages_from_network,
-                messages_for_network,
-                &multikeychain,
-                multikeychain.node_count(),
-                scheduler,
-            );
-            let aggregator = BlockSignatureAggregator::new(metrics.clone());
-            let aggregator_io = AggregatorIO::new(
-                messages_for_rmc,
-                messages_from_rmc,
-                rmc_network,
-                rmc,
-                aggregator,
-            );
-            debug!(target: "aleph-party", "Running the aggregator task for {:?}", session_id);
-            run_aggregator(
-                aggregator_io,
-                io,
-                client,
-                &session_boundaries,
-                metrics,
-                exit,
-            )
-            .await;
+            let aggregator =
+                BlockSignatureAggregator::new(aggregator_config.policy, metrics.clone());
+            debug!(target: "aleph-party", "Running the {:?} aggregator task for {:?}", multicast_variant, session_id);
+            match multicast_variant {
+                MulticastVariant::Reliable => {
+                    let multicast = RmcMulticast::new(
+                        messages_from_network,
+                        messages_for_network,
+                        &multikeychain,
+                    );
+                    let aggregator_io = AggregatorIO::new(
+                        messages_for_rmc,
+                        messages_from_rmc,
+                        rmc_network,
+                        multicast,
+                        aggregator,
+                    );
+                    run_aggregator(
+                        aggregator_io,
+                        io,
+                        client,
+                        &session_boundaries,
+                        aggregator_config,
+                        metrics,
+                        exit,
+                    )
+                    .await
+                }
+                MulticastVariant::Threshold => {
+                    let multicast = ThresholdMulticast::new(
+                        messages_from_network,
+                        messages_for_network,
+                        &multikeychain,
+                    );
+                    let aggregator_io = AggregatorIO::new(
+                        messages_for_rmc,
+                        messages_from_rmc,
+                        rmc_network,
+                        multicast,
+                        aggregator,
+                    );
+                    run_aggregator(
+                        aggregator_io,
+                        io,
+                        client,
+                        &session_boundaries,
+                        aggregator_config,
+                        metrics,
+                        exit,
+                    )
+                    .await
+                }
+            }
             debug!(target: "aleph-party", "Aggregator task stopped for {:?}", session_id);
         }
     };
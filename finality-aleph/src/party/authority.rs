@@ -40,6 +40,7 @@ pub struct Subtasks {
     aggregator: PureTask,
     refresher: PureTask,
     data_store: PureTask,
+    block_sync: PureTask,
 }
 
 impl Subtasks {
@@ -50,6 +51,7 @@ impl Subtasks {
         aggregator: PureTask,
         refresher: PureTask,
         data_store: PureTask,
+        block_sync: PureTask,
     ) -> Self {
         Subtasks {
             exit,
@@ -57,6 +59,7 @@ impl Subtasks {
             aggregator,
             refresher,
             data_store,
+            block_sync,
         }
     }
 
@@ -72,6 +75,8 @@ impl Subtasks {
         trace!(target: "aleph-party", "Refresher stopped");
         self.data_store.stop().await;
         trace!(target: "aleph-party", "DataStore stopped");
+        self.block_sync.stop().await;
+        trace!(target: "aleph-party", "BlockSync stopped");
     }
 
     /// Blocks until the task is done and returns true if it quit unexpectedly.
@@ -82,6 +87,7 @@ impl Subtasks {
             _ = self.aggregator.stopped() => true,
             _ = self.refresher.stopped() => true,
             _ = self.data_store.stopped() => true,
+            _ = self.block_sync.stopped() => true,
         };
         if result {
             debug!(target: "aleph-party", "Something died and it was unexpected");
@@ -0,0 +1,38 @@
+use crate::{
+    data_io::{BlockSync, BlockSyncData},
+    network::{ReceiverComponent, SenderComponent},
+    party::{AuthoritySubtaskCommon, Task},
+};
+use aleph_bft::SpawnHandle;
+use futures::channel::oneshot;
+use log::debug;
+use sc_client_api::{BlockBackend, HeaderBackend};
+use sp_runtime::traits::Block;
+
+/// Runs the block sync within a single session.
+pub fn task<B, C, S, R>(
+    subtask_common: AuthoritySubtaskCommon,
+    mut block_sync: BlockSync<B, C, S, R>,
+) -> Task
+where
+    B: Block,
+    C: HeaderBackend<B> + BlockBackend<B> + Send + Sync + 'static,
+    S: SenderComponent<BlockSyncData<B>> + 'static,
+    R: ReceiverComponent<BlockSyncData<B>> + 'static,
+{
+    let AuthoritySubtaskCommon {
+        spawn_handle,
+        session_id,
+    } = subtask_common;
+    let (stop, exit) = oneshot::channel();
+    let task = {
+        async move {
+            debug!(target: "aleph-party", "Running the block sync task for {:?}", session_id);
+            block_sync.run(exit).await;
+            debug!(target: "aleph-party", "Block sync task stopped for {:?}", session_id);
+        }
+    };
+
+    let handle = spawn_handle.spawn_essential("aleph/consensus_session_block_sync", task);
+    Task::new(handle, stop)
+}
@@ -1,10 +1,14 @@
 use crate::{
+    aggregation::{AggregatorConfig, MulticastVariant},
     crypto::{AuthorityPen, AuthorityVerifier, KeyBox},
-    data_io::{ChainTracker, DataStore, OrderedDataInterpreter},
+    data_io::{
+        BlockImporter, BlockSync, ChainTracker, DataStore, DataStoreConfig, DataStoreMonitor,
+        ObservedProposals, OrderedDataInterpreter, MAX_DATA_BRANCH_LEN,
+    },
     default_aleph_config,
     justification::{AlephJustification, JustificationNotification, Verifier},
     last_block_of_session,
-    network::{split, RequestBlocks, SessionManager, SessionNetwork},
+    network::{split, ManagerError, RequestBlocks, SessionManager, SessionNetwork},
     party::{
         authority::{
             SubtaskCommon as AuthoritySubtaskCommon, Subtasks as AuthoritySubtasks,
@@ -12,25 +16,30 @@ use crate::{
         },
         task::{Handle, Task},
     },
+    select_chain::OrderedHead,
+    session::{supports_session_schedule, SharedSessionSchedule},
     session_id_from_block_num,
     session_map::ReadOnlySessionMap,
-    AuthorityId, Metrics, NodeIndex, SessionBoundaries, SessionId, SessionPeriod, SplitData,
-    UnitCreationDelay,
+    AuthorityId, Metrics, NodeIndex, SessionBoundaries, SessionId, SplitData, UnitCreationDelay,
 };
 use aleph_bft::{DelayConfig, SpawnHandle};
-use aleph_primitives::KEY_TYPE;
+use aleph_primitives::{AlephSessionApi, Version, DEFAULT_FINALITY_VERSION, KEY_TYPE};
 use codec::Encode;
 use futures::channel::mpsc;
 use futures_timer::Delay;
-use log::{debug, info, trace, warn};
+use log::{debug, error, info, trace, warn};
 use sc_client_api::Backend;
 use sp_consensus::SelectChain;
 use sp_keystore::CryptoStore;
-use sp_runtime::traits::{Block, Header};
+use sp_runtime::{
+    generic::BlockId,
+    traits::{Block, Header, Saturating},
+};
 use std::{collections::HashSet, default::Default, marker::PhantomData, sync::Arc, time::Duration};
 
 mod aggregator;
 mod authority;
+mod block_sync;
 mod chain_tracker;
 mod data_store;
 mod member;
@@ -62,15 +71,21 @@ async fn get_node_index(
 pub(crate) struct ConsensusPartyParams<B: Block, SC, C, RB> {
     pub session_manager: SessionManager<SplitData<B>>,
     pub session_authorities: ReadOnlySessionMap,
-    pub session_period: SessionPeriod,
+    pub session_schedule: SharedSessionSchedule,
     pub spawn_handle: crate::SpawnHandle,
     pub client: Arc<C>,
     pub select_chain: SC,
     pub keystore: Arc<dyn CryptoStore>,
     pub block_requester: RB,
+    pub block_importer: BlockImporter<B>,
     pub metrics: Option<Metrics<<B::Header as Header>::Hash>>,
     pub authority_justification_tx: mpsc::UnboundedSender<JustificationNotification<B>>,
     pub unit_creation_delay: UnitCreationDelay,
+    pub aggregator_config: AggregatorConfig,
+    pub data_store_config: DataStoreConfig,
+    pub data_store_monitor: DataStoreMonitor,
+    pub ordered_head: OrderedHead<B>,
+    pub sentry: bool,
 }
 
 pub(crate) struct ConsensusParty<B, C, BE, SC, RB>
@@ -84,20 +99,53 @@ where
 {
     session_manager: SessionManager<SplitData<B>>,
     session_authorities: ReadOnlySessionMap,
-    session_period: SessionPeriod,
+    session_schedule: SharedSessionSchedule,
     spawn_handle: crate::SpawnHandle,
     client: Arc<C>,
     select_chain: SC,
     keystore: Arc<dyn CryptoStore>,
     block_requester: RB,
+    block_importer: BlockImporter<B>,
     phantom: PhantomData<BE>,
     metrics: Option<Metrics<<B::Header as Header>::Hash>>,
     authority_justification_tx: mpsc::UnboundedSender<JustificationNotification<B>>,
     unit_creation_delay: UnitCreationDelay,
+    aggregator_config: AggregatorConfig,
+    data_store_config: DataStoreConfig,
+    data_store_monitor: DataStoreMonitor,
+    ordered_head: OrderedHead<B>,
+    sentry: bool,
 }
 
 const SESSION_STATUS_CHECK_PERIOD: Duration = Duration::from_millis(1000);
 
+/// The variants of the finality protocol this node can run, one for every supported finality
+/// version. Nodes keep supporting the old versions, so operators can upgrade at their own pace
+/// before the network switches to a new one at a session boundary.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ProtocolVariant {
+    V0,
+    /// Block signatures are combined by a rotating leader instead of being broadcast.
+    V1,
+}
+
+impl ProtocolVariant {
+    fn for_version(version: Version) -> Option<Self> {
+        match version {
+            0 => Some(ProtocolVariant::V0),
+            1 => Some(ProtocolVariant::V1),
+            _ => None,
+        }
+    }
+
+    fn multicast(&self) -> MulticastVariant {
+        match self {
+            ProtocolVariant::V0 => MulticastVariant::Reliable,
+            ProtocolVariant::V1 => MulticastVariant::Threshold,
+        }
+    }
+}
+
 impl<B, C, BE, SC, RB> ConsensusParty<B, C, BE, SC, RB>
 where
     B: Block,
@@ -111,15 +159,21 @@ where
         let ConsensusPartyParams {
             session_manager,
             session_authorities,
-            session_period,
+            session_schedule,
             spawn_handle,
             client,
             select_chain,
             keystore,
             block_requester,
+            block_importer,
             metrics,
             authority_justification_tx,
             unit_creation_delay,
+            aggregator_config,
+            data_store_config,
+            data_store_monitor,
+            ordered_head,
+            sentry,
         } = params;
         Self {
             session_manager,
@@ -127,18 +181,66 @@ where
             keystore,
             select_chain,
             block_requester,
+            block_importer,
             metrics,
             authority_justification_tx,
             session_authorities,
-            session_period,
+            session_schedule,
             spawn_handle,
             phantom: PhantomData,
             unit_creation_delay,
+            aggregator_config,
+            data_store_config,
+            data_store_monitor,
+            ordered_head,
+            sentry,
+        }
+    }
+
+    // The maximum branch length is read at the last block of the previous session, which is
+    // finalized before the session starts, so all the nodes agree on it.
+    fn max_data_branch_len(&self, session_boundaries: &SessionBoundaries<B>) -> usize {
+        let block = session_boundaries.first_block().saturating_sub(1u32.into());
+        let at = BlockId::Number(block);
+        // Older runtimes do not keep the maximum branch length on chain.
+        if !supports_session_schedule(self.client.as_ref(), &at) {
+            return MAX_DATA_BRANCH_LEN;
+        }
+        match self.client.runtime_api().max_data_branch_len(&at) {
+            Ok(len) => len as usize,
+            Err(e) => {
+                warn!(target: "aleph-party", "Failed to read the maximum branch length at block {:?}, using the default: {:?}", block, e);
+                MAX_DATA_BRANCH_LEN
+            }
+        }
+    }
+
+    // The finality version is read at the last block of the previous session, so all the nodes
+    // switch versions at the same block.
+    fn finality_version(&self, session_id: SessionId) -> Version {
+        let session_boundaries = SessionBoundaries::new(session_id, &self.session_schedule.get());
+        let block = session_boundaries.first_block().saturating_sub(1u32.into());
+        let at = BlockId::Number(block);
+        // Older runtimes do not know finality versions, they all run the initial one.
+        if !supports_session_schedule(self.client.as_ref(), &at) {
+            return DEFAULT_FINALITY_VERSION;
+        }
+        match self
+            .client
+            .runtime_api()
+            .finality_version_for_session(&at, session_id.0)
+        {
+            Ok(version) => version,
+            Err(e) => {
+                warn!(target: "aleph-party", "Failed to read the finality version at block {:?}, using the default: {:?}", block, e);
+                DEFAULT_FINALITY_VERSION
+            }
         }
     }
 
     async fn spawn_authority_subtasks(
         &self,
+        variant: ProtocolVariant,
         node_id: NodeIndex,
         multikeychain: KeyBox,
         data_network: SessionNetwork<SplitData<B>>,
@@ -147,28 +249,37 @@ where
         exit_rx: futures::channel::oneshot::Receiver<()>,
     ) -> AuthoritySubtasks {
         debug!(target: "afa", "Authority task {:?}", session_id);
-        let session_boundaries = SessionBoundaries::new(session_id, self.session_period);
+        let session_boundaries = SessionBoundaries::new(session_id, &self.session_schedule.get());
+        let max_branch_len = self.max_data_branch_len(&session_boundaries);
         let (blocks_for_aggregator, blocks_from_interpreter) = mpsc::unbounded();
 
-        let consensus_config = create_aleph_config(
-            authorities.len(),
-            node_id,
-            session_id,
-            self.unit_creation_delay,
-        );
+        let consensus_config = match variant {
+            ProtocolVariant::V0 | ProtocolVariant::V1 => create_aleph_config(
+                authorities.len(),
+                node_id,
+                session_id,
+                self.unit_creation_delay,
+            ),
+        };
 
+        let observed_proposals = ObservedProposals::new(authorities.len().into());
         let (chain_tracker, data_provider) = ChainTracker::new(
             self.select_chain.clone(),
             self.client.clone(),
             session_boundaries.clone(),
+            max_branch_len,
+            observed_proposals.clone(),
             Default::default(),
             self.metrics.clone(),
         );
 
         let ordered_data_interpreter = OrderedDataInterpreter::<B, C>::new(
             blocks_for_aggregator,
+            self.ordered_head.clone(),
             self.client.clone(),
             session_boundaries.clone(),
+            max_branch_len,
+            self.data_store_config.chain_info_cache.clone(),
         );
 
         let subtask_common = AuthoritySubtaskCommon {
@@ -180,12 +291,24 @@ where
             justifications_for_chain: self.authority_justification_tx.clone(),
         };
 
-        let (unfiltered_aleph_network, rmc_network) = split(data_network);
+        let (unfiltered_aleph_network, rest_network) = split(data_network);
+        let (rmc_network, block_sync_network) = split(rest_network);
+        let (block_sync, block_requester) = BlockSync::new(
+            self.client.clone(),
+            self.block_importer.clone(),
+            node_id,
+            authorities.len().into(),
+            self.block_requester.clone(),
+            block_sync_network,
+        );
         let (data_store, aleph_network) = DataStore::new(
             session_boundaries.clone(),
+            max_branch_len,
+            observed_proposals,
             self.client.clone(),
-            self.block_requester.clone(),
-            Default::default(),
+            block_requester,
+            self.data_store_config.clone(),
+            self.data_store_monitor.clone(),
             unfiltered_aleph_network,
         );
 
@@ -204,17 +327,21 @@ where
                 self.client.clone(),
                 aggregator_io,
                 session_boundaries,
+                self.aggregator_config,
+                variant.multicast(),
                 self.metrics.clone(),
                 multikeychain,
                 rmc_network,
             ),
             chain_tracker::task(subtask_common.clone(), chain_tracker),
-            data_store::task(subtask_common, data_store),
+            data_store::task(subtask_common.clone(), data_store),
+            block_sync::task(subtask_common, block_sync),
         )
     }
 
     async fn spawn_authority_task(
         &self,
+        variant: ProtocolVariant,
         session_id: SessionId,
         node_id: NodeIndex,
         authorities: Vec<AuthorityId>,
@@ -236,6 +363,7 @@ where
         let (exit, exit_rx) = futures::channel::oneshot::channel();
         let authority_subtasks = self
             .spawn_authority_subtasks(
+                variant,
                 node_id,
                 keybox,
                 data_network,
@@ -257,7 +385,7 @@ where
     }
 
     async fn run_session(&mut self, session_id: SessionId) {
-        let last_block = last_block_of_session::<B>(session_id, self.session_period);
+        let last_block = last_block_of_session::<B>(session_id, &self.session_schedule.get());
 
         // Early skip attempt -- this will trigger during catching up (initial sync).
         if self.client.info().best_number >= last_block {
@@ -294,18 +422,27 @@ where
         };
 
         trace!(target: "afa", "Authorities for session {:?}: {:?}", session_id, authorities);
-        let mut maybe_authority_task = if let Some(node_id) =
-            get_node_index(&authorities, self.keystore.clone()).await
-        {
-            debug!(target: "aleph-party", "Running session {:?} as authority id {:?}", session_id, node_id);
+        let version = self.finality_version(session_id);
+        let maybe_authority = match (
+            get_node_index(&authorities, self.keystore.clone()).await,
+            ProtocolVariant::for_version(version),
+        ) {
+            (Some(node_id), Some(variant)) => Some((node_id, variant)),
+            (Some(_), None) => {
+                error!(target: "aleph-party", "Session {:?} runs the unsupported finality version {:?}, the node has to be upgraded to take part in it.", session_id, version);
+                None
+            }
+            (None, _) => None,
+        };
+        let mut maybe_authority_task = if let Some((node_id, variant)) = maybe_authority {
+            debug!(target: "aleph-party", "Running session {:?} as authority id {:?} with finality version {:?}", session_id, node_id, version);
             Some(
-                self.spawn_authority_task(session_id, node_id, authorities.clone())
+                self.spawn_authority_task(variant, session_id, node_id, authorities.clone())
                     .await,
             )
         } else {
             debug!(target: "afa", "Running session {:?} as non-authority", session_id);
             if let Err(e) = self
-                .session_manager
                 .start_nonvalidator_session(session_id, AuthorityVerifier::new(authorities.clone()))
             {
                 warn!(target: "aleph-party", "Failed to start nonvalidator session{:?}:{:?}", session_id, e);
@@ -370,7 +507,6 @@ where
                         }
                         None => {
                             if let Err(e) = self
-                                .session_manager
                                 .start_nonvalidator_session(next_session_id, authority_verifier)
                             {
                                 warn!(target: "aleph-party", "Failed to early start nonvalidator session{:?}:{:?}", next_session_id, e);
@@ -398,10 +534,26 @@ where
         }
     }
 
+    /// Sentries relay the traffic of their validator in sessions in which they are not authorities.
+    fn start_nonvalidator_session(
+        &self,
+        session_id: SessionId,
+        verifier: AuthorityVerifier,
+    ) -> Result<(), ManagerError> {
+        match self.sentry {
+            true => self
+                .session_manager
+                .start_sentry_session(session_id, verifier),
+            false => self
+                .session_manager
+                .start_nonvalidator_session(session_id, verifier),
+        }
+    }
+
     pub async fn run(mut self) {
         let last_finalized_number = self.client.info().finalized_number;
         let starting_session =
-            session_id_from_block_num::<B>(last_finalized_number, self.session_period);
+            session_id_from_block_num::<B>(last_finalized_number, &self.session_schedule.get());
         for curr_id in starting_session.0.. {
             info!(target: "aleph-party", "Running session {:?}.", curr_id);
             self.run_session(SessionId(curr_id)).await;
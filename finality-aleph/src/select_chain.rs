@@ -0,0 +1,255 @@
+use crate::BlockHashNum;
+use async_trait::async_trait;
+use log::{debug, warn};
+use parking_lot::Mutex;
+use sp_blockchain::{HeaderBackend, HeaderMetadata};
+use sp_consensus::{Error as ConsensusError, SelectChain};
+use sp_runtime::{
+    generic::BlockId,
+    traits::{Block as BlockT, Header as HeaderT, NumberFor},
+};
+use std::sync::Arc;
+
+/// A shared handle to the highest block ordered by AlephBFT. The ordered data interpreter keeps
+/// it up to date, the block might not be finalized yet, as the multisignature under it still has
+/// to be gathered.
+pub struct OrderedHead<B: BlockT>(Arc<Mutex<Option<BlockHashNum<B>>>>);
+
+impl<B: BlockT> OrderedHead<B> {
+    /// Returns the highest block ordered so far, if any.
+    pub fn get(&self) -> Option<BlockHashNum<B>> {
+        self.0.lock().clone()
+    }
+
+    pub(crate) fn update(&self, block: BlockHashNum<B>) {
+        let mut head = self.0.lock();
+        if head.as_ref().map_or(true, |head| head.num < block.num) {
+            *head = Some(block);
+        }
+    }
+}
+
+impl<B: BlockT> Clone for OrderedHead<B> {
+    fn clone(&self) -> Self {
+        OrderedHead(self.0.clone())
+    }
+}
+
+impl<B: BlockT> Default for OrderedHead<B> {
+    fn default() -> Self {
+        OrderedHead(Arc::new(Mutex::new(None)))
+    }
+}
+
+/// A `SelectChain` that never builds on forks conflicting with the blocks already ordered by
+/// AlephBFT. As long as the ordered head is not finalized, the best chain is the best one among
+/// the chains containing it, otherwise the choice is left to the inner `SelectChain`.
+pub struct AlephSelectChain<B: BlockT, SC, C> {
+    inner: SC,
+    client: Arc<C>,
+    ordered_head: OrderedHead<B>,
+}
+
+impl<B: BlockT, SC: Clone, C> Clone for AlephSelectChain<B, SC, C> {
+    fn clone(&self) -> Self {
+        AlephSelectChain {
+            inner: self.inner.clone(),
+            client: self.client.clone(),
+            ordered_head: self.ordered_head.clone(),
+        }
+    }
+}
+
+fn lookup_error(e: sp_blockchain::Error) -> ConsensusError {
+    ConsensusError::ChainLookup(format!("{:?}", e))
+}
+
+impl<B, SC, C> AlephSelectChain<B, SC, C>
+where
+    B: BlockT,
+    C: HeaderBackend<B> + HeaderMetadata<B, Error = sp_blockchain::Error>,
+{
+    pub fn new(inner: SC, client: Arc<C>) -> Self {
+        AlephSelectChain {
+            inner,
+            client,
+            ordered_head: OrderedHead::default(),
+        }
+    }
+
+    /// The handle that should be passed to Aleph, so that it can report the ordered blocks.
+    pub fn ordered_head(&self) -> OrderedHead<B> {
+        self.ordered_head.clone()
+    }
+
+    // The ordered head, if it is above the finalized block and we have already imported it.
+    fn pending_ordered_head(&self) -> Option<BlockHashNum<B>> {
+        let head = self.ordered_head.get()?;
+        if head.num <= self.client.info().finalized_number {
+            return None;
+        }
+        match self.client.header(BlockId::Hash(head.hash)) {
+            Ok(Some(_)) => Some(head),
+            _ => None,
+        }
+    }
+
+    fn is_descendant(
+        &self,
+        hash: B::Hash,
+        ancestor: &BlockHashNum<B>,
+    ) -> Result<bool, sp_blockchain::Error> {
+        let mut current = self.client.header_metadata(hash)?;
+        while current.number > ancestor.num {
+            current = self.client.header_metadata(current.parent)?;
+        }
+        Ok(current.hash == ancestor.hash)
+    }
+}
+
+#[async_trait]
+impl<B, SC, C> SelectChain<B> for AlephSelectChain<B, SC, C>
+where
+    B: BlockT,
+    SC: SelectChain<B>,
+    C: HeaderBackend<B> + HeaderMetadata<B, Error = sp_blockchain::Error> + Send + Sync,
+{
+    async fn leaves(&self) -> Result<Vec<B::Hash>, ConsensusError> {
+        self.inner.leaves().await
+    }
+
+    async fn best_chain(&self) -> Result<B::Header, ConsensusError> {
+        let best = self.inner.best_chain().await?;
+        let ordered = match self.pending_ordered_head() {
+            Some(ordered) => ordered,
+            None => return Ok(best),
+        };
+        if self
+            .is_descendant(best.hash(), &ordered)
+            .map_err(lookup_error)?
+        {
+            return Ok(best);
+        }
+        let mut best_containing: Option<B::Header> = None;
+        for leaf in self.inner.leaves().await? {
+            if !self.is_descendant(leaf, &ordered).map_err(lookup_error)? {
+                continue;
+            }
+            let header = self
+                .client
+                .header(BlockId::Hash(leaf))
+                .map_err(lookup_error)?
+                .ok_or_else(|| {
+                    ConsensusError::ChainLookup(format!("Missing header of leaf {:?}", leaf))
+                })?;
+            if best_containing
+                .as_ref()
+                .map_or(true, |current| header.number() > current.number())
+            {
+                best_containing = Some(header);
+            }
+        }
+        match best_containing {
+            Some(header) => {
+                debug!(target: "aleph-select-chain", "Building on {:?} instead of {:?}, as it contains the ordered block {:?}.", header.hash(), best.hash(), ordered);
+                Ok(header)
+            }
+            None => {
+                warn!(target: "aleph-select-chain", "No leaf contains the ordered block {:?}, building on {:?}.", ordered, best.hash());
+                Ok(best)
+            }
+        }
+    }
+
+    async fn finality_target(
+        &self,
+        target_hash: B::Hash,
+        maybe_max_number: Option<NumberFor<B>>,
+    ) -> Result<B::Hash, ConsensusError> {
+        self.inner
+            .finality_target(target_hash, maybe_max_number)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AlephSelectChain, OrderedHead};
+    use crate::{testing::client_chain_builder::ClientChainBuilder, BlockHashNum};
+    use sp_consensus::SelectChain;
+    use sp_runtime::traits::{Block as BlockT, Header as HeaderT};
+    use std::sync::Arc;
+    use substrate_test_runtime_client::{
+        runtime::Block, DefaultTestClientBuilderExt, TestClientBuilder, TestClientBuilderExt,
+    };
+
+    fn prepare() -> (
+        ClientChainBuilder,
+        impl SelectChain<Block>,
+        OrderedHead<Block>,
+    ) {
+        let (client, longest_chain) = TestClientBuilder::new().build_with_longest_chain();
+        let client = Arc::new(client);
+        let chain_builder =
+            ClientChainBuilder::new(client.clone(), Arc::new(TestClientBuilder::new().build()));
+        let select_chain = AlephSelectChain::new(longest_chain, client);
+        let ordered_head = select_chain.ordered_head();
+        (chain_builder, select_chain, ordered_head)
+    }
+
+    fn hash_num(block: &Block) -> BlockHashNum<Block> {
+        (block.hash(), *block.header().number()).into()
+    }
+
+    #[tokio::test]
+    async fn follows_longest_chain_without_ordered_blocks() {
+        let (mut chain_builder, select_chain, _) = prepare();
+        let main = chain_builder.initialize_single_branch_and_import(5).await;
+        chain_builder
+            .build_and_import_branch_above(&main[1].hash(), 2)
+            .await;
+
+        let best = select_chain.best_chain().await.unwrap();
+        assert_eq!(best.hash(), main[4].hash());
+    }
+
+    #[tokio::test]
+    async fn prefers_fork_containing_ordered_block() {
+        let (mut chain_builder, select_chain, ordered_head) = prepare();
+        let main = chain_builder.initialize_single_branch_and_import(5).await;
+        let fork = chain_builder
+            .build_and_import_branch_above(&main[1].hash(), 2)
+            .await;
+        ordered_head.update(hash_num(&fork[0]));
+
+        let best = select_chain.best_chain().await.unwrap();
+        assert_eq!(best.hash(), fork[1].hash());
+    }
+
+    #[tokio::test]
+    async fn keeps_best_chain_containing_ordered_block() {
+        let (mut chain_builder, select_chain, ordered_head) = prepare();
+        let main = chain_builder.initialize_single_branch_and_import(5).await;
+        chain_builder
+            .build_and_import_branch_above(&main[1].hash(), 2)
+            .await;
+        ordered_head.update(hash_num(&main[2]));
+
+        let best = select_chain.best_chain().await.unwrap();
+        assert_eq!(best.hash(), main[4].hash());
+    }
+
+    #[tokio::test]
+    async fn ignores_finalized_ordered_block() {
+        let (mut chain_builder, select_chain, ordered_head) = prepare();
+        let main = chain_builder.initialize_single_branch_and_import(5).await;
+        chain_builder
+            .build_and_import_branch_above(&main[1].hash(), 2)
+            .await;
+        ordered_head.update(hash_num(&main[1]));
+        chain_builder.finalize_block(&main[1].hash());
+
+        let best = select_chain.best_chain().await.unwrap();
+        assert_eq!(best.hash(), main[4].hash());
+    }
+}
@@ -1,6 +1,10 @@
 use crate::NumberFor;
+use aleph_primitives::{AlephSessionApi, SessionPeriodChange, SESSION_SCHEDULE_API_VERSION};
 use codec::{Decode, Encode};
-use sp_runtime::{traits::Block, SaturatedConversion};
+use parking_lot::RwLock;
+use sp_api::{ApiExt, ProvideRuntimeApi};
+use sp_runtime::{generic::BlockId, traits::Block, SaturatedConversion};
+use std::sync::Arc;
 
 #[derive(PartialEq, Eq, Clone, Debug)]
 pub struct SessionBoundaries<B: Block> {
@@ -9,10 +13,10 @@ pub struct SessionBoundaries<B: Block> {
 }
 
 impl<B: Block> SessionBoundaries<B> {
-    pub fn new(session_id: SessionId, period: SessionPeriod) -> Self {
+    pub fn new(session_id: SessionId, schedule: &SessionSchedule) -> Self {
         SessionBoundaries {
-            first_block: first_block_of_session::<B>(session_id, period),
-            last_block: last_block_of_session::<B>(session_id, period),
+            first_block: first_block_of_session::<B>(session_id, schedule),
+            last_block: last_block_of_session::<B>(session_id, schedule),
         }
     }
 
@@ -27,20 +31,26 @@ impl<B: Block> SessionBoundaries<B> {
 
 pub fn first_block_of_session<B: Block>(
     session_id: SessionId,
-    period: SessionPeriod,
+    schedule: &SessionSchedule,
 ) -> NumberFor<B> {
-    (session_id.0 * period.0).into()
+    aleph_primitives::first_block_of_session(&schedule.0, session_id.0).into()
 }
 
 pub fn last_block_of_session<B: Block>(
     session_id: SessionId,
-    period: SessionPeriod,
+    schedule: &SessionSchedule,
 ) -> NumberFor<B> {
-    ((session_id.0 + 1) * period.0 - 1).into()
+    (aleph_primitives::first_block_of_session(&schedule.0, session_id.0 + 1) - 1).into()
 }
 
-pub fn session_id_from_block_num<B: Block>(num: NumberFor<B>, period: SessionPeriod) -> SessionId {
-    SessionId(num.saturated_into::<u32>() / period.0)
+pub fn session_id_from_block_num<B: Block>(
+    num: NumberFor<B>,
+    schedule: &SessionSchedule,
+) -> SessionId {
+    SessionId(aleph_primitives::session_of_block(
+        &schedule.0,
+        num.saturated_into::<u32>(),
+    ))
 }
 
 #[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash, Ord, PartialOrd, Encode, Decode)]
@@ -48,3 +58,170 @@ pub struct SessionId(pub u32);
 
 #[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash, Ord, PartialOrd, Encode, Decode)]
 pub struct SessionPeriod(pub u32);
+
+/// The lengths of sessions over time, as recorded on chain.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SessionSchedule(Vec<SessionPeriodChange>);
+
+impl SessionSchedule {
+    /// Creates a schedule from the changes returned by the runtime. Returns `None` unless there
+    /// are some changes, they are sorted by session, all periods are nonzero and the first blocks
+    /// follow from the preceding changes. The runtime prunes old changes, so the schedule does not
+    /// have to start at session 0.
+    pub fn new(changes: Vec<SessionPeriodChange>) -> Option<Self> {
+        let sorted = changes
+            .windows(2)
+            .all(|pair| pair[0].session < pair[1].session);
+        let nonzero = changes.iter().all(|change| change.period > 0);
+        let consistent = changes.windows(2).all(|pair| {
+            aleph_primitives::first_block_of_session(&pair[..1], pair[1].session)
+                == pair[1].first_block
+        });
+        match !changes.is_empty() && sorted && nonzero && consistent {
+            true => Some(SessionSchedule(changes)),
+            false => None,
+        }
+    }
+}
+
+impl From<SessionPeriod> for SessionSchedule {
+    fn from(period: SessionPeriod) -> Self {
+        SessionSchedule(constant_schedule(period))
+    }
+}
+
+/// The schedule of a chain whose sessions all have the same length.
+pub fn constant_schedule(period: SessionPeriod) -> Vec<SessionPeriodChange> {
+    vec![SessionPeriodChange {
+        session: 0,
+        first_block: 0,
+        period: period.0,
+    }]
+}
+
+/// Checks whether the runtime at the block provides the session period schedule and the other
+/// methods added to `AlephSessionApi` together with it.
+pub fn supports_session_schedule<B, C>(client: &C, at: &BlockId<B>) -> bool
+where
+    B: Block,
+    C: ProvideRuntimeApi<B>,
+    C::Api: AlephSessionApi<B>,
+{
+    client
+        .runtime_api()
+        .has_api_with::<dyn AlephSessionApi<B>, _>(at, |version| {
+            version >= SESSION_SCHEDULE_API_VERSION
+        })
+        .unwrap_or(false)
+}
+
+/// A session schedule shared between all the components computing session boundaries, kept up to
+/// date with the finalized chain by the `SessionMapUpdater`.
+#[derive(Clone)]
+pub struct SharedSessionSchedule(Arc<RwLock<SessionSchedule>>);
+
+impl SharedSessionSchedule {
+    pub fn new(schedule: SessionSchedule) -> Self {
+        SharedSessionSchedule(Arc::new(RwLock::new(schedule)))
+    }
+
+    /// Returns a snapshot of the current schedule.
+    pub fn get(&self) -> SessionSchedule {
+        self.0.read().clone()
+    }
+
+    pub(crate) fn set(&self, schedule: SessionSchedule) {
+        *self.0.write() = schedule;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        first_block_of_session, last_block_of_session, session_id_from_block_num, SessionId,
+        SessionPeriod, SessionSchedule,
+    };
+    use crate::testing::mocks::TBlock;
+    use aleph_primitives::SessionPeriodChange;
+
+    fn change(session: u32, first_block: u32, period: u32) -> SessionPeriodChange {
+        SessionPeriodChange {
+            session,
+            first_block,
+            period,
+        }
+    }
+
+    #[test]
+    fn constant_period_gives_regular_boundaries() {
+        let schedule: SessionSchedule = SessionPeriod(10).into();
+        assert_eq!(
+            first_block_of_session::<TBlock>(SessionId(3), &schedule),
+            30
+        );
+        assert_eq!(last_block_of_session::<TBlock>(SessionId(3), &schedule), 39);
+        assert_eq!(
+            session_id_from_block_num::<TBlock>(39, &schedule),
+            SessionId(3)
+        );
+        assert_eq!(
+            session_id_from_block_num::<TBlock>(40, &schedule),
+            SessionId(4)
+        );
+    }
+
+    #[test]
+    fn boundaries_follow_period_changes() {
+        let schedule =
+            SessionSchedule::new(vec![change(0, 0, 10), change(2, 20, 5), change(4, 30, 20)])
+                .unwrap();
+        let boundaries: Vec<_> = (0..6)
+            .map(|id| {
+                (
+                    first_block_of_session::<TBlock>(SessionId(id), &schedule),
+                    last_block_of_session::<TBlock>(SessionId(id), &schedule),
+                )
+            })
+            .collect();
+        assert_eq!(
+            boundaries,
+            vec![(0, 9), (10, 19), (20, 24), (25, 29), (30, 49), (50, 69)]
+        );
+        for (id, (first, last)) in boundaries.into_iter().enumerate() {
+            let id = SessionId(id as u32);
+            assert_eq!(session_id_from_block_num::<TBlock>(first, &schedule), id);
+            assert_eq!(session_id_from_block_num::<TBlock>(last, &schedule), id);
+        }
+    }
+
+    #[test]
+    fn pruned_schedule_gives_the_same_boundaries() {
+        let full =
+            SessionSchedule::new(vec![change(0, 0, 10), change(2, 20, 5), change(4, 30, 20)])
+                .unwrap();
+        let pruned = SessionSchedule::new(vec![change(2, 20, 5), change(4, 30, 20)]).unwrap();
+        for id in 2..6 {
+            let id = SessionId(id);
+            assert_eq!(
+                first_block_of_session::<TBlock>(id, &pruned),
+                first_block_of_session::<TBlock>(id, &full)
+            );
+            assert_eq!(
+                last_block_of_session::<TBlock>(id, &pruned),
+                last_block_of_session::<TBlock>(id, &full)
+            );
+        }
+        assert_eq!(
+            session_id_from_block_num::<TBlock>(25, &pruned),
+            SessionId(3)
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_schedules() {
+        assert!(SessionSchedule::new(Vec::new()).is_none());
+        assert!(SessionSchedule::new(vec![change(0, 0, 10), change(0, 0, 5)]).is_none());
+        assert!(SessionSchedule::new(vec![change(0, 0, 10), change(3, 30, 0)]).is_none());
+        assert!(SessionSchedule::new(vec![change(0, 0, 10), change(2, 15, 5)]).is_none());
+    }
+}
@@ -1,10 +1,15 @@
 use crate::{
-    first_block_of_session, session_id_from_block_num, ClientForAleph, SessionId, SessionPeriod,
+    first_block_of_session,
+    session::{
+        constant_schedule, supports_session_schedule, SessionSchedule, SharedSessionSchedule,
+    },
+    session_id_from_block_num, ClientForAleph, SessionId, SessionPeriod,
 };
-use aleph_primitives::{AlephSessionApi, AuthorityId};
+use aleph_primitives::{AlephSessionApi, AuthorityId, SessionPeriodChange};
+use codec::{Decode, Encode};
 use futures::StreamExt;
-use log::{debug, error, trace};
-use sc_client_api::{Backend, FinalityNotification};
+use log::{debug, error, trace, warn};
+use sc_client_api::{AuxStore, Backend, FinalityNotification};
 use sc_utils::mpsc::TracingUnboundedReceiver;
 use sp_runtime::{
     generic::BlockId,
@@ -18,14 +23,18 @@ use tokio::sync::{
 };
 
 const PRUNING_THRESHOLD: u32 = 10;
+const SESSION_AUTHORITIES_KEY_PREFIX: &[u8] = b"aleph_session_authorities";
 type SessionMap = HashMap<SessionId, Vec<AuthorityId>>;
 type SessionSubscribers = HashMap<SessionId, Vec<OneShotSender<Vec<AuthorityId>>>>;
 
 pub trait AuthorityProvider<B> {
     /// returns authorities for block
     fn authorities(&self, block: B) -> Option<Vec<AuthorityId>>;
-    /// returns next session authorities where current session is for block
+    /// returns next session authorities where current session is for block, an emergency override
+    /// of the authorities is already taken into account by the runtime
     fn next_authorities(&self, block: B) -> Option<Vec<AuthorityId>>;
+    /// returns the schedule of session lengths as of block
+    fn session_schedule(&self, block: B) -> Option<Vec<SessionPeriodChange>>;
 }
 
 /// Default implementation of authority provider trait.
@@ -77,6 +86,20 @@ where
             .ok()
             .flatten()
     }
+
+    fn session_schedule(&self, num: NumberFor<B>) -> Option<Vec<SessionPeriodChange>> {
+        let at = BlockId::Number(num);
+        // Older runtimes only know a constant session period.
+        if !supports_session_schedule(self.client.as_ref(), &at) {
+            return self
+                .client
+                .runtime_api()
+                .session_period(&at)
+                .ok()
+                .map(|period| constant_schedule(SessionPeriod(period)));
+        }
+        self.client.runtime_api().session_period_schedule(&at).ok()
+    }
 }
 
 pub trait FinalityNotificator<B, N> {
@@ -128,20 +151,90 @@ where
     }
 }
 
+/// Persistent index of the authorities of finalized sessions. Entries are never overwritten,
+/// as authorities of a finalized session cannot change.
+pub trait SessionAuthorityStore: Send + Sync {
+    /// returns authorities stored for the session
+    fn load(&self, id: SessionId) -> Option<Vec<AuthorityId>>;
+    /// stores authorities for the session, unless some are already stored
+    fn store(&self, id: SessionId, authorities: &[AuthorityId]);
+}
+
+/// Default implementation of the session authority store, keeping the index in the aux DB.
+pub struct AuxSessionAuthorityStore<C: AuxStore + Send + Sync> {
+    client: Arc<C>,
+}
+
+impl<C: AuxStore + Send + Sync> AuxSessionAuthorityStore<C> {
+    pub fn new(client: Arc<C>) -> Self {
+        Self { client }
+    }
+}
+
+fn session_authorities_key(id: SessionId) -> Vec<u8> {
+    let mut key = SESSION_AUTHORITIES_KEY_PREFIX.to_vec();
+    key.extend(id.encode());
+    key
+}
+
+impl<C: AuxStore + Send + Sync> SessionAuthorityStore for AuxSessionAuthorityStore<C> {
+    fn load(&self, id: SessionId) -> Option<Vec<AuthorityId>> {
+        let encoded = match self.client.get_aux(&session_authorities_key(id)) {
+            Ok(encoded) => encoded?,
+            Err(e) => {
+                warn!(target: "aleph-session-updater", "Failed to read authorities of session {:?} from the aux DB: {:?}", id.0, e);
+                return None;
+            }
+        };
+        match Vec::<AuthorityId>::decode(&mut &encoded[..]) {
+            Ok(authorities) => Some(authorities),
+            Err(e) => {
+                error!(target: "aleph-session-updater", "Corrupted authorities of session {:?} in the aux DB: {:?}", id.0, e);
+                None
+            }
+        }
+    }
+
+    fn store(&self, id: SessionId, authorities: &[AuthorityId]) {
+        if self.load(id).is_some() {
+            return;
+        }
+        let key = session_authorities_key(id);
+        if let Err(e) = self
+            .client
+            .insert_aux(&[(&key[..], &authorities.encode()[..])], &[])
+        {
+            error!(target: "aleph-session-updater", "Failed to write authorities of session {:?} to the aux DB: {:?}", id.0, e);
+        }
+    }
+}
+
 #[derive(Clone)]
 /// Wrapper around Mapping from sessionId to Vec of AuthorityIds allowing mutation
-/// and hiding locking details
-struct SharedSessionMap(Arc<RwLock<(SessionMap, SessionSubscribers)>>);
+/// and hiding locking details. Recent sessions are kept in memory, all of them are persisted.
+struct SharedSessionMap {
+    inner: Arc<RwLock<(SessionMap, SessionSubscribers)>>,
+    store: Arc<dyn SessionAuthorityStore>,
+}
 
 #[derive(Clone)]
 /// Wrapper around Mapping from sessionId to Vec of AuthorityIds allowing only reads
 pub struct ReadOnlySessionMap {
     inner: Arc<RwLock<(SessionMap, SessionSubscribers)>>,
+    store: Arc<dyn SessionAuthorityStore>,
 }
 
 impl SharedSessionMap {
-    fn new() -> Self {
-        Self(Arc::new(RwLock::new((HashMap::new(), HashMap::new()))))
+    fn new(store: Arc<dyn SessionAuthorityStore>) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new((HashMap::new(), HashMap::new()))),
+            store,
+        }
+    }
+
+    /// returns authorities of the session if they were already persisted
+    fn stored(&self, id: SessionId) -> Option<Vec<AuthorityId>> {
+        self.store.load(id)
     }
 
     async fn update(
@@ -149,7 +242,8 @@ impl SharedSessionMap {
         id: SessionId,
         authorities: Vec<AuthorityId>,
     ) -> Option<Vec<AuthorityId>> {
-        let mut guard = self.0.write().await;
+        self.store.store(id, &authorities);
+        let mut guard = self.inner.write().await;
 
         // notify all subscribers about insertion and remove them from subscription
         if let Some(senders) = guard.1.remove(&id) {
@@ -163,8 +257,9 @@ impl SharedSessionMap {
         guard.0.insert(id, authorities)
     }
 
+    /// prunes the in-memory map, persisted sessions stay available
     async fn prune_below(&mut self, id: SessionId) {
-        let mut guard = self.0.write().await;
+        let mut guard = self.inner.write().await;
 
         guard.0.retain(|&s, _| s >= id);
         guard.1.retain(|&s, _| s >= id);
@@ -172,14 +267,18 @@ impl SharedSessionMap {
 
     fn read_only(&self) -> ReadOnlySessionMap {
         ReadOnlySessionMap {
-            inner: self.0.clone(),
+            inner: self.inner.clone(),
+            store: self.store.clone(),
         }
     }
 }
 
 impl ReadOnlySessionMap {
     pub async fn get(&self, id: SessionId) -> Option<Vec<AuthorityId>> {
-        self.inner.read().await.0.get(&id).cloned()
+        match self.inner.read().await.0.get(&id) {
+            Some(authorities) => Some(authorities.clone()),
+            None => self.store.load(id),
+        }
     }
 
     /// returns an end of the oneshot channel that fires a message if either authorities are already
@@ -189,11 +288,9 @@ impl ReadOnlySessionMap {
 
         let mut guard = self.inner.write().await;
 
-        if let Some(authorities) = guard.0.get(&id) {
+        if let Some(authorities) = guard.0.get(&id).cloned().or_else(|| self.store.load(id)) {
             // if the value is already present notify immediately
-            sender
-                .send(authorities.clone())
-                .expect("we control both ends");
+            sender.send(authorities).expect("we control both ends");
         } else {
             guard.1.entry(id).or_insert_with(Vec::new).push(sender);
         }
@@ -230,6 +327,7 @@ where
     B: Block,
 {
     session_map: SharedSessionMap,
+    session_schedule: SharedSessionSchedule,
     authority_provider: AP,
     finality_notificator: FN,
     _phantom: PhantomData<B>,
@@ -241,13 +339,22 @@ where
     FN: FinalityNotificator<FinalityNotification<B>, NumberFor<B>>,
     B: Block,
 {
-    pub fn new(authority_provider: AP, finality_notificator: FN) -> Self {
-        Self {
-            session_map: SharedSessionMap::new(),
+    /// The session period is only used until the runtime provides a session schedule.
+    pub fn new(
+        authority_provider: AP,
+        finality_notificator: FN,
+        authority_store: Arc<dyn SessionAuthorityStore>,
+        session_period: SessionPeriod,
+    ) -> Self {
+        let updater = Self {
+            session_map: SharedSessionMap::new(authority_store),
+            session_schedule: SharedSessionSchedule::new(session_period.into()),
             authority_provider,
             finality_notificator,
             _phantom: PhantomData,
-        }
+        };
+        updater.update_schedule(updater.finality_notificator.last_finalized());
+        updater
     }
 
     /// returns readonly view of the session map
@@ -255,27 +362,43 @@ where
         self.session_map.read_only()
     }
 
+    /// returns the session schedule kept up to date with the finalized chain
+    pub fn session_schedule(&self) -> SharedSessionSchedule {
+        self.session_schedule.clone()
+    }
+
+    /// reads the session schedule as of the given finalized block
+    fn update_schedule(&self, num: NumberFor<B>) {
+        match self
+            .authority_provider
+            .session_schedule(num)
+            .and_then(SessionSchedule::new)
+        {
+            Some(schedule) => self.session_schedule.set(schedule),
+            None => {
+                trace!(target: "aleph-session-updater", "No valid session schedule at block #{:?}, keeping the previous one", num)
+            }
+        }
+    }
+
+    /// puts authorities of the session into the session map, querying the runtime only if they
+    /// were not persisted before
+    async fn insert_session(&mut self, session_id: SessionId, num: NumberFor<B>) {
+        let authorities = match self.session_map.stored(session_id) {
+            Some(authorities) => authorities,
+            None => get_authorities_for_session::<_, B>(&self.authority_provider, session_id, num),
+        };
+        self.session_map.update(session_id, authorities).await;
+    }
+
     /// puts authorities for the next session into the session map
     async fn handle_first_block_of_session(&mut self, num: NumberFor<B>, session_id: SessionId) {
         debug!(target: "aleph-session-updater", "Handling first block #{:?} of session {:?}", num, session_id.0);
-        let next_session = SessionId(session_id.0 + 1);
-        let authority_provider = &self.authority_provider;
-        self.session_map
-            .update(
-                next_session,
-                get_authorities_for_session::<_, B>(authority_provider, next_session, num),
-            )
-            .await;
+        self.insert_session(SessionId(session_id.0 + 1), num).await;
 
         // if this is the first session we also need to include starting authorities into the map
         if session_id.0 == 0 {
-            let authority_provider = &self.authority_provider;
-            self.session_map
-                .update(
-                    session_id,
-                    get_authorities_for_session::<_, B>(authority_provider, session_id, num),
-                )
-                .await;
+            self.insert_session(session_id, num).await;
         }
 
         if session_id.0 >= PRUNING_THRESHOLD && session_id.0 % PRUNING_THRESHOLD == 0 {
@@ -286,29 +409,30 @@ where
         }
     }
 
-    async fn update_session(&mut self, session_id: SessionId, period: SessionPeriod) {
-        let first_block = first_block_of_session::<B>(session_id, period);
+    async fn update_session(&mut self, session_id: SessionId) {
+        let first_block = first_block_of_session::<B>(session_id, &self.session_schedule.get());
         self.handle_first_block_of_session(first_block, session_id)
             .await;
     }
 
-    fn catch_up_boundaries(&self, period: SessionPeriod) -> (SessionId, SessionId) {
+    fn catch_up_boundaries(&self) -> (SessionId, SessionId) {
         let last_finalized = self.finality_notificator.last_finalized();
 
-        let current_session = session_id_from_block_num::<B>(last_finalized, period);
+        let current_session =
+            session_id_from_block_num::<B>(last_finalized, &self.session_schedule.get());
         let starting_session = SessionId(current_session.0.saturating_sub(PRUNING_THRESHOLD));
 
         (starting_session, current_session)
     }
 
-    pub async fn run(mut self, period: SessionPeriod) {
+    pub async fn run(mut self) {
         let mut notifications = self.finality_notificator.notification_stream();
 
-        let (starting_session, current_session) = self.catch_up_boundaries(period);
+        let (starting_session, current_session) = self.catch_up_boundaries();
 
         // lets catch up
         for session in starting_session.0..=current_session.0 {
-            self.update_session(SessionId(session), period).await;
+            self.update_session(SessionId(session)).await;
         }
 
         let mut last_updated = current_session;
@@ -317,14 +441,16 @@ where
             let last_finalized = header.number();
             trace!(target: "aleph-session-updater", "got FinalityNotification about #{:?}", last_finalized);
 
-            let session_id = session_id_from_block_num::<B>(*last_finalized, period);
+            self.update_schedule(*last_finalized);
+            let session_id =
+                session_id_from_block_num::<B>(*last_finalized, &self.session_schedule.get());
 
             if last_updated >= session_id {
                 continue;
             }
 
             for session in (last_updated.0 + 1)..=session_id.0 {
-                self.update_session(SessionId(session), period).await;
+                self.update_session(SessionId(session)).await;
             }
 
             last_updated = session_id;
@@ -351,6 +477,7 @@ mod tests {
     struct MockProvider {
         pub session_map: HashMap<NumberFor<TBlock>, Vec<AuthorityId>>,
         pub next_session_map: HashMap<NumberFor<TBlock>, Vec<AuthorityId>>,
+        pub session_schedule: Option<Vec<SessionPeriodChange>>,
         pub asked_for: Arc<Mutex<Vec<NumberFor<TBlock>>>>,
     }
 
@@ -359,11 +486,29 @@ mod tests {
         pub receiver: Mutex<Option<TracingUnboundedReceiver<FinalityNotification<TBlock>>>>,
     }
 
+    #[derive(Clone, Default)]
+    struct MockStore(Arc<Mutex<HashMap<SessionId, Vec<AuthorityId>>>>);
+
+    impl SessionAuthorityStore for MockStore {
+        fn load(&self, id: SessionId) -> Option<Vec<AuthorityId>> {
+            self.0.lock().unwrap().get(&id).cloned()
+        }
+
+        fn store(&self, id: SessionId, authorities: &[AuthorityId]) {
+            self.0
+                .lock()
+                .unwrap()
+                .entry(id)
+                .or_insert_with(|| authorities.to_vec());
+        }
+    }
+
     impl MockProvider {
         fn new() -> Self {
             Self {
                 session_map: HashMap::new(),
                 next_session_map: HashMap::new(),
+                session_schedule: None,
                 asked_for: Arc::new(Mutex::new(Vec::new())),
             }
         }
@@ -390,6 +535,10 @@ mod tests {
             asked.push(b);
             self.next_session_map.get(&b).cloned()
         }
+
+        fn session_schedule(&self, _: NumberFor<TBlock>) -> Option<Vec<SessionPeriodChange>> {
+            self.session_schedule.clone()
+        }
     }
 
     impl FinalityNotificator<FinalityNotification<TBlock>, NumberFor<TBlock>> for MockNotificator {
@@ -441,7 +590,12 @@ mod tests {
             .next_session_map
             .insert(2, authorities(12, 16));
 
-        let updater = SessionMapUpdater::new(mock_provider, mock_notificator);
+        let updater = SessionMapUpdater::new(
+            mock_provider,
+            mock_notificator,
+            Arc::new(MockStore::default()),
+            SessionPeriod(1),
+        );
         let session_map = updater.readonly_session_map();
 
         let blocks = n_new_blocks(&mut client, 2);
@@ -464,7 +618,7 @@ mod tests {
             })
             .unwrap();
 
-        let _handle = tokio::spawn(updater.run(SessionPeriod(1)));
+        let _handle = tokio::spawn(updater.run());
 
         // wait a bit
         Delay::new(Duration::from_millis(50)).await;
@@ -496,10 +650,15 @@ mod tests {
 
         mock_notificator.last_finalized = 2;
 
-        let updater = SessionMapUpdater::new(mock_provider, mock_notificator);
+        let updater = SessionMapUpdater::new(
+            mock_provider,
+            mock_notificator,
+            Arc::new(MockStore::default()),
+            SessionPeriod(1),
+        );
         let session_map = updater.readonly_session_map();
 
-        let _handle = tokio::spawn(updater.run(SessionPeriod(1)));
+        let _handle = tokio::spawn(updater.run());
 
         // wait a bit
         Delay::new(Duration::from_millis(50)).await;
@@ -516,6 +675,65 @@ mod tests {
         );
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn follows_session_schedule() {
+        let (_sender, receiver) = tracing_unbounded("test");
+        let mut mock_provider = MockProvider::new();
+        let mut mock_notificator = MockNotificator::new(receiver);
+
+        mock_provider.session_schedule = Some(vec![
+            SessionPeriodChange {
+                session: 0,
+                first_block: 0,
+                period: 1,
+            },
+            SessionPeriodChange {
+                session: 2,
+                first_block: 2,
+                period: 3,
+            },
+        ]);
+        mock_provider.session_map.insert(0, authorities(0, 4));
+        mock_provider.next_session_map.insert(0, authorities(4, 8));
+        mock_provider.next_session_map.insert(1, authorities(8, 12));
+        mock_provider
+            .next_session_map
+            .insert(2, authorities(12, 16));
+        mock_provider
+            .next_session_map
+            .insert(5, authorities(16, 20));
+
+        mock_notificator.last_finalized = 5;
+
+        let asked = mock_provider.asked_for.clone();
+        let updater = SessionMapUpdater::new(
+            mock_provider,
+            mock_notificator,
+            Arc::new(MockStore::default()),
+            SessionPeriod(1),
+        );
+        let session_map = updater.readonly_session_map();
+        let session_schedule = updater.session_schedule();
+
+        let _handle = tokio::spawn(updater.run());
+
+        // wait a bit
+        Delay::new(Duration::from_millis(50)).await;
+
+        assert_eq!(
+            first_block_of_session::<TBlock>(SessionId(3), &session_schedule.get()),
+            5
+        );
+        {
+            let asked = asked.lock().unwrap();
+            assert_eq!(vec![0, 0, 1, 2, 5], *asked);
+        }
+        assert_eq!(
+            session_map.get(SessionId(4)).await,
+            Some(authorities(16, 20))
+        );
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn prunes_old_sessions() {
         let (_sender, receiver) = tracing_unbounded("test");
@@ -533,10 +751,15 @@ mod tests {
         mock_notificator.last_finalized = 20;
 
         let asked = mock_provider.asked_for.clone();
-        let updater = SessionMapUpdater::new(mock_provider, mock_notificator);
+        let updater = SessionMapUpdater::new(
+            mock_provider,
+            mock_notificator,
+            Arc::new(MockStore::default()),
+            SessionPeriod(1),
+        );
         let session_map = updater.readonly_session_map();
 
-        let _handle = tokio::spawn(updater.run(SessionPeriod(1)));
+        let _handle = tokio::spawn(updater.run());
 
         // wait a bit
         Delay::new(Duration::from_millis(50)).await;
@@ -563,9 +786,85 @@ mod tests {
         }
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn restarts_from_persisted_sessions() {
+        let store = MockStore::default();
+        let (_sender, receiver) = tracing_unbounded("test");
+        let mut mock_provider = MockProvider::new();
+        let mut mock_notificator = MockNotificator::new(receiver);
+
+        mock_provider.session_map.insert(0, authorities(0, 4));
+        mock_provider.next_session_map.insert(0, authorities(4, 8));
+        mock_provider.next_session_map.insert(1, authorities(8, 12));
+        mock_provider
+            .next_session_map
+            .insert(2, authorities(12, 16));
+        mock_notificator.last_finalized = 2;
+
+        let updater = SessionMapUpdater::new(
+            mock_provider,
+            mock_notificator,
+            Arc::new(store.clone()),
+            SessionPeriod(1),
+        );
+        let handle = tokio::spawn(updater.run());
+        Delay::new(Duration::from_millis(50)).await;
+        handle.abort();
+
+        // the state is gone, the runtime would not be able to answer any queries
+        let (_sender, receiver) = tracing_unbounded("test");
+        let mock_provider = MockProvider::new();
+        let mut mock_notificator = MockNotificator::new(receiver);
+        mock_notificator.last_finalized = 2;
+
+        let asked = mock_provider.asked_for.clone();
+        let updater = SessionMapUpdater::new(
+            mock_provider,
+            mock_notificator,
+            Arc::new(store),
+            SessionPeriod(1),
+        );
+        let session_map = updater.readonly_session_map();
+        let _handle = tokio::spawn(updater.run());
+        Delay::new(Duration::from_millis(50)).await;
+
+        assert!(asked.lock().unwrap().is_empty());
+        for i in 0..4 {
+            assert_eq!(
+                session_map.get(SessionId(i)).await,
+                Some(authorities(4 * i as u64, 4 * (i + 1) as u64)),
+            );
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn pruned_sessions_are_read_from_store() {
+        let mut shared = SharedSessionMap::new(Arc::new(MockStore::default()));
+        let readonly = shared.read_only();
+
+        shared.update(SessionId(0), authorities(0, 2)).await;
+        shared.prune_below(SessionId(1)).await;
+
+        assert!(shared.inner.read().await.0.is_empty());
+        assert_eq!(readonly.get(SessionId(0)).await, Some(authorities(0, 2)));
+        let receiver = readonly.subscribe_to_insertion(SessionId(0)).await;
+        assert_eq!(Ok(authorities(0, 2)), receiver.await);
+    }
+
+    #[test]
+    fn aux_store_does_not_overwrite_sessions() {
+        let store = AuxSessionAuthorityStore::new(Arc::new(TestClientBuilder::new().build()));
+
+        store.store(SessionId(1), &authorities(0, 4));
+        store.store(SessionId(1), &authorities(4, 8));
+
+        assert_eq!(store.load(SessionId(1)), Some(authorities(0, 4)));
+        assert_eq!(store.load(SessionId(2)), None);
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn subscription_with_already_defined_session_works() {
-        let mut shared = SharedSessionMap::new();
+        let mut shared = SharedSessionMap::new(Arc::new(MockStore::default()));
         let readonly = shared.read_only();
         let session = SessionId(0);
 
@@ -579,7 +878,7 @@ mod tests {
 
     #[tokio::test(flavor = "multi_thread")]
     async fn notifies_on_insertion() {
-        let mut shared = SharedSessionMap::new();
+        let mut shared = SharedSessionMap::new(Arc::new(MockStore::default()));
         let readonly = shared.read_only();
         let session = SessionId(0);
         let mut receiver = readonly.subscribe_to_insertion(session).await;
@@ -1,5 +1,8 @@
 use crate::{
-    data_io::{AlephData, AlephNetworkMessage, DataStore, DataStoreConfig, MAX_DATA_BRANCH_LEN},
+    data_io::{
+        AlephData, AlephNetworkMessage, DataStore, DataStoreConfig, DataStoreMonitor,
+        DataStoreStatusHandle, ObservedProposals, MAX_DATA_BRANCH_LEN,
+    },
     network::{DataNetwork, RequestBlocks, SimpleNetwork},
     session::{SessionBoundaries, SessionId, SessionPeriod},
     testing::{
@@ -8,6 +11,7 @@ use crate::{
     },
     BlockHashNum,
 };
+use aleph_bft::NodeCount;
 use futures::{
     channel::{
         mpsc::{self, UnboundedReceiver, UnboundedSender},
@@ -24,7 +28,7 @@ use substrate_test_runtime_client::{
     DefaultTestClientBuilderExt, TestClientBuilder, TestClientBuilderExt,
 };
 
-use tokio::time::timeout;
+use tokio::time::{sleep, timeout};
 
 #[derive(Clone)]
 struct TestBlockRequester<B: BlockT> {
@@ -81,6 +85,7 @@ struct TestHandler {
     justification_requests_rx: UnboundedReceiver<BlockHashNum<Block>>,
     network_tx: UnboundedSender<TestData>,
     network: Box<dyn DataNetwork<TestData>>,
+    data_store_status: DataStoreStatusHandle,
 }
 
 impl TestHandler {
@@ -167,18 +172,23 @@ fn prepare_data_store(
         available_proposals_cache_capacity: 8000,
         periodic_maintenance_interval: Duration::from_millis(20),
         request_block_after: Duration::from_millis(30),
+        chain_info_cache: Default::default(),
     };
 
+    let data_store_status = DataStoreStatusHandle::default();
     let session_boundaries = if let Some(session_boundaries) = session_boundaries {
         session_boundaries
     } else {
-        SessionBoundaries::new(SessionId(0), SessionPeriod(900))
+        SessionBoundaries::new(SessionId(0), &SessionPeriod(900).into())
     };
     let (mut data_store, network) = DataStore::new(
         session_boundaries,
+        MAX_DATA_BRANCH_LEN,
+        ObservedProposals::new(NodeCount(4)),
         client.clone(),
         block_requester,
         data_store_config,
+        DataStoreMonitor::new(data_store_status.clone(), None),
         test_network,
     );
 
@@ -196,12 +206,15 @@ fn prepare_data_store(
             justification_requests_rx,
             network_tx,
             network: Box::new(network),
+            data_store_status,
         },
     )
 }
 
 const TIMEOUT_SUCC: Duration = Duration::from_millis(5000);
 const TIMEOUT_FAIL: Duration = Duration::from_millis(200);
+// Several maintenance intervals of the test data store.
+const MAINTENANCE_WAIT: Duration = Duration::from_millis(100);
 
 // This is the basic assumption for other tests, so we better test it, in case this somehow changes in the future.
 #[tokio::test]
@@ -269,7 +282,7 @@ async fn too_long_branch_message_does_not_go_through() {
 
 #[tokio::test]
 async fn branch_not_within_session_boundaries_does_not_go_through() {
-    let session_boundaries = SessionBoundaries::new(SessionId(1), SessionPeriod(20));
+    let session_boundaries = SessionBoundaries::new(SessionId(1), &SessionPeriod(20).into());
     let session_start = session_boundaries.first_block() as usize;
     let session_end = session_boundaries.last_block() as usize;
 
@@ -448,6 +461,41 @@ async fn sends_block_request_on_missing_block() {
     .await;
 }
 
+#[tokio::test]
+async fn reports_pending_proposals_in_status() {
+    run_test(|mut test_handler| async move {
+        let blocks = test_handler
+            .initialize_single_branch(MAX_DATA_BRANCH_LEN * 10)
+            .await;
+        let test_data: TestData = vec![aleph_data_from_blocks(blocks[0..2].to_vec())];
+        test_handler.send_data(test_data);
+
+        timeout(TIMEOUT_SUCC, test_handler.next_block_request())
+            .await
+            .expect("Did not receive block request from Data Store");
+        // Let the maintenance finish and report the status.
+        sleep(MAINTENANCE_WAIT).await;
+        let status = test_handler.data_store_status.status();
+        assert_eq!(status.pending_messages, 1);
+        assert_eq!(status.pending_proposals.len(), 1);
+        let pending = &status.pending_proposals[0];
+        assert_eq!(pending.top_block_hash, format!("{:?}", blocks[1].hash()));
+        assert_eq!(pending.top_block_number, 2);
+        assert_eq!(pending.branch_len, 2);
+        assert_eq!(pending.waiting_messages, 1);
+
+        test_handler.import_branch(blocks).await;
+        test_handler
+            .assert_message_out("Did not receive message from Data Store")
+            .await;
+        sleep(MAINTENANCE_WAIT).await;
+        let status = test_handler.data_store_status.status();
+        assert_eq!(status.pending_messages, 0);
+        assert!(status.pending_proposals.is_empty());
+    })
+    .await;
+}
+
 #[tokio::test]
 async fn sends_justification_request_when_not_finalized() {
     run_test(|mut test_handler| async move {
@@ -2,7 +2,9 @@ use std::sync::{Arc, Mutex};
 
 use crate::{
     justification::{AlephJustification, SessionInfo, SessionInfoProvider, Verifier},
-    last_block_of_session, session_id_from_block_num,
+    last_block_of_session,
+    session::SessionSchedule,
+    session_id_from_block_num,
     testing::mocks::{AcceptancePolicy, TBlock, THash, TNumber},
     SessionPeriod,
 };
@@ -18,14 +20,14 @@ impl Verifier<TBlock> for VerifierWrapper {
 }
 
 pub(crate) struct SessionInfoProviderImpl {
-    session_period: SessionPeriod,
+    session_schedule: SessionSchedule,
     acceptance_policy: Arc<Mutex<AcceptancePolicy>>,
 }
 
 impl SessionInfoProviderImpl {
     pub(crate) fn new(session_period: SessionPeriod, acceptance_policy: AcceptancePolicy) -> Self {
         Self {
-            session_period,
+            session_schedule: session_period.into(),
             acceptance_policy: Arc::new(Mutex::new(acceptance_policy)),
         }
     }
@@ -34,12 +36,12 @@ impl SessionInfoProviderImpl {
 #[async_trait::async_trait]
 impl SessionInfoProvider<TBlock, VerifierWrapper> for SessionInfoProviderImpl {
     async fn for_block_num(&self, number: TNumber) -> SessionInfo<TBlock, VerifierWrapper> {
-        let current_session = session_id_from_block_num::<TBlock>(number, self.session_period);
+        let current_session = session_id_from_block_num::<TBlock>(number, &self.session_schedule);
         SessionInfo {
             current_session,
             last_block_height: last_block_of_session::<TBlock>(
                 current_session,
-                self.session_period,
+                &self.session_schedule,
             ),
             verifier: match &*self.acceptance_policy.lock().unwrap() {
                 AcceptancePolicy::Unavailable => None,
@@ -3,3 +3,4 @@ mod data_store;
 mod justification;
 pub(crate) mod mocks;
 mod network;
+mod simulated_network;
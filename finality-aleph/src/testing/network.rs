@@ -5,8 +5,9 @@ use crate::{
             crypto_basics, Authentication, DiscoveryMessage, MockNetwork, MockNetworkIdentity,
             NetworkData, SessionHandler,
         },
-        ConnectionIO, ConnectionManager, ConnectionManagerConfig, DataNetwork, NetworkIdentity,
-        PeerId, Protocol, Service as NetworkService, SessionManager, SessionNetwork,
+        ComponentNetwork, ConnectionIO, ConnectionManager, ConnectionManagerConfig, DataNetwork,
+        NetworkIdentity, NetworkStatusHandle, PeerId, PeerReputationConfig, Protocol,
+        ReceiverComponent, Service as NetworkService, SessionManager, SessionNetwork,
         IO as NetworkIO,
     },
     MillisecsPerBlock, NodeIndex, SessionId, SessionPeriod,
@@ -97,6 +98,7 @@ async fn prepare_one_session_test_data() -> TestData {
     let (commands_for_service, commands_from_user) = mpsc::unbounded();
     let (messages_for_service, commands_from_manager) = mpsc::unbounded();
     let (messages_for_user, messages_from_network) = mpsc::unbounded();
+    let network_status = NetworkStatusHandle::default();
 
     let connection_io = ConnectionIO::new(
         commands_for_network,
@@ -104,6 +106,7 @@ async fn prepare_one_session_test_data() -> TestData {
         commands_from_user,
         commands_from_manager,
         messages_from_network,
+        network_status.clone(),
     );
     let connection_manager = ConnectionManager::<Authority, MockData>::new(
         authorities[0].clone(),
@@ -113,7 +116,14 @@ async fn prepare_one_session_test_data() -> TestData {
     let network_service = NetworkService::new(
         network.clone(),
         task_manager.spawn_handle(),
-        NetworkIO::new(messages_from_user, messages_for_user, commands_from_io),
+        NetworkIO::new(
+            messages_from_user,
+            messages_for_user,
+            commands_from_io,
+            network_status,
+        ),
+        PeerReputationConfig::default(),
+        None,
     );
 
     let network_manager_task = async move {
@@ -540,9 +550,13 @@ async fn test_connects_to_others() {
         1,
         vec![NetworkData::Data(data.clone(), SessionId(session_id))],
     );
+    let receiver = data_network.receiver();
     assert_eq!(
-        timeout(DEFAULT_TIMEOUT, data_network.next()).await,
-        Ok(Some(data))
+        timeout(DEFAULT_TIMEOUT, async {
+            receiver.lock().await.next_with_sender().await
+        })
+        .await,
+        Ok(Some((data, Some(NodeIndex(1)))))
     );
 
     test_data.cleanup().await;
@@ -0,0 +1,313 @@
+use crate::{
+    crypto::{AuthorityPen, AuthorityVerifier},
+    network::{
+        testing::{crypto_basics, LinkConfig, SimulatedNetwork, SimulatedNetworkHub, Topology},
+        ConnectionIO, ConnectionManager, ConnectionManagerConfig, DataNetwork, NetworkStatusHandle,
+        PeerId, PeerReputationConfig, Service as NetworkService, SessionManager, SessionNetwork,
+        IO as NetworkIO,
+    },
+    MillisecsPerBlock, NodeIndex, SessionId, SessionPeriod,
+};
+
+use aleph_bft::Recipient;
+use futures::channel::{mpsc, oneshot};
+use sc_service::TaskManager;
+use std::{collections::HashSet, time::Duration};
+use tokio::{
+    runtime::Handle,
+    task::JoinHandle,
+    time::{sleep_until, timeout, Instant},
+};
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(20);
+const RETRY_INTERVAL: Duration = Duration::from_millis(200);
+const SESSION_PERIOD: SessionPeriod = SessionPeriod(10);
+const MILLISECS_PER_BLOCK: MillisecsPerBlock = MillisecsPerBlock(100);
+const SESSION_ID: SessionId = SessionId(43);
+type MockData = Vec<u8>;
+
+struct SimulatedNode {
+    network: SimulatedNetwork,
+    session_manager: SessionManager<MockData>,
+    network_manager_exit_tx: oneshot::Sender<()>,
+    network_service_exit_tx: oneshot::Sender<()>,
+    network_manager_handle: JoinHandle<()>,
+    network_service_handle: JoinHandle<()>,
+    // `TaskManager` can't be dropped for `SpawnTaskHandle` to work
+    _task_manager: TaskManager,
+}
+
+impl SimulatedNode {
+    fn new(network: SimulatedNetwork) -> Self {
+        let task_manager = TaskManager::new(Handle::current(), None).unwrap();
+        let (network_manager_exit_tx, network_manager_exit_rx) = oneshot::channel();
+        let (network_service_exit_tx, network_service_exit_rx) = oneshot::channel();
+
+        let (commands_for_network, commands_from_io) = mpsc::unbounded();
+        let (messages_for_network, messages_from_user) = mpsc::unbounded();
+        let (commands_for_service, commands_from_user) = mpsc::unbounded();
+        let (messages_for_service, commands_from_manager) = mpsc::unbounded();
+        let (messages_for_user, messages_from_network) = mpsc::unbounded();
+        let network_status = NetworkStatusHandle::default();
+
+        let connection_io = ConnectionIO::new(
+            commands_for_network,
+            messages_for_network,
+            commands_from_user,
+            commands_from_manager,
+            messages_from_network,
+            network_status.clone(),
+        );
+        let connection_manager = ConnectionManager::<SimulatedNetwork, MockData>::new(
+            network.clone(),
+            ConnectionManagerConfig::with_session_period(&SESSION_PERIOD, &MILLISECS_PER_BLOCK),
+        );
+        let session_manager = SessionManager::new(commands_for_service, messages_for_service);
+        let network_service = NetworkService::new(
+            network.clone(),
+            task_manager.spawn_handle(),
+            NetworkIO::new(
+                messages_from_user,
+                messages_for_user,
+                commands_from_io,
+                network_status,
+            ),
+            PeerReputationConfig::default(),
+            None,
+        );
+
+        let network_manager_handle = tokio::spawn(async move {
+            tokio::select! {
+                _ = connection_io.run(connection_manager) => { },
+                _ = network_manager_exit_rx => { },
+            };
+        });
+        let network_service_handle = tokio::spawn(async move {
+            tokio::select! {
+                _ = network_service.run() => { },
+                _ = network_service_exit_rx => { },
+            };
+        });
+
+        SimulatedNode {
+            network,
+            session_manager,
+            network_manager_exit_tx,
+            network_service_exit_tx,
+            network_manager_handle,
+            network_service_handle,
+            _task_manager: task_manager,
+        }
+    }
+
+    fn peer_id(&self) -> PeerId {
+        self.network.peer_id()
+    }
+
+    async fn cleanup(self) {
+        self.network_manager_exit_tx.send(()).unwrap();
+        self.network_service_exit_tx.send(()).unwrap();
+        self.network_manager_handle.await.unwrap();
+        self.network_service_handle.await.unwrap();
+    }
+}
+
+struct Simulation {
+    hub: SimulatedNetworkHub,
+    nodes: Vec<SimulatedNode>,
+    sessions: Vec<SessionNetwork<MockData>>,
+}
+
+impl Simulation {
+    async fn start(nodes_n: usize, link: LinkConfig) -> Self {
+        let hub = SimulatedNetworkHub::new(link, 0);
+        let nodes: Vec<_> = (0..nodes_n)
+            .map(|index| SimulatedNode::new(hub.add_node(index as u8 + 1)))
+            .collect();
+        hub.wait_for_listeners().await;
+        hub.connect_all();
+
+        let (authority_pens, authority_verifier) = crypto_basics(nodes_n).await;
+        let mut sessions = Vec::new();
+        for (node, (node_index, pen)) in nodes.iter().zip(authority_pens.into_iter()) {
+            sessions.push(
+                start_session(
+                    &node.session_manager,
+                    authority_verifier.clone(),
+                    node_index,
+                    pen,
+                )
+                .await,
+            );
+        }
+
+        Simulation {
+            hub,
+            nodes,
+            sessions,
+        }
+    }
+
+    fn peer_ids(&self, indices: &[usize]) -> HashSet<PeerId> {
+        indices.iter().map(|i| self.nodes[*i].peer_id()).collect()
+    }
+
+    fn partition(&self, groups: &[&[usize]]) -> Topology {
+        Topology::Partitioned(groups.iter().map(|group| self.peer_ids(group)).collect())
+    }
+
+    /// Keeps broadcasting the data from the sender until the receiver gets it.
+    async fn deliver_eventually(&mut self, sender: usize, receiver: usize, data: MockData) {
+        timeout(DEFAULT_TIMEOUT, async {
+            loop {
+                // Sending fails only if the session was already stopped.
+                self.sessions[sender]
+                    .send(data.clone(), Recipient::Everyone)
+                    .expect("Session should be running");
+                while let Ok(Some(received)) =
+                    timeout(RETRY_INTERVAL, self.sessions[receiver].next()).await
+                {
+                    if received == data {
+                        return;
+                    }
+                }
+            }
+        })
+        .await
+        .expect("Data should eventually be delivered");
+    }
+
+    /// Checks that the receiver does not get the given data for a while.
+    async fn check_not_delivered(&mut self, receiver: usize, data: &MockData) {
+        let start = tokio::time::Instant::now();
+        while start.elapsed() < 5 * RETRY_INTERVAL {
+            if let Ok(Some(received)) =
+                timeout(RETRY_INTERVAL, self.sessions[receiver].next()).await
+            {
+                assert_ne!(&received, data, "Data should not cross the partition");
+            }
+        }
+    }
+
+    fn send(&self, sender: usize, data: MockData) {
+        self.sessions[sender]
+            .send(data, Recipient::Everyone)
+            .expect("Session should be running");
+    }
+
+    async fn cleanup(self) {
+        for node in self.nodes {
+            node.session_manager
+                .stop_session(SESSION_ID)
+                .expect("Failed to stop session");
+            node.cleanup().await;
+        }
+    }
+}
+
+async fn start_session(
+    session_manager: &SessionManager<MockData>,
+    authority_verifier: AuthorityVerifier,
+    node_index: NodeIndex,
+    pen: AuthorityPen,
+) -> SessionNetwork<MockData> {
+    session_manager
+        .start_validator_session(SESSION_ID, authority_verifier, node_index, pen)
+        .await
+        .expect("Failed to start validator session!")
+}
+
+#[tokio::test(start_paused = true)]
+async fn delivers_data_in_healthy_network() {
+    let mut simulation = Simulation::start(4, LinkConfig::default()).await;
+
+    for receiver in 1..4 {
+        simulation
+            .deliver_eventually(0, receiver, vec![receiver as u8])
+            .await;
+    }
+
+    simulation.cleanup().await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn delivers_data_over_lossy_and_slow_links() {
+    let link = LinkConfig::new(Duration::from_millis(30), 0.3);
+    let mut simulation = Simulation::start(4, link).await;
+
+    for sender in 0..4 {
+        let receiver = (sender + 1) % 4;
+        simulation
+            .deliver_eventually(sender, receiver, vec![sender as u8, receiver as u8])
+            .await;
+    }
+    assert!(simulation.hub.reports().is_empty());
+
+    simulation.cleanup().await;
+}
+
+#[tokio::test(start_paused = true)]
+async fn partition_blocks_delivery_until_healed() {
+    let mut simulation = Simulation::start(4, LinkConfig::default()).await;
+    simulation.deliver_eventually(0, 3, vec![0]).await;
+
+    let partition = simulation.partition(&[&[0, 1], &[2, 3]]);
+    simulation.hub.set_topology(partition);
+
+    simulation.deliver_eventually(0, 1, vec![1]).await;
+    simulation.deliver_eventually(2, 3, vec![2]).await;
+    let blocked = vec![3];
+    simulation.send(0, blocked.clone());
+    simulation.check_not_delivered(3, &blocked).await;
+
+    simulation.hub.set_topology(Topology::Healed);
+    simulation.deliver_eventually(0, 3, vec![4]).await;
+    simulation.deliver_eventually(3, 0, vec![5]).await;
+
+    simulation.cleanup().await;
+}
+
+// The time is paused, so every phase of the schedule lasts long enough to check the delivery in
+// it, no matter how fast the machine is.
+#[tokio::test(start_paused = true)]
+async fn recovers_from_scheduled_partitions() {
+    const PHASE: Duration = Duration::from_secs(2);
+    let mut simulation = Simulation::start(3, LinkConfig::default()).await;
+    let isolated = simulation.partition(&[&[0, 1]]);
+    let split = simulation.partition(&[&[0], &[1, 2]]);
+    let schedule = vec![
+        (Duration::from_millis(100), isolated),
+        (PHASE, split),
+        (PHASE, Topology::Healed),
+    ];
+    let hub = simulation.hub.clone();
+    let start = Instant::now();
+    let schedule_handle = tokio::spawn(async move { hub.run_schedule(schedule).await });
+
+    // Node 2 is isolated.
+    sleep_until(start + Duration::from_millis(200)).await;
+    simulation.deliver_eventually(0, 1, vec![0, 1]).await;
+    let blocked = vec![0, 2];
+    simulation.send(0, blocked.clone());
+    simulation.check_not_delivered(2, &blocked).await;
+    assert!(start.elapsed() < Duration::from_millis(100) + PHASE);
+
+    // Node 0 is split from the others.
+    sleep_until(start + Duration::from_millis(200) + PHASE).await;
+    simulation.deliver_eventually(1, 2, vec![1, 2]).await;
+    let blocked = vec![2, 0];
+    simulation.send(2, blocked.clone());
+    simulation.check_not_delivered(0, &blocked).await;
+    assert!(start.elapsed() < Duration::from_millis(100) + 2 * PHASE);
+
+    timeout(DEFAULT_TIMEOUT, schedule_handle)
+        .await
+        .expect("Schedule should finish")
+        .unwrap();
+    for (sender, receiver) in [(0, 2), (2, 0), (1, 2)] {
+        simulation
+            .deliver_eventually(sender, receiver, vec![sender as u8, receiver as u8, 1])
+            .await;
+    }
+    simulation.cleanup().await;
+}
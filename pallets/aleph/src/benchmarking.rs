@@ -0,0 +1,116 @@
+//! Benchmarks of the dispatchables of pallet_aleph and of its session change handling.
+
+use super::*;
+use crate::{
+    pallet::{
+        AuthoritiesOverride, FinalityScheduledVersionChange, NextAuthoritiesOverride,
+        SessionAuthorities, SessionPeriodChanges,
+    },
+    Pallet as Aleph,
+};
+use codec::{Decode, Encode};
+use frame_benchmarking::{account, benchmarks};
+use frame_support::{sp_runtime::traits::TrailingZeroInput, BoundedVec};
+use frame_system::RawOrigin;
+
+// More than we expect any committee to have, the weights are linear in the number of authorities
+// anyway.
+const MAX_AUTHORITIES: u32 = 256;
+
+// Authority keys only have to be distinct and decodable, so they are derived from the seed instead
+// of being generated in a keystore.
+fn authority<T: Config>(seed: u32) -> T::AuthorityId {
+    let seed = (b"aleph/authority", seed).encode();
+    T::AuthorityId::decode(&mut TrailingZeroInput::new(&seed[..]))
+        .expect("all-zero-padded bytes decode to a key")
+}
+
+fn authorities<T: Config>(count: u32, offset: u32) -> Vec<T::AuthorityId> {
+    (0..count).map(|i| authority::<T>(i + offset)).collect()
+}
+
+benchmarks! {
+    schedule_session_period_change {
+        let session = Aleph::<T>::session_of(<frame_system::Pallet<T>>::block_number())
+            .saturating_add(MIN_SESSIONS_AHEAD);
+    }: _(RawOrigin::Root, session, 10)
+    verify {
+        assert_eq!(Aleph::<T>::session_period_schedule().len(), 2);
+    }
+
+    set_max_data_branch_len {
+    }: _(RawOrigin::Root, MAX_MAX_DATA_BRANCH_LEN)
+    verify {
+        assert_eq!(Aleph::<T>::max_data_branch_len(), MAX_MAX_DATA_BRANCH_LEN);
+    }
+
+    schedule_authorities_override {
+        let n in 1 .. MAX_AUTHORITIES;
+        let authorities = authorities::<T>(n, 0);
+    }: _(RawOrigin::Root, authorities.clone())
+    verify {
+        assert_eq!(Aleph::<T>::authorities_override(), Some(authorities));
+    }
+
+    cancel_authorities_override {
+        AuthoritiesOverride::<T>::put(authorities::<T>(MAX_AUTHORITIES, 0));
+    }: _(RawOrigin::Root)
+    verify {
+        assert_eq!(Aleph::<T>::authorities_override(), None);
+    }
+
+    schedule_finality_version_change {
+        let session = Aleph::<T>::session_of(<frame_system::Pallet<T>>::block_number())
+            .saturating_add(MIN_SESSIONS_AHEAD);
+    }: _(RawOrigin::Root, 1, session)
+    verify {
+        assert_eq!(Aleph::<T>::finality_version_for_session(session), 1);
+    }
+
+    // The worst case: the announced override gets applied, another one gets announced, the
+    // finality version changes and the histories of authorities and session lengths get pruned.
+    on_new_session {
+        let n in 1 .. MAX_AUTHORITIES;
+        let depth = T::SessionAuthoritiesHistoryDepth::get();
+        let period = T::SessionPeriod::get();
+        let changes = sp_std::vec![
+            SessionPeriodChange { session: 0, first_block: 0, period },
+            SessionPeriodChange { session: 1, first_block: period, period },
+        ];
+        SessionPeriodChanges::<T>::put(
+            BoundedVec::try_from(changes).expect("benchmarks keep at least two changes"),
+        );
+        let first_block = first_block_of_session(&Aleph::<T>::session_period_schedule(), depth);
+        <frame_system::Pallet<T>>::set_block_number(first_block.into());
+        let session = Aleph::<T>::session_of(<frame_system::Pallet<T>>::block_number());
+        SessionAuthorities::<T>::insert(session.saturating_sub(depth), authorities::<T>(n, 0));
+        NextAuthoritiesOverride::<T>::put(authorities::<T>(n, n));
+        AuthoritiesOverride::<T>::put(authorities::<T>(n, 2 * n));
+        FinalityScheduledVersionChange::<T>::put(VersionChange {
+            version_incoming: 1,
+            session,
+        });
+        let accounts: Vec<T::AccountId> = (0..n).map(|i| account("validator", i, 0)).collect();
+        let validators = accounts
+            .iter()
+            .zip(authorities::<T>(n, 3 * n).into_iter());
+        let queued_validators = validators.clone();
+    }: {
+        <Aleph<T> as OneSessionHandler<T::AccountId>>::on_new_session(
+            true,
+            validators,
+            queued_validators,
+        );
+    }
+    verify {
+        assert_eq!(Aleph::<T>::authorities(), authorities::<T>(n, n));
+        assert_eq!(Aleph::<T>::finality_version(), 1);
+        assert_eq!(Aleph::<T>::session_period_schedule().len(), 1);
+    }
+
+    impl_benchmark_test_suite!(
+        Aleph,
+        crate::mock::new_test_ext(&[(1u64, 1u64), (2u64, 2u64)]),
+        crate::mock::Test
+    );
+}
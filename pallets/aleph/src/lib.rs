@@ -1,39 +1,118 @@
 //! This pallet is a runtime companion of Aleph finality gadget.
 //!
-//! Currently, it only provides support for changing sessions but in the future
-//! it will allow reporting equivocation in AlephBFT.
+//! Currently, it provides support for changing sessions, keeps the schedule of session lengths and
+//! allows root to override the authorities in an emergency, but in the future it will allow
+//! reporting equivocation in AlephBFT.
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
 #[cfg(test)]
 mod mock;
 #[cfg(test)]
 mod tests;
 
 mod migrations;
+pub mod weights;
 
 use sp_std::prelude::*;
 
 use frame_support::{
     log,
-    sp_runtime::BoundToRuntimeAppPublic,
-    traits::{OneSessionHandler, StorageVersion},
+    sp_runtime::{BoundToRuntimeAppPublic, Permill, SaturatedConversion},
+    traits::{EstimateNextSessionRotation, Get, OneSessionHandler, StorageVersion},
+    weights::Weight,
     Parameter,
 };
 pub use pallet::*;
+use pallet_session::ShouldEndSession;
+use primitives::{
+    first_block_of_session, session_of_block, SessionIndex, SessionPeriodChange, Version,
+    VersionChange, DEFAULT_FINALITY_VERSION, DEFAULT_MAX_DATA_BRANCH_LEN, MAX_FINALITY_VERSION,
+    MAX_MAX_DATA_BRANCH_LEN,
+};
+pub use weights::WeightInfo;
 
 /// The current storage version.
-const STORAGE_VERSION: StorageVersion = StorageVersion::new(2);
+const STORAGE_VERSION: StorageVersion = StorageVersion::new(4);
+
+/// How many sessions ahead a change of the session length or of the finality version has to be
+/// scheduled, so that every node learns about it from a finalized block before the affected
+/// session starts.
+pub const MIN_SESSIONS_AHEAD: SessionIndex = 2;
 
 #[frame_support::pallet]
 pub mod pallet {
     use super::*;
     use frame_support::{pallet_prelude::*, sp_runtime::RuntimeAppPublic};
-    use frame_system::pallet_prelude::BlockNumberFor;
+    use frame_system::{
+        ensure_root,
+        pallet_prelude::{BlockNumberFor, OriginFor},
+    };
 
     #[pallet::config]
     pub trait Config: frame_system::Config {
-        type AuthorityId: Member + Parameter + RuntimeAppPublic + MaybeSerializeDeserialize;
+        type AuthorityId: Member + Parameter + Ord + RuntimeAppPublic + MaybeSerializeDeserialize;
+        /// The length of sessions, in blocks, before any change is scheduled.
+        #[pallet::constant]
+        type SessionPeriod: Get<u32>;
+        /// How many past sessions, including the current one, keep their authorities in
+        /// `SessionAuthorities`.
+        #[pallet::constant]
+        type SessionAuthoritiesHistoryDepth: Get<SessionIndex>;
+        /// The maximal number of entries in `SessionPeriodChanges`, including the ones kept for
+        /// the sessions still in the authorities history.
+        #[pallet::constant]
+        type MaxSessionPeriodChanges: Get<u32>;
+        type Event: From<Event<Self>> + IsType<<Self as frame_system::Config>::Event>;
+        /// Weight information for the calls and the session change handling.
+        type WeightInfo: WeightInfo;
+    }
+
+    #[pallet::event]
+    #[pallet::generate_deposit(pub(super) fn deposit_event)]
+    pub enum Event<T: Config> {
+        /// An authority override was scheduled, it will be announced at the next session change.
+        AuthoritiesOverrideScheduled(Vec<T::AuthorityId>),
+        /// The scheduled authority override was cancelled before being announced.
+        AuthoritiesOverrideCancelled,
+        /// The authority override was announced as the authorities of the given session.
+        AuthoritiesOverrideAnnounced(SessionIndex, Vec<T::AuthorityId>),
+        /// The authority override became the authorities of the given session.
+        AuthoritiesOverrideApplied(SessionIndex),
+        /// A change of the finality version was scheduled.
+        FinalityVersionChangeScheduled(VersionChange),
+        /// The scheduled change of the finality version was dropped in favour of a new one.
+        FinalityVersionChangeReplaced(VersionChange),
+        /// The finality version changed, starting with the current session.
+        FinalityVersionChanged(VersionChange),
+    }
+
+    #[pallet::error]
+    pub enum Error<T> {
+        /// Sessions have to be at least one block long.
+        ZeroSessionPeriod,
+        /// The change has to be scheduled at least `MIN_SESSIONS_AHEAD` sessions ahead.
+        SessionPeriodChangeTooEarly,
+        /// Changes can only be appended after the last scheduled one.
+        SessionPeriodChangeNotAfterLast,
+        /// There are already `MaxSessionPeriodChanges` changes kept.
+        TooManySessionPeriodChanges,
+        /// The branch length has to be between 1 and `MAX_MAX_DATA_BRANCH_LEN`.
+        InvalidMaxDataBranchLen,
+        /// The authority override has to contain at least one authority.
+        EmptyAuthoritiesOverride,
+        /// The authority override cannot contain the same authority twice.
+        DuplicatedAuthorityInOverride,
+        /// There is no scheduled authority override to cancel.
+        NoAuthoritiesOverrideScheduled,
+        /// The change has to be scheduled at least `MIN_SESSIONS_AHEAD` sessions ahead.
+        FinalityVersionChangeTooEarly,
+        /// Nodes cannot run finality versions above `MAX_FINALITY_VERSION`.
+        UnsupportedFinalityVersion,
+        /// The network already runs the given finality version and no other is scheduled.
+        FinalityVersionUnchanged,
     }
 
     #[pallet::pallet]
@@ -48,17 +127,28 @@ pub mod pallet {
             T::DbWeight::get().reads(1)
                 + match on_chain {
                     _ if on_chain == STORAGE_VERSION => 0,
+                    _ if on_chain == StorageVersion::new(3) => {
+                        migrations::v3_to_v4::migrate::<T, Self>()
+                    }
+                    _ if on_chain == StorageVersion::new(2) => {
+                        migrations::v2_to_v3::migrate::<T, Self>()
+                            + migrations::v3_to_v4::migrate::<T, Self>()
+                    }
                     _ if on_chain == StorageVersion::new(1) => {
                         migrations::v1_to_v2::migrate::<T, Self>()
+                            + migrations::v2_to_v3::migrate::<T, Self>()
+                            + migrations::v3_to_v4::migrate::<T, Self>()
                     }
                     _ if on_chain == StorageVersion::new(0) => {
                         migrations::v0_to_v1::migrate::<T, Self>()
                             + migrations::v1_to_v2::migrate::<T, Self>()
+                            + migrations::v2_to_v3::migrate::<T, Self>()
+                            + migrations::v3_to_v4::migrate::<T, Self>()
                     }
                     _ => {
                         log::warn!(
                             target: "pallet_aleph",
-                            "On chain storage version of pallet aleph is {:?} but it should not be bigger than 2",
+                            "On chain storage version of pallet aleph is {:?} but it should not be bigger than 4",
                             on_chain
                         );
                         0
@@ -71,7 +161,234 @@ pub mod pallet {
     #[pallet::getter(fn authorities)]
     pub(super) type Authorities<T: Config> = StorageValue<_, Vec<T::AuthorityId>, ValueQuery>;
 
+    /// Authorities of the recent sessions, so that old justifications can be verified without
+    /// the state of the blocks they finalize. Only the last `SessionAuthoritiesHistoryDepth`
+    /// sessions are kept.
+    #[pallet::storage]
+    #[pallet::getter(fn session_authorities)]
+    pub(super) type SessionAuthorities<T: Config> =
+        StorageMap<_, Twox64Concat, SessionIndex, Vec<T::AuthorityId>>;
+
+    /// Changes of the session length, sorted by session and starting with the initial period of
+    /// session 0. Empty until the first change is scheduled. A change is pruned once the following
+    /// one applies to all the sessions kept in `SessionAuthorities`, as nodes only compute the
+    /// boundaries of those.
+    #[pallet::storage]
+    #[pallet::getter(fn session_period_changes)]
+    pub(super) type SessionPeriodChanges<T: Config> =
+        StorageValue<_, BoundedVec<SessionPeriodChange, T::MaxSessionPeriodChanges>, ValueQuery>;
+
+    #[pallet::type_value]
+    pub(super) fn DefaultMaxDataBranchLen<T: Config>() -> u32 {
+        DEFAULT_MAX_DATA_BRANCH_LEN
+    }
+
+    /// Maximum number of blocks above the last finalized allowed in an AlephBFT proposal. Nodes
+    /// read it at the last block of the previous session, so a change applies from the session
+    /// after the next one at the earliest.
+    #[pallet::storage]
+    #[pallet::getter(fn max_data_branch_len)]
+    pub(super) type MaxDataBranchLen<T: Config> =
+        StorageValue<_, u32, ValueQuery, DefaultMaxDataBranchLen<T>>;
+
+    /// Authorities overriding the elected ones, waiting for the next session change to be
+    /// announced.
+    #[pallet::storage]
+    #[pallet::getter(fn authorities_override)]
+    pub(super) type AuthoritiesOverride<T: Config> = StorageValue<_, Vec<T::AuthorityId>>;
+
+    /// Announced authorities of the next session, overriding the queued keys. Nodes read the
+    /// authorities of the next session at the first block of the current one, so an override can
+    /// only be announced during a session change.
+    #[pallet::storage]
+    pub(super) type NextAuthoritiesOverride<T: Config> = StorageValue<_, Vec<T::AuthorityId>>;
+
+    #[pallet::type_value]
+    pub(super) fn DefaultFinalityVersion<T: Config>() -> Version {
+        DEFAULT_FINALITY_VERSION
+    }
+
+    /// The finality version of the current session.
+    #[pallet::storage]
+    #[pallet::getter(fn finality_version)]
+    pub(super) type FinalityVersion<T: Config> =
+        StorageValue<_, Version, ValueQuery, DefaultFinalityVersion<T>>;
+
+    /// The change of the finality version waiting for its session to start. Scheduling another
+    /// change replaces it.
+    #[pallet::storage]
+    #[pallet::getter(fn finality_version_change)]
+    pub(super) type FinalityScheduledVersionChange<T: Config> = StorageValue<_, VersionChange>;
+
+    #[pallet::call]
+    impl<T: Config> Pallet<T> {
+        /// Sets the length of `session` and all the following sessions to `period` blocks.
+        #[pallet::weight((T::WeightInfo::schedule_session_period_change(), DispatchClass::Operational))]
+        pub fn schedule_session_period_change(
+            origin: OriginFor<T>,
+            session: SessionIndex,
+            period: u32,
+        ) -> DispatchResult {
+            ensure_root(origin)?;
+            ensure!(period > 0, Error::<T>::ZeroSessionPeriod);
+            let current_session = Self::session_of(<frame_system::Pallet<T>>::block_number());
+            ensure!(
+                session >= current_session.saturating_add(MIN_SESSIONS_AHEAD),
+                Error::<T>::SessionPeriodChangeTooEarly
+            );
+
+            let mut changes = Self::session_period_schedule();
+            if let Some(last) = changes.last() {
+                ensure!(
+                    session > last.session,
+                    Error::<T>::SessionPeriodChangeNotAfterLast
+                );
+            }
+            let first_block = first_block_of_session(&changes, session);
+            changes.push(SessionPeriodChange {
+                session,
+                first_block,
+                period,
+            });
+            let changes = BoundedVec::<_, T::MaxSessionPeriodChanges>::try_from(changes)
+                .map_err(|_| Error::<T>::TooManySessionPeriodChanges)?;
+            SessionPeriodChanges::<T>::put(changes);
+
+            Ok(())
+        }
+
+        /// Sets the maximum number of blocks in a single AlephBFT proposal.
+        #[pallet::weight((T::WeightInfo::set_max_data_branch_len(), DispatchClass::Operational))]
+        pub fn set_max_data_branch_len(origin: OriginFor<T>, len: u32) -> DispatchResult {
+            ensure_root(origin)?;
+            ensure!(
+                len > 0 && len <= MAX_MAX_DATA_BRANCH_LEN,
+                Error::<T>::InvalidMaxDataBranchLen
+            );
+            MaxDataBranchLen::<T>::put(len);
+
+            Ok(())
+        }
+
+        /// Replaces the authorities elected for the session after the next one with `authorities`.
+        /// Meant for emergencies, e.g. when the keys of the elected authorities were compromised.
+        #[pallet::weight((T::WeightInfo::schedule_authorities_override(authorities.len() as u32), DispatchClass::Operational))]
+        pub fn schedule_authorities_override(
+            origin: OriginFor<T>,
+            authorities: Vec<T::AuthorityId>,
+        ) -> DispatchResult {
+            ensure_root(origin)?;
+            ensure!(
+                !authorities.is_empty(),
+                Error::<T>::EmptyAuthoritiesOverride
+            );
+            let mut distinct = authorities.clone();
+            distinct.sort();
+            distinct.dedup();
+            ensure!(
+                distinct.len() == authorities.len(),
+                Error::<T>::DuplicatedAuthorityInOverride
+            );
+
+            AuthoritiesOverride::<T>::put(authorities.clone());
+            Self::deposit_event(Event::AuthoritiesOverrideScheduled(authorities));
+
+            Ok(())
+        }
+
+        /// Switches the network to the finality `version` starting with `session`. Nodes read the
+        /// version of a session at the last block of the previous one, so all of them switch at
+        /// the same block, as long as they support the new version. Replaces the change scheduled
+        /// before, if there is one, so scheduling the current version cancels it.
+        #[pallet::weight((T::WeightInfo::schedule_finality_version_change(), DispatchClass::Operational))]
+        pub fn schedule_finality_version_change(
+            origin: OriginFor<T>,
+            version_incoming: Version,
+            session: SessionIndex,
+        ) -> DispatchResult {
+            ensure_root(origin)?;
+            let current_session = Self::session_of(<frame_system::Pallet<T>>::block_number());
+            ensure!(
+                session >= current_session.saturating_add(MIN_SESSIONS_AHEAD),
+                Error::<T>::FinalityVersionChangeTooEarly
+            );
+            ensure!(
+                version_incoming <= MAX_FINALITY_VERSION,
+                Error::<T>::UnsupportedFinalityVersion
+            );
+            let scheduled = FinalityScheduledVersionChange::<T>::get();
+            ensure!(
+                scheduled.is_some() || version_incoming != FinalityVersion::<T>::get(),
+                Error::<T>::FinalityVersionUnchanged
+            );
+
+            let change = VersionChange {
+                version_incoming,
+                session,
+            };
+            FinalityScheduledVersionChange::<T>::put(change);
+            if let Some(replaced) = scheduled {
+                Self::deposit_event(Event::FinalityVersionChangeReplaced(replaced));
+            }
+            Self::deposit_event(Event::FinalityVersionChangeScheduled(change));
+
+            Ok(())
+        }
+
+        /// Cancels the authority override that was not announced yet.
+        #[pallet::weight((T::WeightInfo::cancel_authorities_override(), DispatchClass::Operational))]
+        pub fn cancel_authorities_override(origin: OriginFor<T>) -> DispatchResult {
+            ensure_root(origin)?;
+            ensure!(
+                AuthoritiesOverride::<T>::take().is_some(),
+                Error::<T>::NoAuthoritiesOverrideScheduled
+            );
+            Self::deposit_event(Event::AuthoritiesOverrideCancelled);
+
+            Ok(())
+        }
+    }
+
     impl<T: Config> Pallet<T> {
+        /// The session length changes that were not pruned yet, starting with the initial period
+        /// of session 0 if no change was scheduled so far.
+        pub fn session_period_schedule() -> Vec<SessionPeriodChange> {
+            let changes = SessionPeriodChanges::<T>::get();
+            if changes.is_empty() {
+                return sp_std::vec![SessionPeriodChange {
+                    session: 0,
+                    first_block: 0,
+                    period: T::SessionPeriod::get(),
+                }];
+            }
+            changes.into_inner()
+        }
+
+        /// The length of `session` in blocks, according to the schedule.
+        pub fn session_period(session: SessionIndex) -> u32 {
+            let schedule = Self::session_period_schedule();
+            first_block_of_session(&schedule, session.saturating_add(1))
+                - first_block_of_session(&schedule, session)
+        }
+
+        /// The session the block belongs to according to the schedule.
+        pub fn session_of(block: T::BlockNumber) -> SessionIndex {
+            session_of_block(
+                &Self::session_period_schedule(),
+                block.saturated_into::<u32>(),
+            )
+        }
+
+        /// The first block of the session containing `now` and the first block of the next one.
+        fn session_bounds(now: T::BlockNumber) -> (u32, u32) {
+            let schedule = Self::session_period_schedule();
+            let session = session_of_block(&schedule, now.saturated_into::<u32>());
+            (
+                first_block_of_session(&schedule, session),
+                first_block_of_session(&schedule, session.saturating_add(1)),
+            )
+        }
+
         pub(crate) fn initialize_authorities(authorities: &[T::AuthorityId]) {
             if !authorities.is_empty() {
                 assert!(
@@ -79,12 +396,86 @@ pub mod pallet {
                     "Authorities are already initialized!"
                 );
                 <Authorities<T>>::put(authorities);
+                Self::record_session_authorities(0, authorities);
             }
         }
 
         pub(crate) fn update_authorities(authorities: &[T::AuthorityId]) {
             <Authorities<T>>::put(authorities);
         }
+
+        // Remembers the authorities of the session and forgets the ones that fell out of the
+        // history.
+        pub(crate) fn record_session_authorities(
+            session: SessionIndex,
+            authorities: &[T::AuthorityId],
+        ) {
+            SessionAuthorities::<T>::insert(session, authorities);
+            let depth = T::SessionAuthoritiesHistoryDepth::get();
+            if session >= depth {
+                SessionAuthorities::<T>::remove(session - depth);
+            }
+        }
+
+        // Called during a session change. Forgets the changes of the session length that no
+        // session in the authorities history depends on.
+        pub(crate) fn prune_session_period_changes(session: SessionIndex) {
+            let oldest_kept = session
+                .saturating_add(1)
+                .saturating_sub(T::SessionAuthoritiesHistoryDepth::get());
+            let changes = SessionPeriodChanges::<T>::get();
+            let superseded = changes
+                .iter()
+                .skip(1)
+                .take_while(|change| change.session <= oldest_kept)
+                .count();
+            if superseded == 0 {
+                return;
+            }
+            let pruned: Vec<_> = changes.into_inner().into_iter().skip(superseded).collect();
+            if let Ok(pruned) = BoundedVec::try_from(pruned) {
+                SessionPeriodChanges::<T>::put(pruned);
+            }
+        }
+
+        #[cfg(feature = "runtime-benchmarks")]
+        pub fn set_session_authorities(session: SessionIndex, authorities: &[T::AuthorityId]) {
+            SessionAuthorities::<T>::insert(session, authorities);
+        }
+
+        /// The authorities of the next session, if they are overridden.
+        pub fn next_session_authorities_override() -> Option<Vec<T::AuthorityId>> {
+            NextAuthoritiesOverride::<T>::get()
+        }
+
+        /// The finality version of `session`, which has to be the current or a future one.
+        pub fn finality_version_for_session(session: SessionIndex) -> Version {
+            match FinalityScheduledVersionChange::<T>::get() {
+                Some(change) if session >= change.session => change.version_incoming,
+                _ => FinalityVersion::<T>::get(),
+            }
+        }
+
+        // Called during a session change.
+        fn update_finality_version(session: SessionIndex) {
+            if let Some(change) = FinalityScheduledVersionChange::<T>::get() {
+                if session >= change.session {
+                    FinalityVersion::<T>::put(change.version_incoming);
+                    FinalityScheduledVersionChange::<T>::kill();
+                    Self::deposit_event(Event::FinalityVersionChanged(change));
+                }
+            }
+        }
+
+        // Called during a session change, after the authorities of the new session are set.
+        fn announce_authorities_override() {
+            if let Some(authorities) = AuthoritiesOverride::<T>::take() {
+                let session =
+                    Self::session_of(<frame_system::Pallet<T>>::block_number()).saturating_add(1);
+                NextAuthoritiesOverride::<T>::put(authorities.clone());
+                Self::deposit_event(Event::AuthoritiesOverrideAnnounced(session, authorities));
+            }
+        }
     }
 
     impl<T: Config> BoundToRuntimeAppPublic for Pallet<T> {
@@ -108,12 +499,58 @@ pub mod pallet {
             I: Iterator<Item = (&'a T::AccountId, T::AuthorityId)>,
             T::AccountId: 'a,
         {
-            if changed {
-                let (_, authorities): (Vec<_>, Vec<_>) = validators.unzip();
+            let session = Self::session_of(<frame_system::Pallet<T>>::block_number());
+            if let Some(authorities) = NextAuthoritiesOverride::<T>::take() {
                 Self::update_authorities(authorities.as_slice());
+                Self::deposit_event(Event::AuthoritiesOverrideApplied(session));
+            } else {
+                let (_, authorities): (Vec<_>, Vec<_>) = validators.unzip();
+                // Even if the validators did not change, the authorities of an overridden session
+                // have to be restored.
+                if changed || authorities != Authorities::<T>::get() {
+                    Self::update_authorities(authorities.as_slice());
+                }
             }
+            let authorities = Authorities::<T>::get();
+            Self::record_session_authorities(session, &authorities);
+            Self::prune_session_period_changes(session);
+            Self::announce_authorities_override();
+            Self::update_finality_version(session);
+            <frame_system::Pallet<T>>::register_extra_weight_unchecked(
+                T::WeightInfo::on_new_session(authorities.len() as u32),
+                DispatchClass::Mandatory,
+            );
         }
 
         fn on_disabled(_validator_index: u32) {}
     }
+
+    impl<T: Config> ShouldEndSession<T::BlockNumber> for Pallet<T> {
+        fn should_end_session(now: T::BlockNumber) -> bool {
+            let (first_block, _) = Self::session_bounds(now);
+            first_block == now.saturated_into::<u32>()
+        }
+    }
+
+    impl<T: Config> EstimateNextSessionRotation<T::BlockNumber> for Pallet<T> {
+        fn average_session_length() -> T::BlockNumber {
+            let (first_block, next_first_block) =
+                Self::session_bounds(<frame_system::Pallet<T>>::block_number());
+            (next_first_block - first_block).into()
+        }
+
+        fn estimate_current_session_progress(now: T::BlockNumber) -> (Option<Permill>, Weight) {
+            let (first_block, next_first_block) = Self::session_bounds(now);
+            let progress = Permill::from_rational(
+                now.saturated_into::<u32>() - first_block + 1,
+                next_first_block - first_block,
+            );
+            (Some(progress), T::DbWeight::get().reads(1))
+        }
+
+        fn estimate_next_session_rotation(now: T::BlockNumber) -> (Option<T::BlockNumber>, Weight) {
+            let (_, next_first_block) = Self::session_bounds(now);
+            (Some(next_first_block.into()), T::DbWeight::get().reads(1))
+        }
+    }
 }
@@ -0,0 +1,28 @@
+use crate::{Config, Pallet};
+use frame_support::{
+    log,
+    traits::{Get, PalletInfoAccess, StorageVersion},
+    weights::Weight,
+};
+
+pub fn migrate<T: Config, P: PalletInfoAccess>() -> Weight {
+    log::info!(target: "pallet_aleph", "Running migration from STORAGE_VERSION 2 to 3");
+
+    // The history starts with the current session, the authorities of the previous ones are not
+    // available anymore.
+    let session = Pallet::<T>::session_of(<frame_system::Pallet<T>>::block_number());
+    let authorities = Pallet::<T>::authorities();
+    let mut writes = 0;
+    if authorities.is_empty() {
+        log::info!(target: "pallet_aleph", "Storage item Authorities is empty, not seeding the history!");
+    } else {
+        Pallet::<T>::record_session_authorities(session, &authorities);
+        writes += 1;
+    }
+
+    // store new version
+    StorageVersion::new(3).put::<P>();
+    writes += 1;
+
+    T::DbWeight::get().reads(2) + T::DbWeight::get().writes(writes)
+}
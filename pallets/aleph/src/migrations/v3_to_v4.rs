@@ -0,0 +1,61 @@
+use crate::{Config, Pallet, SessionPeriodChanges};
+use codec::Decode;
+use frame_support::{
+    log,
+    traits::{Get, PalletInfoAccess, StorageVersion},
+    weights::Weight,
+    BoundedVec,
+};
+use primitives::{first_block_of_session, SessionIndex, SessionPeriodChange};
+use sp_std::vec::Vec;
+
+// The changes used to be kept without their first blocks and without the initial period, which
+// the schedule now starts with.
+#[derive(Decode)]
+struct SessionPeriodChangeV3 {
+    session: SessionIndex,
+    period: u32,
+}
+
+pub fn migrate<T: Config, P: PalletInfoAccess>() -> Weight {
+    log::info!(target: "pallet_aleph", "Running migration from STORAGE_VERSION 3 to 4");
+
+    let translated = SessionPeriodChanges::<T>::translate(
+        |changes: Option<Vec<SessionPeriodChangeV3>>| {
+            changes.map(|changes| {
+                let mut schedule = sp_std::vec![SessionPeriodChange {
+                    session: 0,
+                    first_block: 0,
+                    period: T::SessionPeriod::get(),
+                }];
+                for change in changes {
+                    let first_block = first_block_of_session(&schedule, change.session);
+                    schedule.push(SessionPeriodChange {
+                        session: change.session,
+                        first_block,
+                        period: change.period,
+                    });
+                }
+                // Only the latest changes matter for the current and the future sessions.
+                let max_changes = T::MaxSessionPeriodChanges::get() as usize;
+                if schedule.len() > max_changes {
+                    log::warn!(target: "pallet_aleph", "Storage item SessionPeriodChanges has more than {} changes, dropping the oldest ones!", max_changes);
+                    schedule.drain(..schedule.len() - max_changes);
+                }
+                BoundedVec::try_from(schedule)
+                    .expect("the schedule was cut down to MaxSessionPeriodChanges")
+            })
+        },
+    );
+    if translated.is_err() {
+        log::error!(target: "pallet_aleph", "Could not decode SessionPeriodChanges!");
+    }
+
+    let session = Pallet::<T>::session_of(<frame_system::Pallet<T>>::block_number());
+    Pallet::<T>::prune_session_period_changes(session);
+
+    // store new version
+    StorageVersion::new(4).put::<P>();
+
+    T::DbWeight::get().reads(3) + T::DbWeight::get().writes(3)
+}
@@ -29,7 +29,7 @@ construct_runtime!(
     {
         System: frame_system::{Pallet, Call, Config, Storage, Event<T>},
         Balances: pallet_balances::{Pallet, Call, Storage, Config<T>, Event<T>},
-        Aleph: pallet_aleph::{Pallet, Storage},
+        Aleph: pallet_aleph::{Pallet, Call, Storage, Event<T>},
         Session: pallet_session::{Pallet, Call, Storage, Event, Config<T>},
         Timestamp: pallet_timestamp::{Pallet, Call, Storage, Inherent},
     }
@@ -79,8 +79,9 @@ impl frame_system::Config for Test {
 }
 
 parameter_types! {
-    pub const Period: u64 = 1;
-    pub const Offset: u64 = 0;
+    pub const SessionPeriod: u32 = 1;
+    pub const SessionAuthoritiesHistoryDepth: u32 = 3;
+    pub const MaxSessionPeriodChanges: u32 = 4;
 }
 
 parameter_types! {
@@ -103,8 +104,8 @@ impl pallet_session::Config for Test {
     type Event = Event;
     type ValidatorId = u64;
     type ValidatorIdOf = ConvertInto;
-    type ShouldEndSession = pallet_session::PeriodicSessions<Period, Offset>;
-    type NextSessionRotation = pallet_session::PeriodicSessions<Period, Offset>;
+    type ShouldEndSession = Aleph;
+    type NextSessionRotation = Aleph;
     type SessionManager = ();
     type SessionHandler = <TestSessionKeys as OpaqueKeys>::KeyTypeIdProviders;
     type Keys = TestSessionKeys;
@@ -132,6 +133,11 @@ impl pallet_timestamp::Config for Test {
 
 impl Config for Test {
     type AuthorityId = AuthorityId;
+    type SessionPeriod = SessionPeriod;
+    type SessionAuthoritiesHistoryDepth = SessionAuthoritiesHistoryDepth;
+    type MaxSessionPeriodChanges = MaxSessionPeriodChanges;
+    type Event = Event;
+    type WeightInfo = ();
 }
 
 pub fn to_authorities(authorities: &[u64]) -> Vec<AuthorityId> {
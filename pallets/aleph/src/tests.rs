@@ -2,12 +2,18 @@
 
 use std::collections::HashMap;
 
-use crate::{migrations, mock::*, pallet, Config};
+use crate::{migrations, mock::*, pallet, Config, Error};
 use frame_support::{
-    generate_storage_alias,
+    assert_noop, assert_ok, generate_storage_alias,
     storage::migration::{get_storage_value, put_storage_value},
-    traits::{GetStorageVersion, OneSessionHandler, StorageVersion},
+    traits::{EstimateNextSessionRotation, GetStorageVersion, OneSessionHandler, StorageVersion},
 };
+use pallet_session::ShouldEndSession;
+use primitives::{
+    SessionPeriodChange, VersionChange, DEFAULT_FINALITY_VERSION, DEFAULT_MAX_DATA_BRANCH_LEN,
+    MAX_FINALITY_VERSION, MAX_MAX_DATA_BRANCH_LEN,
+};
+use sp_runtime::DispatchError;
 
 generate_storage_alias!(
     Aleph, SessionForValidatorsChange => Value<u32>
@@ -163,3 +169,398 @@ fn test_session_rotation() {
         assert_eq!(Aleph::authorities(), to_authorities(&[3, 4]));
     })
 }
+
+#[test]
+fn session_length_follows_schedule() {
+    new_test_ext(&[(1u64, 1u64), (2u64, 2u64)]).execute_with(|| {
+        initialize_session();
+
+        assert_ok!(Aleph::schedule_session_period_change(Origin::root(), 3, 4));
+        assert_eq!(
+            Aleph::session_period_schedule(),
+            vec![
+                SessionPeriodChange {
+                    session: 0,
+                    first_block: 0,
+                    period: 1
+                },
+                SessionPeriodChange {
+                    session: 3,
+                    first_block: 3,
+                    period: 4
+                },
+            ]
+        );
+
+        let ending: Vec<_> = (0..12).filter(|b| Aleph::should_end_session(*b)).collect();
+        assert_eq!(ending, vec![0, 1, 2, 3, 7, 11]);
+        assert_eq!(Aleph::session_of(8), 4);
+        assert_eq!(Aleph::estimate_next_session_rotation(4).0, Some(7));
+    })
+}
+
+#[test]
+fn session_period_changes_are_validated() {
+    new_test_ext(&[(1u64, 1u64), (2u64, 2u64)]).execute_with(|| {
+        initialize_session();
+
+        assert_noop!(
+            Aleph::schedule_session_period_change(Origin::signed(1), 5, 4),
+            DispatchError::BadOrigin
+        );
+        assert_noop!(
+            Aleph::schedule_session_period_change(Origin::root(), 5, 0),
+            Error::<Test>::ZeroSessionPeriod
+        );
+        assert_noop!(
+            Aleph::schedule_session_period_change(Origin::root(), 2, 4),
+            Error::<Test>::SessionPeriodChangeTooEarly
+        );
+
+        assert_ok!(Aleph::schedule_session_period_change(Origin::root(), 5, 4));
+        assert_noop!(
+            Aleph::schedule_session_period_change(Origin::root(), 4, 4),
+            Error::<Test>::SessionPeriodChangeNotAfterLast
+        );
+
+        assert_ok!(Aleph::schedule_session_period_change(Origin::root(), 6, 4));
+        assert_ok!(Aleph::schedule_session_period_change(Origin::root(), 7, 4));
+        assert_noop!(
+            Aleph::schedule_session_period_change(Origin::root(), 8, 4),
+            Error::<Test>::TooManySessionPeriodChanges
+        );
+    })
+}
+
+#[test]
+fn session_period_changes_are_pruned_after_leaving_history() {
+    new_test_ext(&[(1u64, 1u64), (2u64, 2u64)]).execute_with(|| {
+        initialize_session();
+        assert_ok!(Aleph::schedule_session_period_change(Origin::root(), 3, 1));
+        assert_ok!(Aleph::schedule_session_period_change(Origin::root(), 4, 1));
+
+        run_session(4);
+        assert_eq!(Aleph::session_period_schedule().len(), 3);
+
+        run_session(5);
+        let sessions: Vec<_> = Aleph::session_period_schedule()
+            .iter()
+            .map(|change| change.session)
+            .collect();
+        assert_eq!(sessions, vec![3, 4]);
+
+        run_session(6);
+        assert_eq!(
+            Aleph::session_period_schedule(),
+            vec![SessionPeriodChange {
+                session: 4,
+                first_block: 4,
+                period: 1
+            }]
+        );
+        assert_eq!(Aleph::session_of(10), 10);
+    })
+}
+
+#[test]
+fn max_data_branch_len_can_be_changed_by_root() {
+    new_test_ext(&[(1u64, 1u64), (2u64, 2u64)]).execute_with(|| {
+        assert_eq!(Aleph::max_data_branch_len(), DEFAULT_MAX_DATA_BRANCH_LEN);
+
+        assert_noop!(
+            Aleph::set_max_data_branch_len(Origin::signed(1), 20),
+            DispatchError::BadOrigin
+        );
+        assert_noop!(
+            Aleph::set_max_data_branch_len(Origin::root(), 0),
+            Error::<Test>::InvalidMaxDataBranchLen
+        );
+        assert_noop!(
+            Aleph::set_max_data_branch_len(Origin::root(), MAX_MAX_DATA_BRANCH_LEN + 1),
+            Error::<Test>::InvalidMaxDataBranchLen
+        );
+
+        assert_ok!(Aleph::set_max_data_branch_len(Origin::root(), 20));
+        assert_eq!(Aleph::max_data_branch_len(), 20);
+    })
+}
+
+#[test]
+fn authorities_override_is_announced_and_applied() {
+    new_test_ext(&[(1u64, 1u64), (2u64, 2u64)]).execute_with(|| {
+        initialize_session();
+        run_session(1);
+
+        assert_ok!(Aleph::schedule_authorities_override(
+            Origin::root(),
+            to_authorities(&[5, 6])
+        ));
+        assert_eq!(Aleph::next_session_authorities_override(), None);
+
+        Aleph::on_new_session(
+            false,
+            new_session_validators(&[1, 2]),
+            new_session_validators(&[1, 2]),
+        );
+        assert_eq!(Aleph::authorities(), to_authorities(&[1, 2]));
+        assert_eq!(
+            Aleph::next_session_authorities_override(),
+            Some(to_authorities(&[5, 6]))
+        );
+
+        Aleph::on_new_session(
+            false,
+            new_session_validators(&[1, 2]),
+            new_session_validators(&[1, 2]),
+        );
+        assert_eq!(Aleph::authorities(), to_authorities(&[5, 6]));
+        assert_eq!(Aleph::next_session_authorities_override(), None);
+
+        Aleph::on_new_session(
+            false,
+            new_session_validators(&[1, 2]),
+            new_session_validators(&[1, 2]),
+        );
+        assert_eq!(Aleph::authorities(), to_authorities(&[1, 2]));
+    })
+}
+
+#[test]
+fn authorities_override_is_validated() {
+    new_test_ext(&[(1u64, 1u64), (2u64, 2u64)]).execute_with(|| {
+        initialize_session();
+
+        assert_noop!(
+            Aleph::schedule_authorities_override(Origin::signed(1), to_authorities(&[5])),
+            DispatchError::BadOrigin
+        );
+        assert_noop!(
+            Aleph::schedule_authorities_override(Origin::root(), vec![]),
+            Error::<Test>::EmptyAuthoritiesOverride
+        );
+        assert_noop!(
+            Aleph::schedule_authorities_override(Origin::root(), to_authorities(&[5, 6, 5])),
+            Error::<Test>::DuplicatedAuthorityInOverride
+        );
+    })
+}
+
+#[test]
+fn authorities_override_can_be_cancelled_before_announcement() {
+    new_test_ext(&[(1u64, 1u64), (2u64, 2u64)]).execute_with(|| {
+        initialize_session();
+
+        assert_noop!(
+            Aleph::cancel_authorities_override(Origin::root()),
+            Error::<Test>::NoAuthoritiesOverrideScheduled
+        );
+        assert_ok!(Aleph::schedule_authorities_override(
+            Origin::root(),
+            to_authorities(&[5, 6])
+        ));
+        assert_ok!(Aleph::cancel_authorities_override(Origin::root()));
+
+        Aleph::on_new_session(
+            false,
+            new_session_validators(&[1, 2]),
+            new_session_validators(&[1, 2]),
+        );
+        assert_eq!(Aleph::next_session_authorities_override(), None);
+        assert_eq!(Aleph::authorities(), to_authorities(&[1, 2]));
+    })
+}
+
+#[test]
+fn migration_from_v2_to_v3_works() {
+    new_test_ext(&[(1u64, 1u64), (2u64, 2u64)]).execute_with(|| {
+        initialize_session();
+        run_session(2);
+        StorageVersion::new(2).put::<Aleph>();
+        Aleph::update_authorities(to_authorities(&[3, 4]).as_slice());
+
+        let _weight = migrations::v2_to_v3::migrate::<Test, Aleph>();
+
+        let v3 = <pallet::Pallet<Test> as GetStorageVersion>::on_chain_storage_version();
+
+        assert_eq!(
+            v3,
+            StorageVersion::new(3),
+            "Storage version after applying migration should be incremented"
+        );
+        assert_eq!(
+            Aleph::session_authorities(2),
+            Some(to_authorities(&[3, 4])),
+            "Migration should seed the history with the current authorities"
+        );
+    })
+}
+
+#[test]
+fn migration_from_v3_to_v4_works() {
+    new_test_ext(&[(1u64, 1u64), (2u64, 2u64)]).execute_with(|| {
+        initialize_session();
+        StorageVersion::new(3).put::<Aleph>();
+        put_storage_value(
+            b"Aleph",
+            b"SessionPeriodChanges",
+            &[],
+            vec![(3u32, 4u32), (5u32, 2u32)],
+        );
+
+        let _weight = migrations::v3_to_v4::migrate::<Test, Aleph>();
+
+        let v4 = <pallet::Pallet<Test> as GetStorageVersion>::on_chain_storage_version();
+
+        assert_eq!(
+            v4,
+            StorageVersion::new(4),
+            "Storage version after applying migration should be incremented"
+        );
+        assert_eq!(
+            Aleph::session_period_schedule(),
+            vec![
+                SessionPeriodChange {
+                    session: 0,
+                    first_block: 0,
+                    period: 1
+                },
+                SessionPeriodChange {
+                    session: 3,
+                    first_block: 3,
+                    period: 4
+                },
+                SessionPeriodChange {
+                    session: 5,
+                    first_block: 11,
+                    period: 2
+                },
+            ],
+            "Migration should compute the first blocks of the changes"
+        );
+    })
+}
+
+#[test]
+fn session_authorities_history_is_pruned() {
+    new_test_ext(&[(1u64, 1u64), (2u64, 2u64)]).execute_with(|| {
+        assert_eq!(Aleph::session_authorities(0), Some(to_authorities(&[1, 2])));
+
+        initialize_session();
+        for session in 1..5u64 {
+            run_session(session as u32);
+            let validators = [session, session + 10];
+            Aleph::on_new_session(
+                true,
+                new_session_validators(&validators),
+                new_session_validators(&validators),
+            );
+        }
+
+        assert_eq!(Aleph::session_authorities(0), None);
+        assert_eq!(Aleph::session_authorities(1), None);
+        for session in 2..5u64 {
+            assert_eq!(
+                Aleph::session_authorities(session as u32),
+                Some(to_authorities(&[session, session + 10]))
+            );
+        }
+    })
+}
+
+#[test]
+fn finality_version_change_is_validated() {
+    new_test_ext(&[(1u64, 1u64), (2u64, 2u64)]).execute_with(|| {
+        initialize_session();
+
+        assert_noop!(
+            Aleph::schedule_finality_version_change(Origin::signed(1), 1, 5),
+            DispatchError::BadOrigin
+        );
+        assert_noop!(
+            Aleph::schedule_finality_version_change(Origin::root(), 1, 2),
+            Error::<Test>::FinalityVersionChangeTooEarly
+        );
+        assert_noop!(
+            Aleph::schedule_finality_version_change(Origin::root(), MAX_FINALITY_VERSION + 1, 5),
+            Error::<Test>::UnsupportedFinalityVersion
+        );
+        assert_noop!(
+            Aleph::schedule_finality_version_change(Origin::root(), DEFAULT_FINALITY_VERSION, 5),
+            Error::<Test>::FinalityVersionUnchanged
+        );
+    })
+}
+
+#[test]
+fn finality_version_change_replacement_is_announced() {
+    new_test_ext(&[(1u64, 1u64), (2u64, 2u64)]).execute_with(|| {
+        initialize_session();
+        let first = VersionChange {
+            version_incoming: 1,
+            session: 3,
+        };
+        let second = VersionChange {
+            version_incoming: DEFAULT_FINALITY_VERSION,
+            session: 4,
+        };
+
+        assert_ok!(Aleph::schedule_finality_version_change(
+            Origin::root(),
+            first.version_incoming,
+            first.session
+        ));
+        assert_ok!(Aleph::schedule_finality_version_change(
+            Origin::root(),
+            second.version_incoming,
+            second.session
+        ));
+
+        assert_eq!(Aleph::finality_version_change(), Some(second));
+        let events: Vec<_> = System::events()
+            .into_iter()
+            .map(|record| record.event)
+            .collect();
+        assert!(
+            events.contains(&Event::Aleph(pallet::Event::FinalityVersionChangeReplaced(
+                first
+            )))
+        );
+        assert!(events.contains(&Event::Aleph(
+            pallet::Event::FinalityVersionChangeScheduled(second)
+        )));
+    })
+}
+
+#[test]
+fn finality_version_changes_at_scheduled_session() {
+    new_test_ext(&[(1u64, 1u64), (2u64, 2u64)]).execute_with(|| {
+        initialize_session();
+        assert_eq!(Aleph::finality_version(), DEFAULT_FINALITY_VERSION);
+
+        assert_ok!(Aleph::schedule_finality_version_change(
+            Origin::root(),
+            1,
+            3
+        ));
+        assert_eq!(
+            Aleph::finality_version_change(),
+            Some(VersionChange {
+                version_incoming: 1,
+                session: 3
+            })
+        );
+        assert_eq!(
+            Aleph::finality_version_for_session(2),
+            DEFAULT_FINALITY_VERSION
+        );
+        assert_eq!(Aleph::finality_version_for_session(3), 1);
+
+        run_session(2);
+        assert_eq!(Aleph::finality_version(), DEFAULT_FINALITY_VERSION);
+
+        run_session(3);
+        assert_eq!(Aleph::finality_version(), 1);
+        assert_eq!(Aleph::finality_version_change(), None);
+        assert_eq!(Aleph::finality_version_for_session(4), 1);
+    })
+}
@@ -0,0 +1,104 @@
+//! Weights for pallet_aleph.
+//!
+//! The weights were estimated from the storage accesses of each call and still have to be
+//! replaced with the output of `scripts/run_benchmarks.sh`, run on the reference hardware.
+
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{
+    traits::Get,
+    weights::{constants::RocksDbWeight, Weight},
+};
+use sp_std::marker::PhantomData;
+
+/// Weight functions needed for pallet_aleph.
+pub trait WeightInfo {
+    fn schedule_session_period_change() -> Weight;
+    fn set_max_data_branch_len() -> Weight;
+    fn schedule_authorities_override(n: u32) -> Weight;
+    fn cancel_authorities_override() -> Weight;
+    fn schedule_finality_version_change() -> Weight;
+    fn on_new_session(n: u32) -> Weight;
+}
+
+/// Weights for pallet_aleph using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+    // Storage: Aleph SessionPeriodChanges (r:1 w:1)
+    fn schedule_session_period_change() -> Weight {
+        (16_000_000 as Weight)
+            .saturating_add(T::DbWeight::get().reads(1 as Weight))
+            .saturating_add(T::DbWeight::get().writes(1 as Weight))
+    }
+    // Storage: Aleph MaxDataBranchLen (r:0 w:1)
+    fn set_max_data_branch_len() -> Weight {
+        (9_000_000 as Weight).saturating_add(T::DbWeight::get().writes(1 as Weight))
+    }
+    // Storage: Aleph AuthoritiesOverride (r:0 w:1)
+    fn schedule_authorities_override(n: u32) -> Weight {
+        (14_000_000 as Weight)
+            .saturating_add((150_000 as Weight).saturating_mul(n as Weight))
+            .saturating_add(T::DbWeight::get().writes(1 as Weight))
+    }
+    // Storage: Aleph AuthoritiesOverride (r:1 w:1)
+    fn cancel_authorities_override() -> Weight {
+        (20_000_000 as Weight)
+            .saturating_add(T::DbWeight::get().reads(1 as Weight))
+            .saturating_add(T::DbWeight::get().writes(1 as Weight))
+    }
+    // Storage: Aleph SessionPeriodChanges (r:1 w:0)
+    // Storage: Aleph FinalityScheduledVersionChange (r:1 w:1)
+    // Storage: Aleph FinalityVersion (r:1 w:0)
+    fn schedule_finality_version_change() -> Weight {
+        (15_000_000 as Weight)
+            .saturating_add(T::DbWeight::get().reads(3 as Weight))
+            .saturating_add(T::DbWeight::get().writes(1 as Weight))
+    }
+    // Storage: Aleph SessionPeriodChanges (r:1 w:1)
+    // Storage: Aleph NextAuthoritiesOverride (r:1 w:1)
+    // Storage: Aleph Authorities (r:1 w:1)
+    // Storage: Aleph SessionAuthorities (r:0 w:2)
+    // Storage: Aleph AuthoritiesOverride (r:1 w:1)
+    // Storage: Aleph FinalityScheduledVersionChange (r:1 w:1)
+    // Storage: Aleph FinalityVersion (r:0 w:1)
+    fn on_new_session(n: u32) -> Weight {
+        (45_000_000 as Weight)
+            .saturating_add((120_000 as Weight).saturating_mul(n as Weight))
+            .saturating_add(T::DbWeight::get().reads(5 as Weight))
+            .saturating_add(T::DbWeight::get().writes(8 as Weight))
+    }
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+    fn schedule_session_period_change() -> Weight {
+        (16_000_000 as Weight)
+            .saturating_add(RocksDbWeight::get().reads(1 as Weight))
+            .saturating_add(RocksDbWeight::get().writes(1 as Weight))
+    }
+    fn set_max_data_branch_len() -> Weight {
+        (9_000_000 as Weight).saturating_add(RocksDbWeight::get().writes(1 as Weight))
+    }
+    fn schedule_authorities_override(n: u32) -> Weight {
+        (14_000_000 as Weight)
+            .saturating_add((150_000 as Weight).saturating_mul(n as Weight))
+            .saturating_add(RocksDbWeight::get().writes(1 as Weight))
+    }
+    fn cancel_authorities_override() -> Weight {
+        (20_000_000 as Weight)
+            .saturating_add(RocksDbWeight::get().reads(1 as Weight))
+            .saturating_add(RocksDbWeight::get().writes(1 as Weight))
+    }
+    fn schedule_finality_version_change() -> Weight {
+        (15_000_000 as Weight)
+            .saturating_add(RocksDbWeight::get().reads(3 as Weight))
+            .saturating_add(RocksDbWeight::get().writes(1 as Weight))
+    }
+    fn on_new_session(n: u32) -> Weight {
+        (45_000_000 as Weight)
+            .saturating_add((120_000 as Weight).saturating_mul(n as Weight))
+            .saturating_add(RocksDbWeight::get().reads(5 as Weight))
+            .saturating_add(RocksDbWeight::get().writes(8 as Weight))
+    }
+}
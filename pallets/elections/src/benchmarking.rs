@@ -0,0 +1,93 @@
+//! Benchmarks of the dispatchables of pallet_elections.
+
+use super::*;
+use crate::{
+    pallet::{Members, MembersPerSession, PendingFinalityParticipation, ReservedMembers},
+    traits::FinalityParticipationVerifier,
+    Pallet as Elections,
+};
+use frame_benchmarking::{account, benchmarks};
+use frame_support::BoundedVec;
+use frame_system::RawOrigin;
+use primitives::FinalityParticipation;
+
+fn accounts<T: Config>(count: u32) -> Vec<T::AccountId> {
+    (0..count).map(|i| account("member", i, 0)).collect()
+}
+
+fn bounded<T: Config>(accounts: Vec<T::AccountId>) -> BoundedVec<T::AccountId, T::MaxMembers> {
+    accounts
+        .try_into()
+        .expect("benchmarks never exceed MaxMembers")
+}
+
+benchmarks! {
+    change_members {
+        let n in 1 .. T::MaxMembers::get();
+        let members = accounts::<T>(n);
+        ReservedMembers::<T>::put(bounded::<T>(members[..1].to_vec()));
+    }: _(RawOrigin::Root, members.clone())
+    verify {
+        assert_eq!(Elections::<T>::members().into_inner(), members);
+    }
+
+    set_members_per_session {
+    }: _(RawOrigin::Root, T::MaxMembers::get())
+    verify {
+        assert_eq!(MembersPerSession::<T>::get(), T::MaxMembers::get());
+    }
+
+    change_reserved_members {
+        let n in 1 .. T::MaxMembers::get();
+        let members = accounts::<T>(n);
+        Members::<T>::put(bounded::<T>(members.clone()));
+    }: _(RawOrigin::Root, members.clone())
+    verify {
+        assert_eq!(ReservedMembers::<T>::get().into_inner(), members);
+    }
+
+    set_election_mode {
+    }: _(RawOrigin::Root, ElectionMode::DelegatedProofOfStake)
+    verify {
+        assert_eq!(Elections::<T>::election_mode(), ElectionMode::DelegatedProofOfStake);
+    }
+
+    set_rotation_mode {
+    }: _(RawOrigin::Root, RotationMode::Random)
+    verify {
+        assert_eq!(Elections::<T>::rotation_mode(), RotationMode::Random);
+    }
+
+    set_underperformance_thresholds {
+    }: _(RawOrigin::Root, Perbill::from_percent(50), 2)
+    verify {
+        assert_eq!(UnderperformanceThreshold::<T>::get(), Perbill::from_percent(50));
+        assert_eq!(UnderperformanceBenchEras::<T>::get(), 2);
+    }
+
+    // The whole committee took part in the finalization and all the proofs are valid.
+    note_finality_participation {
+        let n in 1 .. T::MaxMembers::get();
+        MembersPerSession::<T>::put(n);
+        let rewards: Vec<_> = accounts::<T>(n).into_iter().map(|account| (account, 100)).collect();
+        PendingFinalityParticipation::<T>::put(PendingFinalityRewards {
+            session: 1,
+            era: 1,
+            rewards: rewards.try_into().expect("there are at most MaxMembers accounts"),
+            reported: BoundedVec::default(),
+        });
+        let participation = FinalityParticipation {
+            session: 1,
+            participants: T::FinalityParticipationVerifier::benchmark_proofs(1, n),
+        };
+    }: _(RawOrigin::None, participation)
+    verify {
+        assert!(PendingFinalityParticipation::<T>::get().is_some());
+    }
+
+    impl_benchmark_test_suite!(
+        Elections,
+        crate::mock::new_test_ext(vec![1, 2], vec![]),
+        crate::mock::Test
+    );
+}
@@ -1,15 +1,33 @@
 use crate::{
-    traits::{EraInfoProvider, SessionInfoProvider, ValidatorRewardsHandler},
-    Config, ErasReserved, MembersPerSession, Pallet, ReservedMembers, SessionValidatorBlockCount,
+    traits::{
+        EraInfoProvider, FinalityParticipationVerifier, SessionInfoProvider, SessionPeriodProvider,
+        ValidatorBondProvider, ValidatorRewardsHandler,
+    },
+    BenchedValidators, BlockCount, Config, ElectionError, ErasReserved, ErasValidatorPerformance,
+    Event, MembersPerSession, NextEraReserved, Pallet, PendingFinalityParticipation,
+    PendingFinalityRewards, ReservedMembers, RotationMode, SessionValidatorBlockCount,
+    UnderperformanceBenchEras, UnderperformanceThreshold, ValidatorEraBlockCount,
     ValidatorEraTotalReward, ValidatorTotalRewards,
 };
-use frame_election_provider_support::sp_arithmetic::Perquintill;
-use frame_support::pallet_prelude::Get;
+use codec::Encode;
+use frame_election_provider_support::{
+    sp_arithmetic::{Perbill, Perquintill},
+    ElectionDataProvider,
+};
+use frame_support::{
+    log,
+    pallet_prelude::{BoundedVec, Get},
+    storage::bounded_btree_map::BoundedBTreeMap,
+    traits::Randomness,
+};
+use primitives::{EraValidators, ParticipationProof};
+use sp_core::hashing::blake2_256;
 use sp_staking::{EraIndex, SessionIndex};
 use sp_std::{collections::btree_map::BTreeMap, vec::Vec};
 
 const MAX_REWARD: u32 = 1_000_000_000;
 const LENIENT_THRESHOLD: Perquintill = Perquintill::from_percent(90);
+const COMMITTEE_SEED_SUBJECT: &[u8] = b"aleph/committee";
 
 fn calculate_adjusted_session_points(
     sessions_per_era: EraIndex,
@@ -64,6 +82,9 @@ fn rotate<T: Clone + PartialEq>(
         .filter(|v| !reserved.contains(v))
         .collect();
     let n_all_validators_without_reserved = validators_without_reserved.len();
+    if n_all_validators_without_reserved == 0 {
+        return Some(reserved);
+    }
 
     // The validators for the committee at the session `n` are chosen as follow:
     // 1. Reserved validators are always chosen.
@@ -84,15 +105,129 @@ fn rotate<T: Clone + PartialEq>(
     Some(committee)
 }
 
+// The `round`-th number of a deterministic stream of pseudorandom numbers derived from the `seed`.
+fn random_number<S: Encode>(seed: &S, round: u32) -> u128 {
+    let hash = blake2_256(&(seed, round).encode());
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&hash[..16]);
+    u128::from_le_bytes(bytes)
+}
+
+fn rotate_randomly<T: Clone + PartialEq, S: Encode>(
+    n_validators: usize,
+    validators: Vec<(T, u128)>,
+    reserved: Vec<T>,
+    seed: S,
+) -> Vec<T> {
+    let mut candidates: Vec<_> = validators
+        .into_iter()
+        .filter(|(v, _)| !reserved.contains(v))
+        .collect();
+    let free_seats = n_validators.saturating_sub(reserved.len());
+
+    // The validators for the committee are chosen as follow:
+    // 1. Reserved validators are always chosen.
+    // 2. The free seats are filled one by one, every time the validator is drawn from the ones not
+    // chosen yet, with probability proportional to its stake. If none of them has any stake, every
+    // one of them is equally likely.
+    let mut chosen = Vec::new();
+    let mut round = 0;
+    while chosen.len() < free_seats && !candidates.is_empty() {
+        let random = random_number(&seed, round);
+        round += 1;
+        let total = candidates
+            .iter()
+            .fold(0u128, |total, (_, stake)| total.saturating_add(*stake));
+        let index = if total == 0 {
+            (random % candidates.len() as u128) as usize
+        } else {
+            let mut point = random % total;
+            candidates
+                .iter()
+                .position(|(_, stake)| {
+                    if point < *stake {
+                        return true;
+                    }
+                    point -= stake;
+                    false
+                })
+                .unwrap_or(candidates.len() - 1)
+        };
+        chosen.push(candidates.remove(index).0);
+    }
+
+    reserved.into_iter().chain(chosen).collect()
+}
+
+fn select_by_stake<T: Clone + Ord>(
+    n_validators: usize,
+    candidates: Vec<(T, u128)>,
+    reserved: Vec<T>,
+) -> Vec<T> {
+    let mut candidates: Vec<_> = candidates
+        .into_iter()
+        .filter(|(c, _)| !reserved.contains(c))
+        .collect();
+    // Highest stake first, ties are broken by the account id so that the result is deterministic.
+    candidates.sort_by(|(c1, s1), (c2, s2)| s2.cmp(s1).then_with(|| c1.cmp(c2)));
+
+    let free_seats = n_validators.saturating_sub(reserved.len());
+    reserved
+        .into_iter()
+        .chain(candidates.into_iter().take(free_seats).map(|(c, _)| c))
+        .collect()
+}
+
 impl<T> Pallet<T>
 where
     T: Config,
 {
+    // Elects the `desired_targets` validators: the reserved members and the candidates with at
+    // least the minimal bond and the highest backing. The reserved members are taken from
+    // `ReservedMembers`, as this is what `NextEraReserved` is populated with for the elected era.
+    pub(crate) fn members_by_stake(
+        backing: BTreeMap<T::AccountId, u128>,
+    ) -> Result<Vec<T::AccountId>, ElectionError> {
+        let candidates =
+            T::DataProvider::electable_targets(None).map_err(ElectionError::DataProvider)?;
+        let n_validators =
+            T::DataProvider::desired_targets().map_err(ElectionError::DataProvider)?;
+        let min_bond = T::ValidatorBondProvider::min_validator_bond();
+
+        let candidates = candidates
+            .into_iter()
+            .filter(|c| {
+                T::ValidatorBondProvider::active_bond(c).map_or(false, |bond| bond >= min_bond)
+            })
+            .map(|c| {
+                let stake = backing.get(&c).copied().unwrap_or(0);
+                (c, stake)
+            })
+            .collect();
+
+        Ok(select_by_stake(
+            n_validators as usize,
+            candidates,
+            ReservedMembers::<T>::get().into_inner(),
+        ))
+    }
+
     fn update_validator_total_rewards(era: EraIndex) {
         let validator_totals = T::ValidatorRewardsHandler::validator_totals(era);
-        let scaled_totals = compute_validator_scaled_total_rewards(validator_totals).into_iter();
+        let scaled_totals = compute_validator_scaled_total_rewards(validator_totals);
 
-        ValidatorEraTotalReward::<T>::put(ValidatorTotalRewards(scaled_totals.collect()));
+        let mut totals = BoundedBTreeMap::<_, _, T::MaxMembers>::new();
+        for (validator, total) in scaled_totals {
+            if totals.try_insert(validator, total).is_err() {
+                log::warn!(target: "pallet_elections", "More than MaxMembers validators in era {:?}, the rest gets no rewards", era);
+                break;
+            }
+        }
+        ValidatorEraTotalReward::<T>::put(ValidatorTotalRewards(totals));
+    }
+
+    fn is_benched(validator: &T::AccountId, era: EraIndex) -> bool {
+        Self::benched_until(validator).map_or(false, |until| until > era)
     }
 
     fn get_committee_and_non_committee(
@@ -107,8 +242,8 @@ where
         (committee, non_committee)
     }
 
-    fn blocks_to_produce_per_session() -> u32 {
-        T::SessionPeriod::get() / MembersPerSession::<T>::get()
+    fn blocks_to_produce_per_session(session: SessionIndex) -> u32 {
+        T::SessionPeriodProvider::session_period(session) / MembersPerSession::<T>::get()
     }
 
     fn reward_for_session_non_committee(
@@ -131,47 +266,160 @@ where
         })
     }
 
+    // The part of the committee rewards depending on the finality participation is paid separately.
     fn reward_for_session_committee(
         committee: Vec<T::AccountId>,
         nr_of_sessions: SessionIndex,
         blocks_per_session: u32,
         validator_totals: &BTreeMap<T::AccountId, u32>,
     ) -> impl IntoIterator<Item = (T::AccountId, u32)> + '_ {
+        let block_production_ratio = Perbill::one() - T::FinalityRewardRatio::get();
         committee.into_iter().map(move |validator| {
             let total = BTreeMap::<_, _>::get(validator_totals, &validator).unwrap_or(&0);
             let blocks_created = SessionValidatorBlockCount::<T>::get(&validator);
             (
                 validator,
-                calculate_adjusted_session_points(
-                    nr_of_sessions,
-                    blocks_per_session,
-                    blocks_created,
-                    *total,
-                ),
+                block_production_ratio
+                    * calculate_adjusted_session_points(
+                        nr_of_sessions,
+                        blocks_per_session,
+                        blocks_created,
+                        *total,
+                    ),
             )
         })
     }
 
+    // The finality rewards of the committee members for the session, computed from the totals of
+    // the active era.
+    fn finality_rewards(
+        session: SessionIndex,
+        committee: Vec<T::AccountId>,
+    ) -> Vec<(T::AccountId, u32)> {
+        let finality_ratio = T::FinalityRewardRatio::get();
+        let nr_of_sessions = T::EraInfoProvider::sessions_per_era();
+        let blocks_per_session = Self::blocks_to_produce_per_session(session);
+        let validator_total_rewards = ValidatorEraTotalReward::<T>::get()
+            .map(|totals| totals.0.into_inner())
+            .unwrap_or_default();
+
+        committee
+            .into_iter()
+            .map(|validator| {
+                let total = validator_total_rewards.get(&validator).unwrap_or(&0);
+                (
+                    validator,
+                    finality_ratio
+                        * calculate_adjusted_session_points(
+                            nr_of_sessions,
+                            blocks_per_session,
+                            blocks_per_session,
+                            *total,
+                        ),
+                )
+            })
+            .collect()
+    }
+
+    // Pays the members whose participation is proven for the first time. Invalid proofs are
+    // skipped, so that the valid ones still count.
+    pub(crate) fn reward_finality_participation(
+        pending: &mut PendingFinalityRewards<T::AccountId, T::MaxMembers>,
+        participants: Vec<ParticipationProof>,
+    ) {
+        let mut rewards = Vec::new();
+        for proof in participants {
+            if pending.reported.contains(&proof.member) {
+                continue;
+            }
+            let validator =
+                match T::FinalityParticipationVerifier::participant(pending.session, &proof) {
+                    Some(validator) => validator,
+                    None => continue,
+                };
+            if pending.reported.try_push(proof.member).is_err() {
+                continue;
+            }
+            if let Some(index) = pending.rewards.iter().position(|(v, _)| v == &validator) {
+                rewards.push(pending.rewards.remove(index));
+            }
+        }
+
+        T::ValidatorRewardsHandler::add_era_rewards(pending.era, rewards);
+    }
+
+    // Keeps the finality rewards of the committee of the ending session until its participation
+    // is reported. If nothing was ever reported for the previous session, its whole committee is
+    // rewarded.
+    fn expect_finality_participation(session: SessionIndex) {
+        if let Some(pending) = PendingFinalityParticipation::<T>::take() {
+            if pending.reported.is_empty() {
+                T::ValidatorRewardsHandler::add_era_rewards(
+                    pending.era,
+                    pending.rewards.into_inner(),
+                );
+            }
+        }
+        let active_era = match T::EraInfoProvider::active_era() {
+            Some(ae) if ae > 0 => ae,
+            _ => return,
+        };
+
+        // `rotate_committee` never chooses more than `MaxMembers` validators.
+        let committee: Vec<_> = T::SessionInfoProvider::current_committee()
+            .into_iter()
+            .take(T::MaxMembers::get() as usize)
+            .collect();
+        if let Ok(rewards) = BoundedVec::try_from(Self::finality_rewards(session, committee)) {
+            PendingFinalityParticipation::<T>::put(PendingFinalityRewards {
+                session,
+                era: active_era,
+                rewards,
+                reported: BoundedVec::default(),
+            });
+        }
+    }
+
     // Choose a subset of all the validators for current era that contains all the
-    // reserved nodes. Non reserved ones are chosen in consecutive batches for every session
+    // reserved nodes. Non reserved ones are chosen either in consecutive batches for every session,
+    // or randomly, weighted by their stake, in both cases skipping the benched ones. As both the
+    // reserved nodes and the committee size are bounded, the committee has at most `MaxMembers`
+    // members.
     fn rotate_committee() -> Option<Vec<T::AccountId>> {
         let current_era = match T::EraInfoProvider::active_era() {
             Some(ae) if ae > 0 => ae,
             _ => return None,
         };
 
-        let all_validators = T::ValidatorRewardsHandler::all_era_validators(current_era);
-        let reserved = ErasReserved::<T>::get();
-        let n_validators = MembersPerSession::<T>::get() as usize;
+        let reserved = ErasReserved::<T>::get().into_inner();
+        let n_validators = MembersPerSession::<T>::get().min(T::MaxMembers::get()) as usize;
         let current_session = T::SessionInfoProvider::current_session_index();
 
-        rotate(
-            current_era,
-            current_session,
-            n_validators,
-            all_validators,
-            reserved,
-        )
+        match Self::rotation_mode() {
+            RotationMode::Sequential => rotate(
+                current_era,
+                current_session,
+                n_validators,
+                T::ValidatorRewardsHandler::all_era_validators(current_era)
+                    .into_iter()
+                    .filter(|v| !Self::is_benched(v, current_era))
+                    .collect(),
+                reserved,
+            ),
+            RotationMode::Random => {
+                let subject = (COMMITTEE_SEED_SUBJECT, current_session).encode();
+                let (seed, _) = T::Randomness::random(&subject);
+                Some(rotate_randomly(
+                    n_validators,
+                    T::ValidatorRewardsHandler::validator_totals(current_era)
+                        .into_iter()
+                        .filter(|(v, _)| !Self::is_benched(v, current_era))
+                        .collect(),
+                    reserved,
+                    seed,
+                ))
+            }
+        }
     }
 
     fn if_era_starts_do<F: Fn()>(era: EraIndex, start_index: SessionIndex, on_era_start: F) {
@@ -192,7 +440,21 @@ where
         // `n+1` starts a new era.
         Self::if_era_starts_do(active_era + 1, session, || {
             let reserved_validators = ReservedMembers::<T>::get();
-            ErasReserved::<T>::put(reserved_validators)
+            NextEraReserved::<T>::put(reserved_validators)
+        });
+    }
+
+    // `ErasReserved` keeps the reserved nodes of the active era until the next one starts.
+    fn activate_reserved_on_new_era_start(session: SessionIndex) {
+        let active_era = match T::EraInfoProvider::active_era() {
+            Some(ae) => ae,
+            _ => return,
+        };
+
+        Self::if_era_starts_do(active_era, session, || {
+            if let Some(reserved_validators) = NextEraReserved::<T>::take() {
+                ErasReserved::<T>::put(reserved_validators)
+            }
         });
     }
 
@@ -207,18 +469,88 @@ where
         });
     }
 
-    fn adjust_rewards_for_session() {
+    fn record_session_performance(session: SessionIndex) {
+        if T::EraInfoProvider::active_era().is_none() {
+            return;
+        }
+
+        let expected = Self::blocks_to_produce_per_session(session);
+        for validator in T::SessionInfoProvider::current_committee() {
+            let created = SessionValidatorBlockCount::<T>::get(&validator);
+            ValidatorEraBlockCount::<T>::mutate(&validator, |production| {
+                production.created = production.created.saturating_add(created);
+                production.expected = production.expected.saturating_add(expected);
+            });
+        }
+    }
+
+    // Computes the block production ratios in the era that has just ended and benches the
+    // validators below the threshold for the eras starting with the `active_era`.
+    pub(crate) fn evaluate_era_performance(active_era: EraIndex) {
+        if active_era == 0 {
+            return;
+        }
+        let ended_era = active_era - 1;
+        let threshold = UnderperformanceThreshold::<T>::get();
+        let bench_eras = UnderperformanceBenchEras::<T>::get();
+        let reserved = ErasReserved::<T>::get();
+
+        BenchedValidators::<T>::translate(|_, until: EraIndex| {
+            if until > active_era {
+                Some(until)
+            } else {
+                None
+            }
+        });
+
+        for (validator, production) in ValidatorEraBlockCount::<T>::drain() {
+            if production.expected == 0 {
+                continue;
+            }
+            let performance = Perbill::from_rational(production.created, production.expected);
+            ErasValidatorPerformance::<T>::insert(ended_era, &validator, performance);
+
+            if performance < threshold && !reserved.contains(&validator) {
+                if bench_eras > 0 {
+                    BenchedValidators::<T>::insert(&validator, active_era + bench_eras);
+                }
+                Self::deposit_event(Event::ValidatorUnderperformed(validator, performance));
+            }
+        }
+
+        if let Some(era) = ended_era.checked_sub(T::PerformanceHistoryDepth::get()) {
+            ErasValidatorPerformance::<T>::remove_prefix(era, None);
+        }
+    }
+
+    fn evaluate_performance_on_new_era_start(session: SessionIndex) {
+        let active_era = match T::EraInfoProvider::active_era() {
+            Some(ae) => ae,
+            _ => return,
+        };
+
+        Self::if_era_starts_do(active_era, session, || {
+            Self::evaluate_era_performance(active_era)
+        });
+    }
+
+    fn adjust_rewards_for_session(session: SessionIndex) {
         let active_era = match T::EraInfoProvider::active_era() {
             Some(ae) if ae > 0 => ae,
             _ => return,
         };
 
+        T::ValidatorRewardsHandler::add_rewards(Self::session_rewards(active_era, session));
+    }
+
+    // The rewards for the current session, based on the blocks produced in it so far.
+    fn session_rewards(active_era: EraIndex, session: SessionIndex) -> Vec<(T::AccountId, u32)> {
         let (committee, non_committee) = Self::get_committee_and_non_committee(active_era);
         let nr_of_sessions = T::EraInfoProvider::sessions_per_era();
-        let blocks_per_session = Self::blocks_to_produce_per_session();
+        let blocks_per_session = Self::blocks_to_produce_per_session(session);
         let validator_total_rewards = ValidatorEraTotalReward::<T>::get()
-            .unwrap_or_else(|| ValidatorTotalRewards(BTreeMap::new()))
-            .0;
+            .map(|totals| totals.0.into_inner())
+            .unwrap_or_default();
 
         let rewards = Self::reward_for_session_non_committee(
             non_committee,
@@ -237,7 +569,60 @@ where
             .into_iter(),
         );
 
-        T::ValidatorRewardsHandler::add_rewards(rewards);
+        rewards.collect()
+    }
+
+    /// The committee of the next session, which is chosen when the current one starts.
+    pub fn next_session_committee() -> Vec<T::AccountId> {
+        T::SessionInfoProvider::next_committee()
+    }
+
+    /// The validators of the active era, split into the reserved ones and the rest.
+    pub fn era_validators() -> EraValidators<T::AccountId> {
+        let reserved = ErasReserved::<T>::get().into_inner();
+        let non_reserved = match T::EraInfoProvider::active_era() {
+            Some(ae) => T::ValidatorRewardsHandler::all_era_validators(ae)
+                .into_iter()
+                .filter(|v| !reserved.contains(v))
+                .collect(),
+            None => Vec::new(),
+        };
+
+        EraValidators {
+            reserved,
+            non_reserved,
+        }
+    }
+
+    /// How many blocks the validator has produced in the current session so far.
+    pub fn validator_block_count(validator: T::AccountId) -> BlockCount {
+        SessionValidatorBlockCount::<T>::get(validator)
+    }
+
+    /// The reward points of the active era, together with the rewards for the current session as
+    /// if it ended now. The rewards for finality participation are only included once paid.
+    pub fn era_reward_points_preview() -> Vec<(T::AccountId, u32)> {
+        let active_era = match T::EraInfoProvider::active_era() {
+            Some(ae) => ae,
+            None => return Vec::new(),
+        };
+
+        // As in `adjust_rewards_for_session`, nothing is paid for the sessions of the era 0.
+        let session_rewards = if active_era > 0 {
+            Self::session_rewards(active_era, T::SessionInfoProvider::current_session_index())
+        } else {
+            Vec::new()
+        };
+        let mut points = BTreeMap::new();
+        for (validator, reward) in T::ValidatorRewardsHandler::era_reward_points(active_era)
+            .into_iter()
+            .chain(session_rewards)
+        {
+            let total = points.entry(validator).or_insert(0u32);
+            *total = total.saturating_add(reward);
+        }
+
+        points.into_iter().collect()
     }
 }
 
@@ -263,6 +648,9 @@ where
         // new session is always called before the end_session of the previous session
         // so we need to populate reserved set here not on start_session nor end_session
         let committee = Self::rotate_committee();
+        if let Some(committee) = &committee {
+            Self::deposit_event(Event::CommitteeRotated(new_index, committee.clone()));
+        }
         Self::populate_reserved_on_next_era_start(new_index);
 
         committee
@@ -274,7 +662,9 @@ where
 
     fn end_session(end_index: SessionIndex) {
         <T as Config>::SessionManager::end_session(end_index);
-        Self::adjust_rewards_for_session();
+        Self::adjust_rewards_for_session(end_index);
+        Self::expect_finality_participation(end_index);
+        Self::record_session_performance(end_index);
 
         // clear block count
         SessionValidatorBlockCount::<T>::remove_all(None);
@@ -282,7 +672,9 @@ where
 
     fn start_session(start_index: SessionIndex) {
         <T as Config>::SessionManager::start_session(start_index);
+        Self::activate_reserved_on_new_era_start(start_index);
         Self::populate_totals_on_new_era_start(start_index);
+        Self::evaluate_performance_on_new_era_start(start_index);
     }
 }
 
@@ -290,7 +682,7 @@ where
 mod tests {
     use crate::impls::{
         calculate_adjusted_session_points, compute_validator_scaled_total_rewards, rotate,
-        MAX_REWARD,
+        rotate_randomly, select_by_stake, MAX_REWARD,
     };
     use std::collections::VecDeque;
 
@@ -423,4 +815,80 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn select_by_stake_chooses_highest_stakes() {
+        assert_eq!(
+            vec![3, 1],
+            select_by_stake(2, vec![(1, 20), (2, 10), (3, 30)], vec![])
+        );
+    }
+
+    #[test]
+    fn select_by_stake_breaks_ties_by_id() {
+        assert_eq!(
+            vec![1, 3],
+            select_by_stake(2, vec![(3, 10), (2, 5), (1, 10)], vec![])
+        );
+    }
+
+    #[test]
+    fn select_by_stake_always_chooses_reserved() {
+        assert_eq!(
+            vec![4, 2, 3],
+            select_by_stake(3, vec![(1, 10), (2, 30), (3, 20), (4, 0)], vec![4, 2])
+        );
+        assert_eq!(
+            vec![4, 5],
+            select_by_stake(1, vec![(1, 10), (2, 30)], vec![4, 5])
+        );
+    }
+
+    #[test]
+    fn rotate_randomly_chooses_reserved_and_distinct_validators() {
+        let validators: Vec<_> = (0..20).map(|v| (v, 10 + v as u128)).collect();
+        let reserved = vec![3, 7];
+
+        for seed in 0u64..100 {
+            let committee = rotate_randomly(8, validators.clone(), reserved.clone(), seed);
+            assert_eq!(8, committee.len());
+            assert_eq!(reserved[..], committee[..2]);
+            for (i, v) in committee.iter().enumerate() {
+                assert!(!committee[..i].contains(v));
+            }
+        }
+    }
+
+    #[test]
+    fn rotate_randomly_is_deterministic() {
+        let validators: Vec<_> = (0..20).map(|v| (v, 10 + v as u128)).collect();
+
+        assert_eq!(
+            rotate_randomly(8, validators.clone(), vec![1], [7u8; 32]),
+            rotate_randomly(8, validators, vec![1], [7u8; 32])
+        );
+    }
+
+    #[test]
+    fn rotate_randomly_chooses_everyone_when_seats_suffice() {
+        let mut committee = rotate_randomly(10, vec![(1, 0), (2, 5), (3, 0)], vec![4], 0u64);
+        committee.sort_unstable();
+
+        assert_eq!(vec![1, 2, 3, 4], committee);
+    }
+
+    #[test]
+    fn rotate_randomly_prefers_validators_with_more_stake() {
+        let validators = vec![(1, 1), (2, 1_000), (3, 0)];
+
+        let mut chosen_richer = 0;
+        for seed in 0u64..1000 {
+            let committee = rotate_randomly(1, validators.clone(), vec![], seed);
+            assert_ne!(vec![3], committee);
+            if committee == vec![2] {
+                chosen_richer += 1;
+            }
+        }
+        assert!(chosen_richer > 950);
+    }
 }
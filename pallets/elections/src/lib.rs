@@ -1,6 +1,31 @@
 //! This pallet manages changes in the committee responsible for producing blocks and establishing consensus.
-//! Currently, it's PoA where the validators are set by the root account. In the future, a new
-//! version for DPoS elections will replace the current one.
+//! The validators of the era are elected in one of two modes, chosen by the root account:
+//! - PoA (default), where the validators are set by the root account.
+//! - DPoS, where the validators are the candidates (accounts that called `staking.validate`) with
+//!   at least `MinValidatorBond` bonded, with the highest backed stake.
+//!
+//! In both modes the reserved members are always elected.
+//!
+//! The committee of every session consists of the reserved members and of other validators of
+//! the era, rotated in one of two modes, chosen by the root account:
+//! - Sequential (default), where consecutive sessions take consecutive windows of the validators.
+//! - Random, where the validators are sampled without replacement, with probability proportional
+//!   to their stake, using on-chain randomness. As the committee is chosen when the previous
+//!   session starts, it is still known one session ahead.
+//!
+//! The block production of every validator is tracked over the era. When a new era starts,
+//! validators that produced less than `UnderperformanceThreshold` of the blocks expected from them
+//! in the previous era are benched: they are not chosen to the committee, nor rewarded for the
+//! sessions they sit out, for the next `UnderperformanceBenchEras` eras. Reserved members are never
+//! benched.
+//!
+//! The session reward of a committee member depends on its work in both block production and
+//! finalization. `FinalityRewardRatio` of it is paid only to the members that contributed
+//! signatures to the justifications of the session, as reported by the block author of the next
+//! session in an inherent. Every reported participant comes with its signature of one of the blocks
+//! of the session, checked by `FinalityParticipationVerifier` against the finality authorities of
+//! the session, so the author can leave participants out but cannot make them up. If no report
+//! arrives before the next session ends, every member of the committee gets that part.
 //!
 //! ### Terminology
 //! For definition of session, era, staking see pallet_session and pallet_staking.
@@ -10,51 +35,158 @@
 //! - ReservedMembers: Validators that are chosen to be in committee every single session.
 //!
 //! ### Storage
-//! - `Members` - List of possible validators.
+//! - `Members` - List of possible validators, used in the PoA mode.
+//! - `CurrentElectionMode` - The mode used to elect the validators of the next era.
 //! - `MembersPerSession` - Committee size.
+//! - `CurrentRotationMode` - The mode used to choose the committee of the next session.
 //! - `ReservedMembers` - List of reserved nodes.
-//! - `ErasReserved` - List of reserved nodes for the current era.
-//!   This is populated from `ReservedMembers` at the time of planning the first session of the era.
+//! - `ErasReserved` - List of reserved nodes for the active era.
+//! - `NextEraReserved` - List of reserved nodes for the next era. This is populated from
+//!   `ReservedMembers` at the time of planning the first session of the era and moved to
+//!   `ErasReserved` once the era starts.
 //! - `SessionValidatorBlockCount` - Count per validator, how many blocks did the validator produced
 //!   in the current session.
 //! - `ValidatorEraTotalReward` - Total possible reward per validator for the current era. Scaled to
 //!   fit in the u32.
+//! - `ValidatorEraBlockCount` - Blocks created and expected per validator in the current era.
+//! - `ErasValidatorPerformance` - Ratio of created to expected blocks per validator in the past
+//!   eras, kept for `PerformanceHistoryDepth` eras.
+//! - `UnderperformanceThreshold` - Validators with the ratio below it are benched.
+//! - `UnderperformanceBenchEras` - For how many eras the underperforming validators are benched.
+//! - `BenchedValidators` - The first era in which the benched validators can be chosen again.
+//! - `PendingFinalityParticipation` - The last ended session, the era it belongs to and the
+//!   finality rewards of its committee members whose participation has not been reported yet.
+//!
+//! The lists of accounts kept in the storage hold at most `MaxMembers` accounts each.
+//!
+//! The committee of the next session, the validators of the era, the blocks produced in the
+//! current session and a preview of the reward points of the era are exposed to the clients by the
+//! `ElectionsApi` runtime API.
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
+#[cfg(feature = "runtime-benchmarks")]
+mod benchmarking;
 mod impls;
+mod migrations;
 #[cfg(test)]
 mod mock;
 #[cfg(test)]
 mod tests;
 mod traits;
-
-use codec::{Decode, Encode};
-use frame_support::traits::StorageVersion;
+pub mod weights;
+
+use codec::{Decode, Encode, MaxEncodedLen};
+use frame_election_provider_support::sp_arithmetic::Perbill;
+use frame_support::{
+    log,
+    storage::bounded_btree_map::BoundedBTreeMap,
+    traits::{Get, StorageVersion},
+    BoundedVec, CloneNoBound, EqNoBound, PartialEqNoBound, RuntimeDebugNoBound,
+};
 use scale_info::TypeInfo;
+use sp_staking::{EraIndex, SessionIndex};
 use sp_std::{collections::btree_map::BTreeMap, prelude::Vec};
 
 pub use pallet::*;
+pub use weights::WeightInfo;
 
-const STORAGE_VERSION: StorageVersion = StorageVersion::new(0);
+/// The current storage version.
+const STORAGE_VERSION: StorageVersion = StorageVersion::new(1);
 
 pub type BlockCount = u32;
 pub type TotalReward = u32;
 
-#[derive(Decode, Encode, TypeInfo)]
-pub struct ValidatorTotalRewards<T>(pub BTreeMap<T, TotalReward>);
+#[derive(Decode, Encode, TypeInfo, MaxEncodedLen)]
+#[scale_info(skip_type_params(S))]
+#[codec(mel_bound(T: MaxEncodedLen, S: Get<u32>))]
+pub struct ValidatorTotalRewards<T, S>(pub BoundedBTreeMap<T, TotalReward, S>);
+
+/// The finality rewards of the committee of an ended session. A member is paid once its
+/// participation in the finalization of the session is proven, in the era the session belongs to.
+#[derive(
+    Decode,
+    Encode,
+    TypeInfo,
+    MaxEncodedLen,
+    CloneNoBound,
+    PartialEqNoBound,
+    EqNoBound,
+    RuntimeDebugNoBound,
+)]
+#[scale_info(skip_type_params(S))]
+#[codec(mel_bound(T: MaxEncodedLen, S: Get<u32>))]
+pub struct PendingFinalityRewards<T: Clone + PartialEq + Eq + sp_std::fmt::Debug, S: Get<u32>> {
+    pub session: SessionIndex,
+    pub era: EraIndex,
+    /// The rewards of the members whose participation was not proven yet.
+    pub rewards: BoundedVec<(T, u32), S>,
+    /// The finality authority indices of the members whose participation was proven.
+    pub reported: BoundedVec<u32, S>,
+}
+
+/// How many blocks a validator created and how many it was expected to create.
+#[derive(Decode, Encode, TypeInfo, MaxEncodedLen, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BlockProduction {
+    pub created: BlockCount,
+    pub expected: BlockCount,
+}
+
+/// The way the validators of an era are elected.
+#[derive(Decode, Encode, TypeInfo, MaxEncodedLen, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ElectionMode {
+    /// The validators are the `Members` set by the root account.
+    ProofOfAuthority,
+    /// The validators are the candidates with the highest backed stake.
+    DelegatedProofOfStake,
+}
+
+impl Default for ElectionMode {
+    fn default() -> Self {
+        ElectionMode::ProofOfAuthority
+    }
+}
+
+/// The way the non-reserved members of the committee are chosen for every session.
+#[derive(Decode, Encode, TypeInfo, MaxEncodedLen, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RotationMode {
+    /// Consecutive sessions take consecutive windows of the validators.
+    Sequential,
+    /// The validators are sampled with probability proportional to their stake.
+    Random,
+}
+
+impl Default for RotationMode {
+    fn default() -> Self {
+        RotationMode::Sequential
+    }
+}
 
 #[frame_support::pallet]
 pub mod pallet {
     use super::*;
-    use crate::traits::{EraInfoProvider, SessionInfoProvider, ValidatorRewardsHandler};
+    use crate::traits::{
+        EraInfoProvider, FinalityParticipationVerifier, SessionInfoProvider, SessionPeriodProvider,
+        ValidatorBondProvider, ValidatorRewardsHandler,
+    };
     use frame_election_provider_support::{
         ElectionDataProvider, ElectionProvider, Support, Supports,
     };
-    use frame_support::{pallet_prelude::*, traits::Get};
-    use frame_system::{ensure_root, pallet_prelude::OriginFor};
+    use frame_support::{
+        inherent::{InherentData, InherentIdentifier, MakeFatalError, ProvideInherent},
+        pallet_prelude::*,
+        traits::{Get, Randomness},
+    };
+    use frame_system::{
+        ensure_none, ensure_root,
+        pallet_prelude::{BlockNumberFor, OriginFor},
+    };
     use pallet_session::SessionManager;
-    use primitives::DEFAULT_MEMBERS_PER_SESSION;
+    use primitives::{
+        FinalityParticipation, DEFAULT_MEMBERS_PER_SESSION,
+        FINALITY_PARTICIPATION_INHERENT_IDENTIFIER,
+    };
+    use sp_staking::SessionIndex;
 
     #[pallet::config]
     pub trait Config: frame_system::Config {
@@ -66,40 +198,119 @@ pub mod pallet {
             AccountId = Self::AccountId,
             BlockNumber = Self::BlockNumber,
         >;
-        /// Nr of blocks in the session.
-        #[pallet::constant]
-        type SessionPeriod: Get<u32>;
+        /// Something that provides the lengths of sessions.
+        type SessionPeriodProvider: SessionPeriodProvider;
         /// Handler for managing new session.
         type SessionManager: SessionManager<<Self as frame_system::Config>::AccountId>;
         /// Something that provides information about sessions.
         type SessionInfoProvider: SessionInfoProvider<Self>;
         /// Something that handles addition of rewards for validators.
         type ValidatorRewardsHandler: ValidatorRewardsHandler<Self>;
+        /// Something that provides the bonds of the candidates in the DPoS mode.
+        type ValidatorBondProvider: ValidatorBondProvider<Self>;
+        /// Source of randomness for the random committee rotation.
+        type Randomness: Randomness<Self::Hash, Self::BlockNumber>;
+        /// For how many eras the block production ratios of the validators are kept.
+        #[pallet::constant]
+        type PerformanceHistoryDepth: Get<EraIndex>;
+        /// The part of the session reward of a committee member that is paid only if it took part
+        /// in finalization.
+        #[pallet::constant]
+        type FinalityRewardRatio: Get<Perbill>;
+        /// Something that checks the proofs of the finality participation.
+        type FinalityParticipationVerifier: FinalityParticipationVerifier<Self>;
+        /// The maximal number of accounts in the lists of members and reserved members, and in a
+        /// committee.
+        #[pallet::constant]
+        type MaxMembers: Get<u32>;
+        /// Weight information for the calls of this pallet.
+        type WeightInfo: WeightInfo;
     }
 
     #[pallet::event]
     #[pallet::generate_deposit(pub(super) fn deposit_event)]
     pub enum Event<T: Config> {
         ChangeMembers(Vec<T::AccountId>),
+        /// The validators of the next eras will be elected in the given mode.
+        ElectionModeChanged(ElectionMode),
+        /// The committees of the next sessions will be chosen in the given mode.
+        RotationModeChanged(RotationMode),
+        /// The validator created too few blocks in the last era, given as the ratio of created to
+        /// expected blocks, and is benched.
+        ValidatorUnderperformed(T::AccountId, Perbill),
+        /// The committee of the given session was chosen.
+        CommitteeRotated(SessionIndex, Vec<T::AccountId>),
+        /// The reserved members were changed, they are reserved starting with the next era.
+        ReservedChanged(Vec<T::AccountId>),
+        /// The size of the committee was changed.
+        MembersPerSessionChanged(u32),
+    }
+
+    #[pallet::error]
+    pub enum Error<T> {
+        /// The list has more than `MaxMembers` accounts.
+        TooManyMembers,
+        /// An account appears in the list more than once.
+        DuplicatedMember,
+        /// A reserved member is not one of the members.
+        ReservedMemberNotInMembers,
+        /// The committee has to have at least one member.
+        ZeroMembersPerSession,
     }
 
     #[pallet::pallet]
     #[pallet::storage_version(STORAGE_VERSION)]
-    #[pallet::without_storage_info]
     pub struct Pallet<T>(_);
 
+    #[pallet::hooks]
+    impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+        fn on_runtime_upgrade() -> Weight {
+            let on_chain = <Pallet<T> as GetStorageVersion>::on_chain_storage_version();
+            T::DbWeight::get().reads(1)
+                + match on_chain {
+                    _ if on_chain == STORAGE_VERSION => 0,
+                    _ if on_chain == StorageVersion::new(0) => {
+                        migrations::v0_to_v1::migrate::<T, Self>()
+                    }
+                    _ => {
+                        log::warn!(
+                            target: "pallet_elections",
+                            "On chain storage version of pallet elections is {:?} but it should not be bigger than 1",
+                            on_chain
+                        );
+                        0
+                    }
+                }
+        }
+    }
+
     #[pallet::storage]
     #[pallet::getter(fn members)]
-    pub type Members<T: Config> = StorageValue<_, Vec<T::AccountId>, ValueQuery>;
+    pub type Members<T: Config> =
+        StorageValue<_, BoundedVec<T::AccountId, T::MaxMembers>, ValueQuery>;
+
+    #[pallet::storage]
+    #[pallet::getter(fn election_mode)]
+    pub type CurrentElectionMode<T> = StorageValue<_, ElectionMode, ValueQuery>;
 
     #[pallet::storage]
     pub type MembersPerSession<T> = StorageValue<_, u32, ValueQuery>;
 
     #[pallet::storage]
-    pub type ReservedMembers<T: Config> = StorageValue<_, Vec<T::AccountId>, ValueQuery>;
+    #[pallet::getter(fn rotation_mode)]
+    pub type CurrentRotationMode<T> = StorageValue<_, RotationMode, ValueQuery>;
 
     #[pallet::storage]
-    pub type ErasReserved<T: Config> = StorageValue<_, Vec<T::AccountId>, ValueQuery>;
+    pub type ReservedMembers<T: Config> =
+        StorageValue<_, BoundedVec<T::AccountId, T::MaxMembers>, ValueQuery>;
+
+    #[pallet::storage]
+    pub type ErasReserved<T: Config> =
+        StorageValue<_, BoundedVec<T::AccountId, T::MaxMembers>, ValueQuery>;
+
+    #[pallet::storage]
+    pub type NextEraReserved<T: Config> =
+        StorageValue<_, BoundedVec<T::AccountId, T::MaxMembers>, OptionQuery>;
 
     #[pallet::storage]
     pub type SessionValidatorBlockCount<T: Config> =
@@ -107,40 +318,192 @@ pub mod pallet {
 
     #[pallet::storage]
     pub type ValidatorEraTotalReward<T: Config> =
-        StorageValue<_, ValidatorTotalRewards<T::AccountId>, OptionQuery>;
+        StorageValue<_, ValidatorTotalRewards<T::AccountId, T::MaxMembers>, OptionQuery>;
+
+    #[pallet::storage]
+    pub type ValidatorEraBlockCount<T: Config> =
+        StorageMap<_, Twox64Concat, T::AccountId, BlockProduction, ValueQuery>;
+
+    #[pallet::storage]
+    #[pallet::getter(fn era_validator_performance)]
+    pub type ErasValidatorPerformance<T: Config> = StorageDoubleMap<
+        _,
+        Twox64Concat,
+        EraIndex,
+        Twox64Concat,
+        T::AccountId,
+        Perbill,
+        OptionQuery,
+    >;
+
+    #[pallet::storage]
+    pub type UnderperformanceThreshold<T> = StorageValue<_, Perbill, ValueQuery>;
+
+    #[pallet::storage]
+    pub type UnderperformanceBenchEras<T> = StorageValue<_, EraIndex, ValueQuery>;
+
+    #[pallet::storage]
+    #[pallet::getter(fn benched_until)]
+    pub type BenchedValidators<T: Config> =
+        StorageMap<_, Twox64Concat, T::AccountId, EraIndex, OptionQuery>;
+
+    #[pallet::storage]
+    pub type PendingFinalityParticipation<T: Config> =
+        StorageValue<_, PendingFinalityRewards<T::AccountId, T::MaxMembers>, OptionQuery>;
 
     #[pallet::call]
     impl<T: Config> Pallet<T> {
-        #[pallet::weight((T::BlockWeights::get().max_block, DispatchClass::Operational))]
+        /// Sets the members, which have to include all the reserved members.
+        #[pallet::weight((T::WeightInfo::change_members(members.len() as u32), DispatchClass::Operational))]
         pub fn change_members(origin: OriginFor<T>, members: Vec<T::AccountId>) -> DispatchResult {
             ensure_root(origin)?;
-            Members::<T>::put(members.clone());
+            let bounded_members = Self::bounded_members(members.clone())?;
+            ensure!(
+                ReservedMembers::<T>::get()
+                    .iter()
+                    .all(|reserved| bounded_members.contains(reserved)),
+                Error::<T>::ReservedMemberNotInMembers
+            );
+            Members::<T>::put(bounded_members);
             Self::deposit_event(Event::ChangeMembers(members));
 
             Ok(())
         }
 
-        #[pallet::weight((T::BlockWeights::get().max_block, DispatchClass::Operational))]
+        /// Sets the size of the committee, between 1 and `MaxMembers`.
+        #[pallet::weight((T::WeightInfo::set_members_per_session(), DispatchClass::Operational))]
         pub fn set_members_per_session(
             origin: OriginFor<T>,
             members_per_session: u32,
         ) -> DispatchResult {
             ensure_root(origin)?;
+            ensure!(members_per_session > 0, Error::<T>::ZeroMembersPerSession);
+            ensure!(
+                members_per_session <= T::MaxMembers::get(),
+                Error::<T>::TooManyMembers
+            );
             MembersPerSession::<T>::put(members_per_session);
+            Self::deposit_event(Event::MembersPerSessionChanged(members_per_session));
 
             Ok(())
         }
 
-        #[pallet::weight((T::BlockWeights::get().max_block, DispatchClass::Operational))]
+        /// Sets the reserved members, which have to be members.
+        #[pallet::weight((T::WeightInfo::change_reserved_members(members.len() as u32), DispatchClass::Operational))]
         pub fn change_reserved_members(
             origin: OriginFor<T>,
             members: Vec<T::AccountId>,
         ) -> DispatchResult {
             ensure_root(origin)?;
-            ReservedMembers::<T>::put(members);
+            let reserved = Self::bounded_members(members.clone())?;
+            let all_members = Members::<T>::get();
+            ensure!(
+                reserved.iter().all(|member| all_members.contains(member)),
+                Error::<T>::ReservedMemberNotInMembers
+            );
+            ReservedMembers::<T>::put(reserved);
+            Self::deposit_event(Event::ReservedChanged(members));
+
+            Ok(())
+        }
+
+        /// Sets the mode in which the validators are elected. It takes effect at the next
+        /// election, i.e. for the next era that is not planned yet.
+        #[pallet::weight((T::WeightInfo::set_election_mode(), DispatchClass::Operational))]
+        pub fn set_election_mode(origin: OriginFor<T>, mode: ElectionMode) -> DispatchResult {
+            ensure_root(origin)?;
+            CurrentElectionMode::<T>::put(mode);
+            Self::deposit_event(Event::ElectionModeChanged(mode));
+
+            Ok(())
+        }
+
+        /// Sets the mode in which the committee is chosen. It takes effect for the first session
+        /// whose committee is not chosen yet, i.e. the session after the next one.
+        #[pallet::weight((T::WeightInfo::set_rotation_mode(), DispatchClass::Operational))]
+        pub fn set_rotation_mode(origin: OriginFor<T>, mode: RotationMode) -> DispatchResult {
+            ensure_root(origin)?;
+            CurrentRotationMode::<T>::put(mode);
+            Self::deposit_event(Event::RotationModeChanged(mode));
+
+            Ok(())
+        }
+
+        /// Sets the ratio of created to expected blocks below which validators are benched, and
+        /// for how many eras they are benched. It applies from the evaluation of the current era.
+        #[pallet::weight((T::WeightInfo::set_underperformance_thresholds(), DispatchClass::Operational))]
+        pub fn set_underperformance_thresholds(
+            origin: OriginFor<T>,
+            threshold: Perbill,
+            bench_eras: EraIndex,
+        ) -> DispatchResult {
+            ensure_root(origin)?;
+            UnderperformanceThreshold::<T>::put(threshold);
+            UnderperformanceBenchEras::<T>::put(bench_eras);
 
             Ok(())
         }
+
+        /// Rewards the members of the committee of the last ended session that took part in its
+        /// finalization. Only allowed as an inherent. Every block of the following session can
+        /// report the members whose participation was not proven yet.
+        #[pallet::weight((
+            T::WeightInfo::note_finality_participation(participation.participants.len() as u32),
+            DispatchClass::Mandatory
+        ))]
+        pub fn note_finality_participation(
+            origin: OriginFor<T>,
+            participation: FinalityParticipation,
+        ) -> DispatchResult {
+            ensure_none(origin)?;
+            let mut pending = PendingFinalityParticipation::<T>::get().ok_or(
+                DispatchError::Other("No finality participation report is expected"),
+            )?;
+            ensure!(
+                participation.session == pending.session,
+                DispatchError::Other("Finality participation report for a wrong session")
+            );
+            ensure!(
+                participation.participants.len() <= T::MaxMembers::get() as usize,
+                DispatchError::Other("Finality participation report with too many participants")
+            );
+
+            Self::reward_finality_participation(&mut pending, participation.participants);
+            PendingFinalityParticipation::<T>::put(pending);
+
+            Ok(())
+        }
+    }
+
+    #[pallet::inherent]
+    impl<T: Config> ProvideInherent for Pallet<T> {
+        type Call = Call<T>;
+        type Error = MakeFatalError<()>;
+        const INHERENT_IDENTIFIER: InherentIdentifier = FINALITY_PARTICIPATION_INHERENT_IDENTIFIER;
+
+        fn create_inherent(data: &InherentData) -> Option<Self::Call> {
+            let mut participation: FinalityParticipation =
+                data.get_data(&Self::INHERENT_IDENTIFIER).ok().flatten()?;
+            let pending = PendingFinalityParticipation::<T>::get()?;
+            if participation.session != pending.session {
+                return None;
+            }
+            // Only the members whose participation is not proven yet are reported.
+            participation
+                .participants
+                .retain(|proof| !pending.reported.contains(&proof.member));
+            participation
+                .participants
+                .truncate(T::MaxMembers::get() as usize);
+            if participation.participants.is_empty() {
+                return None;
+            }
+            Some(Call::note_finality_participation { participation })
+        }
+
+        fn is_inherent(call: &Self::Call) -> bool {
+            matches!(call, Call::note_finality_participation { .. })
+        }
     }
 
     #[pallet::genesis_config]
@@ -164,30 +527,66 @@ pub mod pallet {
     #[pallet::genesis_build]
     impl<T: Config> GenesisBuild<T> for GenesisConfig<T> {
         fn build(&self) {
-            <Members<T>>::put(&self.members);
+            let members: BoundedVec<_, _> = self
+                .members
+                .clone()
+                .try_into()
+                .expect("Too many members in the genesis config");
+            let reserved_members: BoundedVec<_, _> = self
+                .reserved_members
+                .clone()
+                .try_into()
+                .expect("Too many reserved members in the genesis config");
+            assert!(
+                self.members_per_session <= T::MaxMembers::get(),
+                "Too many members per session in the genesis config"
+            );
+            <Members<T>>::put(members);
             <MembersPerSession<T>>::put(&self.members_per_session);
-            <ReservedMembers<T>>::put(&self.reserved_members);
+            <ReservedMembers<T>>::put(reserved_members);
         }
     }
 
-    impl<T: Config> Pallet<T> {}
+    impl<T: Config> Pallet<T> {
+        fn bounded_members(
+            members: Vec<T::AccountId>,
+        ) -> Result<BoundedVec<T::AccountId, T::MaxMembers>, Error<T>> {
+            let mut unique = members.clone();
+            unique.sort();
+            unique.dedup();
+            ensure!(unique.len() == members.len(), Error::<T>::DuplicatedMember);
+
+            members.try_into().map_err(|_| Error::<T>::TooManyMembers)
+        }
+    }
 
     #[derive(Debug)]
-    pub enum Error {
+    pub enum ElectionError {
         DataProvider(&'static str),
     }
 
     impl<T: Config> ElectionProvider for Pallet<T> {
         type AccountId = T::AccountId;
         type BlockNumber = T::BlockNumber;
-        type Error = Error;
+        type Error = ElectionError;
         type DataProvider = T::DataProvider;
 
-        // The elections are PoA so only the nodes listed in the Members will be elected as validators.
-        // We calculate the supports for them for the sake of eras payouts.
+        // In the PoA mode only the nodes listed in the Members will be elected as validators, in the
+        // DPoS mode the candidates with the highest backed stake are. We calculate the supports for
+        // them for the sake of eras payouts.
         fn elect() -> Result<Supports<T::AccountId>, Self::Error> {
-            let voters = Self::DataProvider::electing_voters(None).map_err(Error::DataProvider)?;
-            let members = Pallet::<T>::members();
+            let voters =
+                Self::DataProvider::electing_voters(None).map_err(ElectionError::DataProvider)?;
+            let members = match Pallet::<T>::election_mode() {
+                ElectionMode::ProofOfAuthority => Pallet::<T>::members().into_inner(),
+                ElectionMode::DelegatedProofOfStake => {
+                    let mut backing = BTreeMap::new();
+                    for (_, vote, targets) in voters.iter() {
+                        *backing.entry(targets[0].clone()).or_insert(0) += *vote as u128;
+                    }
+                    Pallet::<T>::members_by_stake(backing)?
+                }
+            };
             let mut supports: BTreeMap<_, _> = members
                 .iter()
                 .map(|id| {
@@ -0,0 +1,85 @@
+use crate::{
+    Config, ErasReserved, Members, ReservedMembers, ValidatorEraTotalReward, ValidatorTotalRewards,
+};
+use frame_support::{
+    log,
+    storage::bounded_btree_map::BoundedBTreeMap,
+    traits::{Get, PalletInfoAccess, StorageVersion},
+    weights::Weight,
+    BoundedVec,
+};
+use sp_std::{collections::btree_map::BTreeMap, vec::Vec};
+
+// The bounded types are encoded the same way as the unbounded ones, the lists only have to be cut
+// down to `MaxMembers` accounts. Duplicated accounts are dropped as well, as the calls no longer
+// accept them.
+fn bound<T: Config>(
+    name: &str,
+    accounts: Vec<T::AccountId>,
+) -> BoundedVec<T::AccountId, T::MaxMembers> {
+    let mut unique = Vec::new();
+    for account in accounts {
+        if !unique.contains(&account) {
+            unique.push(account);
+        }
+    }
+    let max_members = T::MaxMembers::get() as usize;
+    if unique.len() > max_members {
+        log::warn!(target: "pallet_elections", "Storage item {} has more than {} accounts, truncating!", name, max_members);
+        unique.truncate(max_members);
+    }
+    unique
+        .try_into()
+        .expect("the list was truncated to MaxMembers")
+}
+
+pub fn migrate<T: Config, P: PalletInfoAccess>() -> Weight {
+    let mut writes = 0;
+    let mut reads = 0;
+    log::info!(target: "pallet_elections", "Running migration from STORAGE_VERSION 0 to 1");
+
+    let members = |name: &'static str| {
+        move |accounts: Option<Vec<T::AccountId>>| {
+            accounts.map(|accounts| bound::<T>(name, accounts))
+        }
+    };
+    for result in [
+        Members::<T>::translate(members("Members")),
+        ReservedMembers::<T>::translate(members("ReservedMembers")),
+        ErasReserved::<T>::translate(members("ErasReserved")),
+    ] {
+        reads += 1;
+        writes += 1;
+        if result.is_err() {
+            log::error!(target: "pallet_elections", "Could not decode a list of members!");
+        }
+    }
+
+    // The totals are computed anew at the start of every era, so the ones over the bound are
+    // dropped.
+    let totals = ValidatorEraTotalReward::<T>::translate(
+        |totals: Option<BTreeMap<T::AccountId, u32>>| {
+            totals.map(|totals| {
+                let mut bounded = BoundedBTreeMap::new();
+                for (validator, total) in totals {
+                    if bounded.try_insert(validator, total).is_err() {
+                        log::warn!(target: "pallet_elections", "Storage item ValidatorEraTotalReward has more than MaxMembers validators, truncating!");
+                        break;
+                    }
+                }
+                ValidatorTotalRewards(bounded)
+            })
+        },
+    );
+    reads += 1;
+    writes += 1;
+    if totals.is_err() {
+        log::error!(target: "pallet_elections", "Could not decode ValidatorEraTotalReward!");
+    }
+
+    // store new version
+    StorageVersion::new(1).put::<P>();
+    writes += 1;
+
+    T::DbWeight::get().reads(reads) + T::DbWeight::get().writes(writes)
+}
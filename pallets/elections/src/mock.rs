@@ -3,18 +3,26 @@
 use super::*;
 use crate as pallet_elections;
 
-use crate::traits::{EraInfoProvider, SessionInfoProvider, ValidatorRewardsHandler};
+use crate::traits::{
+    EraInfoProvider, FinalityParticipationVerifier, SessionInfoProvider, SessionPeriodProvider,
+    ValidatorBondProvider, ValidatorRewardsHandler,
+};
 use frame_election_provider_support::{data_provider, ElectionDataProvider, VoteWeight};
 use frame_support::{
-    construct_runtime, parameter_types, sp_io, traits::GenesisBuild, weights::RuntimeDbWeight,
+    construct_runtime, parameter_types, sp_io,
+    traits::{GenesisBuild, Randomness},
+    weights::RuntimeDbWeight,
     BoundedVec,
 };
-use sp_core::H256;
+use primitives::{AuthoritySignature, ParticipationProof};
+use sp_core::{ed25519, H256};
 use sp_runtime::{
     testing::{Header, TestXt},
     traits::IdentityLookup,
+    Perbill,
 };
 use sp_staking::{EraIndex, SessionIndex};
+use std::{cell::RefCell, collections::BTreeMap};
 
 type UncheckedExtrinsic = frame_system::mocking::MockUncheckedExtrinsic<Test>;
 type Block = frame_system::mocking::MockBlock<Test>;
@@ -95,24 +103,50 @@ where
 }
 
 parameter_types! {
-    pub const SessionPeriod: u32 = 5;
+    pub const PerformanceHistoryDepth: EraIndex = 2;
+    pub const FinalityRewardRatio: Perbill = Perbill::from_percent(50);
+    pub const MaxMembers: u32 = 100;
 }
 
 pub struct MockProvider;
 
 impl SessionInfoProvider<Test> for MockProvider {
     fn current_session_index() -> SessionIndex {
-        todo!()
+        0
     }
 
     fn current_committee() -> Vec<<Test as frame_system::Config>::AccountId> {
-        todo!()
+        COMMITTEES.with(|c| c.borrow().0.clone())
+    }
+
+    fn next_committee() -> Vec<<Test as frame_system::Config>::AccountId> {
+        COMMITTEES.with(|c| c.borrow().1.clone())
+    }
+}
+
+impl SessionPeriodProvider for MockProvider {
+    fn session_period(_session: SessionIndex) -> u32 {
+        5
+    }
+}
+
+impl FinalityParticipationVerifier<Test> for MockProvider {
+    fn participant(_session: SessionIndex, proof: &ParticipationProof) -> Option<AccountId> {
+        if proof.signature != signature_of(proof.member) {
+            return None;
+        }
+        FINALITY_AUTHORITIES.with(|a| a.borrow().get(proof.member as usize).cloned())
+    }
+
+    #[cfg(feature = "runtime-benchmarks")]
+    fn benchmark_proofs(_session: SessionIndex, n: u32) -> Vec<ParticipationProof> {
+        (0..n).map(participation_proof).collect()
     }
 }
 
 impl ValidatorRewardsHandler<Test> for MockProvider {
     fn all_era_validators(_era: EraIndex) -> Vec<<Test as frame_system::Config>::AccountId> {
-        todo!()
+        ERA_VALIDATORS.with(|v| v.borrow().clone())
     }
 
     fn validator_totals(_era: EraIndex) -> Vec<(<Test as frame_system::Config>::AccountId, u128)> {
@@ -120,23 +154,60 @@ impl ValidatorRewardsHandler<Test> for MockProvider {
     }
 
     fn add_rewards(
-        _rewards: impl IntoIterator<Item = (<Test as frame_system::Config>::AccountId, u32)>,
+        rewards: impl IntoIterator<Item = (<Test as frame_system::Config>::AccountId, u32)>,
     ) {
-        todo!()
+        REWARDS.with(|r| r.borrow_mut().extend(rewards));
+    }
+
+    fn add_era_rewards(
+        era: EraIndex,
+        rewards: impl IntoIterator<Item = (<Test as frame_system::Config>::AccountId, u32)>,
+    ) {
+        ERA_REWARDS.with(|r| {
+            r.borrow_mut()
+                .extend(rewards.into_iter().map(|(v, reward)| (era, v, reward)))
+        });
+    }
+
+    fn era_reward_points(_era: EraIndex) -> Vec<(<Test as frame_system::Config>::AccountId, u32)> {
+        REWARDS.with(|r| r.borrow().clone())
     }
 }
 
 impl EraInfoProvider for MockProvider {
     fn active_era() -> Option<EraIndex> {
-        todo!()
+        ACTIVE_ERA.with(|e| *e.borrow())
     }
 
-    fn era_start_session_index(_era: EraIndex) -> Option<SessionIndex> {
-        todo!()
+    fn era_start_session_index(era: EraIndex) -> Option<SessionIndex> {
+        ERA_START_SESSIONS.with(|s| s.borrow().get(&era).copied())
     }
 
     fn sessions_per_era() -> SessionIndex {
-        todo!()
+        SESSIONS_PER_ERA
+    }
+}
+
+impl ValidatorBondProvider<Test> for MockProvider {
+    fn min_validator_bond() -> u128 {
+        MIN_VALIDATOR_BOND
+    }
+
+    fn active_bond(stash: &AccountId) -> Option<u128> {
+        CANDIDATES.with(|c| {
+            c.borrow()
+                .iter()
+                .find(|(candidate, _)| candidate == stash)
+                .map(|(_, bond)| *bond)
+        })
+    }
+}
+
+pub struct TestRandomness;
+
+impl Randomness<H256, u64> for TestRandomness {
+    fn random(subject: &[u8]) -> (H256, u64) {
+        (H256::from(sp_core::hashing::blake2_256(subject)), 0)
     }
 }
 
@@ -144,32 +215,139 @@ impl Config for Test {
     type EraInfoProvider = MockProvider;
     type Event = Event;
     type DataProvider = StakingMock;
-    type SessionPeriod = SessionPeriod;
+    type SessionPeriodProvider = MockProvider;
     type SessionManager = ();
     type SessionInfoProvider = MockProvider;
     type ValidatorRewardsHandler = MockProvider;
+    type ValidatorBondProvider = MockProvider;
+    type Randomness = TestRandomness;
+    type PerformanceHistoryDepth = PerformanceHistoryDepth;
+    type FinalityRewardRatio = FinalityRewardRatio;
+    type FinalityParticipationVerifier = MockProvider;
+    type MaxMembers = MaxMembers;
+    type WeightInfo = ();
+}
+
+type MaxVotesPerVoter = frame_support::traits::ConstU32<1>;
+type AccountIdBoundedVec = BoundedVec<AccountId, MaxVotesPerVoter>;
+
+pub(crate) const MIN_VALIDATOR_BOND: u128 = 100;
+pub(crate) const SESSIONS_PER_ERA: SessionIndex = 5;
+
+thread_local! {
+    static ACTIVE_ERA: RefCell<Option<EraIndex>> = RefCell::new(None);
+    static REWARDS: RefCell<Vec<(AccountId, u32)>> = RefCell::new(Vec::new());
+    static ERA_REWARDS: RefCell<Vec<(EraIndex, AccountId, u32)>> = RefCell::new(Vec::new());
+    static CANDIDATES: RefCell<Vec<(AccountId, u128)>> = RefCell::new(Vec::new());
+    static VOTERS: RefCell<Vec<(AccountId, VoteWeight, AccountId)>> = RefCell::new(Vec::new());
+    static DESIRED_TARGETS: RefCell<u32> = RefCell::new(0);
+    static COMMITTEES: RefCell<(Vec<AccountId>, Vec<AccountId>)> = RefCell::new((Vec::new(), Vec::new()));
+    static ERA_VALIDATORS: RefCell<Vec<AccountId>> = RefCell::new(Vec::new());
+    static FINALITY_AUTHORITIES: RefCell<Vec<AccountId>> = RefCell::new(Vec::new());
+    static ERA_START_SESSIONS: RefCell<BTreeMap<EraIndex, SessionIndex>> = RefCell::new(BTreeMap::new());
+}
+
+pub fn set_active_era(era: Option<EraIndex>) {
+    ACTIVE_ERA.with(|e| *e.borrow_mut() = era);
+}
+
+/// Returns the rewards added since the last call.
+pub fn take_rewards() -> Vec<(AccountId, u32)> {
+    REWARDS.with(|r| r.take())
+}
+
+/// Returns the rewards added to specific eras since the last call, as `(era, validator, reward)`.
+pub fn take_era_rewards() -> Vec<(EraIndex, AccountId, u32)> {
+    ERA_REWARDS.with(|r| r.take())
+}
+
+/// Sets the committees of the current and of the next session.
+pub fn set_committees(current: Vec<AccountId>, next: Vec<AccountId>) {
+    COMMITTEES.with(|c| *c.borrow_mut() = (current, next));
+}
+
+pub fn set_era_validators(validators: Vec<AccountId>) {
+    ERA_VALIDATORS.with(|v| *v.borrow_mut() = validators);
 }
 
-type AccountIdBoundedVec = BoundedVec<AccountId, ()>;
+pub fn set_era_start_session(era: EraIndex, session: SessionIndex) {
+    ERA_START_SESSIONS.with(|s| s.borrow_mut().insert(era, session));
+}
+
+/// Sets the owners of the finality authority keys of the reported session, in the order of the
+/// authorities.
+pub fn set_finality_authorities(authorities: Vec<AccountId>) {
+    FINALITY_AUTHORITIES.with(|a| *a.borrow_mut() = authorities);
+}
+
+// The only signature `MockProvider` accepts from the member.
+fn signature_of(member: u32) -> AuthoritySignature {
+    ed25519::Signature::from_raw([member as u8; 64]).into()
+}
+
+/// A proof of participation of the member that `MockProvider` accepts.
+pub fn participation_proof(member: u32) -> ParticipationProof {
+    ParticipationProof {
+        member,
+        block: 0,
+        signature: signature_of(member),
+    }
+}
+
+/// A proof of participation of the member with a wrong signature.
+pub fn invalid_participation_proof(member: u32) -> ParticipationProof {
+    ParticipationProof {
+        signature: signature_of(member + 1),
+        ..participation_proof(member)
+    }
+}
+
+pub fn bounded(accounts: Vec<AccountId>) -> BoundedVec<AccountId, MaxMembers> {
+    accounts
+        .try_into()
+        .expect("the test lists fit in MaxMembers")
+}
+
+/// Sets the candidates with their bonds, the votes as `(voter, weight, target)` and the number of
+/// validators to elect, as reported by `StakingMock`.
+pub fn set_staking_data(
+    candidates: Vec<(AccountId, u128)>,
+    voters: Vec<(AccountId, VoteWeight, AccountId)>,
+    desired_targets: u32,
+) {
+    CANDIDATES.with(|c| *c.borrow_mut() = candidates);
+    VOTERS.with(|v| *v.borrow_mut() = voters);
+    DESIRED_TARGETS.with(|d| *d.borrow_mut() = desired_targets);
+}
 
 pub struct StakingMock;
 impl ElectionDataProvider for StakingMock {
     type AccountId = AccountId;
     type BlockNumber = u64;
-    type MaxVotesPerVoter = ();
+    type MaxVotesPerVoter = MaxVotesPerVoter;
 
     fn electable_targets(_maybe_max_len: Option<usize>) -> data_provider::Result<Vec<AccountId>> {
-        Ok(Vec::new())
+        Ok(CANDIDATES.with(|c| c.borrow().iter().map(|(candidate, _)| *candidate).collect()))
     }
 
     fn electing_voters(
         _maybe_max_len: Option<usize>,
     ) -> data_provider::Result<Vec<(AccountId, VoteWeight, AccountIdBoundedVec)>> {
-        Ok(Vec::new())
+        Ok(VOTERS.with(|v| {
+            v.borrow()
+                .iter()
+                .map(|(voter, weight, target)| {
+                    let targets = vec![*target]
+                        .try_into()
+                        .expect("a single target fits in the bound");
+                    (*voter, *weight, targets)
+                })
+                .collect()
+        }))
     }
 
     fn desired_targets() -> data_provider::Result<u32> {
-        Ok(0)
+        Ok(DESIRED_TARGETS.with(|d| *d.borrow()))
     }
 
     fn next_election_prediction(_now: u64) -> u64 {
@@ -1,8 +1,29 @@
 #![cfg(test)]
 
-use frame_election_provider_support::{ElectionProvider, Support};
+use frame_election_provider_support::{sp_arithmetic::Perbill, ElectionProvider, Support};
+use frame_support::{
+    assert_noop, assert_ok,
+    inherent::{InherentData, ProvideInherent},
+    storage::unhashed,
+    traits::{Get, GetStorageVersion, StorageVersion},
+    BoundedVec,
+};
+use pallet_session::SessionManager;
+use primitives::{
+    EraValidators, FinalityParticipation, FINALITY_PARTICIPATION_INHERENT_IDENTIFIER,
+};
+use sp_runtime::DispatchError;
+use sp_staking::EraIndex;
+use std::collections::BTreeMap;
 
-use crate::mock::*;
+use crate::{
+    migrations, mock::*, traits::ValidatorRewardsHandler, BenchedValidators, BlockProduction, Call,
+    CurrentElectionMode, ElectionMode, ErasReserved, ErasValidatorPerformance, Error, Members,
+    MembersPerSession, NextEraReserved, PendingFinalityParticipation, PendingFinalityRewards,
+    ReservedMembers, RotationMode, SessionValidatorBlockCount, UnderperformanceBenchEras,
+    UnderperformanceThreshold, ValidatorEraBlockCount, ValidatorEraTotalReward,
+    ValidatorTotalRewards,
+};
 
 #[test]
 fn test_elect() {
@@ -18,3 +39,630 @@ fn test_elect() {
         assert_eq!(elected.unwrap(), &[(1, supp.clone()), (2, supp)]);
     });
 }
+
+#[test]
+fn test_elect_by_stake() {
+    new_test_ext(vec![1, 2], vec![]).execute_with(|| {
+        CurrentElectionMode::<Test>::put(ElectionMode::DelegatedProofOfStake);
+        set_staking_data(
+            vec![(3, MIN_VALIDATOR_BOND), (4, MIN_VALIDATOR_BOND), (5, 50)],
+            vec![
+                (3, 100, 3),
+                (4, 100, 4),
+                (5, 50, 5),
+                (10, 300, 5),
+                (11, 20, 3),
+            ],
+            1,
+        );
+
+        // 5 has the most backing, but its bond is too small.
+        let elected = <Elections as ElectionProvider>::elect().unwrap();
+        assert_eq!(
+            elected,
+            &[(
+                3,
+                Support {
+                    total: 120,
+                    voters: vec![(3, 100), (11, 20)],
+                }
+            )]
+        );
+    });
+}
+
+#[test]
+fn test_elect_by_stake_includes_reserved() {
+    new_test_ext(vec![1, 2], vec![7]).execute_with(|| {
+        CurrentElectionMode::<Test>::put(ElectionMode::DelegatedProofOfStake);
+        set_staking_data(
+            vec![(3, MIN_VALIDATOR_BOND), (4, MIN_VALIDATOR_BOND)],
+            vec![(3, 100, 3), (4, 200, 4)],
+            2,
+        );
+
+        let elected: Vec<_> = <Elections as ElectionProvider>::elect()
+            .unwrap()
+            .into_iter()
+            .map(|(validator, _)| validator)
+            .collect();
+        assert_eq!(elected, vec![4, 7]);
+    });
+}
+
+#[test]
+fn only_root_can_set_election_mode() {
+    new_test_ext(vec![1, 2], vec![]).execute_with(|| {
+        assert_noop!(
+            Elections::set_election_mode(Origin::signed(1), ElectionMode::DelegatedProofOfStake),
+            DispatchError::BadOrigin
+        );
+        assert_eq!(Elections::election_mode(), ElectionMode::ProofOfAuthority);
+
+        assert_ok!(Elections::set_election_mode(
+            Origin::root(),
+            ElectionMode::DelegatedProofOfStake
+        ));
+        assert_eq!(
+            Elections::election_mode(),
+            ElectionMode::DelegatedProofOfStake
+        );
+    });
+}
+
+#[test]
+fn only_root_can_set_rotation_mode() {
+    new_test_ext(vec![1, 2], vec![]).execute_with(|| {
+        assert_noop!(
+            Elections::set_rotation_mode(Origin::signed(1), RotationMode::Random),
+            DispatchError::BadOrigin
+        );
+        assert_eq!(Elections::rotation_mode(), RotationMode::Sequential);
+
+        assert_ok!(Elections::set_rotation_mode(
+            Origin::root(),
+            RotationMode::Random
+        ));
+        assert_eq!(Elections::rotation_mode(), RotationMode::Random);
+    });
+}
+
+fn produced(created: u32, expected: u32) -> BlockProduction {
+    BlockProduction { created, expected }
+}
+
+#[test]
+fn underperforming_validators_are_benched() {
+    new_test_ext(vec![1, 2, 3], vec![]).execute_with(|| {
+        System::set_block_number(1);
+        UnderperformanceThreshold::<Test>::put(Perbill::from_percent(50));
+        UnderperformanceBenchEras::<Test>::put(2);
+        ErasReserved::<Test>::put(bounded(vec![3]));
+        ValidatorEraBlockCount::<Test>::insert(1, produced(2, 10));
+        ValidatorEraBlockCount::<Test>::insert(2, produced(5, 10));
+        ValidatorEraBlockCount::<Test>::insert(3, produced(0, 10));
+
+        Elections::evaluate_era_performance(3);
+
+        assert_eq!(Elections::benched_until(1), Some(5));
+        assert_eq!(Elections::benched_until(2), None);
+        assert_eq!(Elections::benched_until(3), None);
+        assert_eq!(
+            Elections::era_validator_performance(2, 1),
+            Some(Perbill::from_percent(20))
+        );
+        assert_eq!(
+            Elections::era_validator_performance(2, 3),
+            Some(Perbill::zero())
+        );
+        assert_eq!(ValidatorEraBlockCount::<Test>::iter().count(), 0);
+        System::assert_has_event(Event::Elections(crate::Event::ValidatorUnderperformed(
+            1,
+            Perbill::from_percent(20),
+        )));
+    });
+}
+
+#[test]
+fn benches_expire() {
+    new_test_ext(vec![1, 2], vec![]).execute_with(|| {
+        BenchedValidators::<Test>::insert(1, 3);
+        BenchedValidators::<Test>::insert(2, 4);
+
+        Elections::evaluate_era_performance(3);
+
+        assert_eq!(Elections::benched_until(1), None);
+        assert_eq!(Elections::benched_until(2), Some(4));
+    });
+}
+
+#[test]
+fn performance_history_is_pruned() {
+    new_test_ext(vec![1], vec![]).execute_with(|| {
+        ErasValidatorPerformance::<Test>::insert(0, 1, Perbill::one());
+        ErasValidatorPerformance::<Test>::insert(1, 1, Perbill::one());
+        ValidatorEraBlockCount::<Test>::insert(1, produced(10, 10));
+
+        Elections::evaluate_era_performance(3);
+
+        assert_eq!(Elections::era_validator_performance(0, 1), None);
+        assert_eq!(
+            Elections::era_validator_performance(1, 1),
+            Some(Perbill::one())
+        );
+        assert_eq!(
+            Elections::era_validator_performance(2, 1),
+            Some(Perbill::one())
+        );
+    });
+}
+
+#[test]
+fn only_root_can_set_underperformance_thresholds() {
+    new_test_ext(vec![1, 2], vec![]).execute_with(|| {
+        assert_noop!(
+            Elections::set_underperformance_thresholds(
+                Origin::signed(1),
+                Perbill::from_percent(50),
+                2
+            ),
+            DispatchError::BadOrigin
+        );
+
+        assert_ok!(Elections::set_underperformance_thresholds(
+            Origin::root(),
+            Perbill::from_percent(50),
+            2
+        ));
+        assert_eq!(
+            UnderperformanceThreshold::<Test>::get(),
+            Perbill::from_percent(50)
+        );
+        assert_eq!(UnderperformanceBenchEras::<Test>::get(), 2);
+    });
+}
+
+fn participation(session: u32, members: Vec<u32>) -> FinalityParticipation {
+    FinalityParticipation {
+        session,
+        participants: members.into_iter().map(participation_proof).collect(),
+    }
+}
+
+fn pending_rewards(
+    session: u32,
+    era: EraIndex,
+    rewards: Vec<(AccountId, u32)>,
+) -> PendingFinalityRewards<AccountId, MaxMembers> {
+    PendingFinalityRewards {
+        session,
+        era,
+        rewards: rewards.try_into().unwrap(),
+        reported: BoundedVec::default(),
+    }
+}
+
+#[test]
+fn finality_rewards_are_fixed_when_session_ends() {
+    new_test_ext(vec![1, 2], vec![]).execute_with(|| {
+        set_active_era(Some(1));
+        set_committees(vec![1, 2], vec![]);
+        ValidatorEraTotalReward::<Test>::put(ValidatorTotalRewards(
+            [(1, 1_000), (2, 1_000)]
+                .into_iter()
+                .collect::<BTreeMap<_, _>>()
+                .try_into()
+                .unwrap(),
+        ));
+
+        <Elections as SessionManager<AccountId>>::end_session(4);
+
+        // A full session is worth 1_000 / SESSIONS_PER_ERA, half of it is paid for finality.
+        assert_eq!(
+            PendingFinalityParticipation::<Test>::get(),
+            Some(pending_rewards(4, 1, vec![(1, 100), (2, 100)]))
+        );
+    });
+}
+
+#[test]
+fn finality_participants_are_rewarded_in_era_of_session() {
+    new_test_ext(vec![1, 2], vec![]).execute_with(|| {
+        // The last session of the era 1 is reported in the era 2.
+        set_active_era(Some(2));
+        ValidatorEraTotalReward::<Test>::put(ValidatorTotalRewards(
+            [(1, 5_000), (2, 5_000)]
+                .into_iter()
+                .collect::<BTreeMap<_, _>>()
+                .try_into()
+                .unwrap(),
+        ));
+        PendingFinalityParticipation::<Test>::put(pending_rewards(4, 1, vec![(1, 100), (2, 100)]));
+        // The authorities are ordered differently than the committee.
+        set_finality_authorities(vec![2, 1]);
+
+        assert_ok!(Elections::note_finality_participation(
+            Origin::none(),
+            participation(4, vec![1])
+        ));
+
+        assert_eq!(take_era_rewards(), vec![(1, 1, 100)]);
+        assert_eq!(take_rewards(), vec![]);
+        let pending = PendingFinalityParticipation::<Test>::get().unwrap();
+        assert_eq!(pending.rewards.into_inner(), vec![(2, 100)]);
+        assert_eq!(pending.reported.into_inner(), vec![1]);
+    });
+}
+
+#[test]
+fn unproven_finality_participants_are_not_rewarded() {
+    new_test_ext(vec![1, 2, 3], vec![]).execute_with(|| {
+        set_active_era(Some(1));
+        PendingFinalityParticipation::<Test>::put(pending_rewards(4, 1, vec![(1, 100), (2, 100)]));
+        // The owner of the third key is not in the committee.
+        set_finality_authorities(vec![1, 2, 3]);
+
+        let report = FinalityParticipation {
+            session: 4,
+            participants: vec![
+                participation_proof(0),
+                participation_proof(0),
+                invalid_participation_proof(1),
+                participation_proof(2),
+                participation_proof(7),
+            ],
+        };
+        assert_ok!(Elections::note_finality_participation(
+            Origin::none(),
+            report
+        ));
+
+        assert_eq!(take_era_rewards(), vec![(1, 1, 100)]);
+    });
+}
+
+#[test]
+fn participants_left_out_of_report_are_rewarded_by_later_reports() {
+    new_test_ext(vec![1, 2, 3], vec![]).execute_with(|| {
+        set_active_era(Some(1));
+        set_committees(vec![1, 2, 3], vec![]);
+        PendingFinalityParticipation::<Test>::put(pending_rewards(
+            4,
+            1,
+            vec![(1, 100), (2, 100), (3, 100)],
+        ));
+        set_finality_authorities(vec![1, 2, 3]);
+
+        // The author reports only itself.
+        assert_ok!(Elections::note_finality_participation(
+            Origin::none(),
+            participation(4, vec![0])
+        ));
+        assert_eq!(take_era_rewards(), vec![(1, 1, 100)]);
+
+        // The next author reports more members, only the ones not paid yet are paid.
+        assert_ok!(Elections::note_finality_participation(
+            Origin::none(),
+            participation(4, vec![0, 1])
+        ));
+        assert_eq!(take_era_rewards(), vec![(1, 2, 100)]);
+
+        // Once the participation was reported, the members never proven are not paid.
+        <Elections as SessionManager<AccountId>>::end_session(5);
+        assert_eq!(take_era_rewards(), vec![]);
+    });
+}
+
+#[test]
+fn whole_committee_is_rewarded_when_participation_is_never_reported() {
+    new_test_ext(vec![1, 2], vec![]).execute_with(|| {
+        set_active_era(Some(2));
+        PendingFinalityParticipation::<Test>::put(pending_rewards(4, 1, vec![(1, 100), (2, 100)]));
+
+        <Elections as SessionManager<AccountId>>::end_session(5);
+
+        assert_eq!(take_era_rewards(), vec![(1, 1, 100), (1, 2, 100)]);
+        assert_eq!(
+            PendingFinalityParticipation::<Test>::get()
+                .map(|pending| (pending.session, pending.era)),
+            Some((5, 2))
+        );
+    });
+}
+
+#[test]
+fn finality_participation_of_other_session_is_rejected() {
+    new_test_ext(vec![1, 2], vec![]).execute_with(|| {
+        set_active_era(Some(1));
+        PendingFinalityParticipation::<Test>::put(pending_rewards(4, 1, vec![(1, 100), (2, 100)]));
+
+        assert_noop!(
+            Elections::note_finality_participation(Origin::none(), participation(3, vec![0])),
+            DispatchError::Other("Finality participation report for a wrong session")
+        );
+        assert_noop!(
+            Elections::note_finality_participation(Origin::signed(1), participation(4, vec![0])),
+            DispatchError::BadOrigin
+        );
+        assert_noop!(
+            Elections::note_finality_participation(
+                Origin::none(),
+                participation(4, (0..=MaxMembers::get()).collect())
+            ),
+            DispatchError::Other("Finality participation report with too many participants")
+        );
+    });
+}
+
+#[test]
+fn finality_participation_inherent_reports_participants_not_proven_yet() {
+    new_test_ext(vec![1, 2], vec![]).execute_with(|| {
+        let mut data = InherentData::new();
+        data.put_data(
+            FINALITY_PARTICIPATION_INHERENT_IDENTIFIER,
+            &participation(4, vec![0, 1]),
+        )
+        .unwrap();
+        assert_eq!(Elections::create_inherent(&data), None);
+
+        PendingFinalityParticipation::<Test>::put(pending_rewards(3, 1, vec![(1, 100)]));
+        assert_eq!(Elections::create_inherent(&data), None);
+
+        let mut pending = pending_rewards(4, 1, vec![(1, 100), (2, 100)]);
+        PendingFinalityParticipation::<Test>::put(pending.clone());
+        assert_eq!(
+            Elections::create_inherent(&data),
+            Some(Call::note_finality_participation {
+                participation: participation(4, vec![0, 1])
+            })
+        );
+
+        pending.reported = vec![0].try_into().unwrap();
+        PendingFinalityParticipation::<Test>::put(pending.clone());
+        assert_eq!(
+            Elections::create_inherent(&data),
+            Some(Call::note_finality_participation {
+                participation: participation(4, vec![1])
+            })
+        );
+
+        pending.reported = vec![0, 1].try_into().unwrap();
+        PendingFinalityParticipation::<Test>::put(pending);
+        assert_eq!(Elections::create_inherent(&data), None);
+    });
+}
+
+#[test]
+fn members_are_validated() {
+    new_test_ext(vec![1, 2], vec![1]).execute_with(|| {
+        assert_noop!(
+            Elections::change_members(Origin::root(), vec![1, 2, 1]),
+            Error::<Test>::DuplicatedMember
+        );
+        assert_noop!(
+            Elections::change_members(Origin::root(), (1..=MaxMembers::get() as u64 + 1).collect()),
+            Error::<Test>::TooManyMembers
+        );
+        assert_noop!(
+            Elections::change_members(Origin::root(), vec![2, 3]),
+            Error::<Test>::ReservedMemberNotInMembers
+        );
+
+        assert_ok!(Elections::change_members(Origin::root(), vec![1, 3]));
+        assert_eq!(Elections::members().into_inner(), vec![1, 3]);
+    });
+}
+
+#[test]
+fn reserved_members_are_validated() {
+    new_test_ext(vec![1, 2], vec![]).execute_with(|| {
+        assert_noop!(
+            Elections::change_reserved_members(Origin::root(), vec![2, 2]),
+            Error::<Test>::DuplicatedMember
+        );
+        assert_noop!(
+            Elections::change_reserved_members(Origin::root(), vec![2, 3]),
+            Error::<Test>::ReservedMemberNotInMembers
+        );
+
+        assert_ok!(Elections::change_reserved_members(Origin::root(), vec![2]));
+        assert_eq!(ReservedMembers::<Test>::get().into_inner(), vec![2]);
+    });
+}
+
+#[test]
+fn members_per_session_is_validated() {
+    new_test_ext(vec![1, 2], vec![]).execute_with(|| {
+        assert_noop!(
+            Elections::set_members_per_session(Origin::root(), 0),
+            Error::<Test>::ZeroMembersPerSession
+        );
+        assert_noop!(
+            Elections::set_members_per_session(Origin::root(), MaxMembers::get() + 1),
+            Error::<Test>::TooManyMembers
+        );
+
+        assert_ok!(Elections::set_members_per_session(Origin::root(), 1));
+        assert_eq!(MembersPerSession::<Test>::get(), 1);
+    });
+}
+
+#[test]
+fn migration_from_v0_to_v1_bounds_members() {
+    new_test_ext(vec![], vec![]).execute_with(|| {
+        let too_many: Vec<AccountId> = (0..MaxMembers::get() as u64 + 10).collect();
+        unhashed::put(&Members::<Test>::hashed_key(), &too_many);
+        unhashed::put(&ReservedMembers::<Test>::hashed_key(), &vec![1u64, 2, 1]);
+
+        migrations::v0_to_v1::migrate::<Test, Elections>();
+
+        assert_eq!(
+            Elections::members().into_inner(),
+            too_many[..MaxMembers::get() as usize].to_vec()
+        );
+        assert_eq!(ReservedMembers::<Test>::get().into_inner(), vec![1, 2]);
+        assert_eq!(
+            <Elections as GetStorageVersion>::on_chain_storage_version(),
+            StorageVersion::new(1)
+        );
+    });
+}
+
+#[test]
+fn reserved_and_committee_size_changes_are_announced() {
+    new_test_ext(vec![1, 2], vec![]).execute_with(|| {
+        System::set_block_number(1);
+
+        assert_ok!(Elections::change_reserved_members(Origin::root(), vec![2]));
+        System::assert_has_event(Event::Elections(crate::Event::ReservedChanged(vec![2])));
+
+        assert_ok!(Elections::set_members_per_session(Origin::root(), 1));
+        System::assert_has_event(Event::Elections(crate::Event::MembersPerSessionChanged(1)));
+    });
+}
+
+#[test]
+fn committee_rotation_is_announced() {
+    new_test_ext(vec![1, 2, 3], vec![]).execute_with(|| {
+        System::set_block_number(1);
+        set_active_era(Some(1));
+        set_era_validators(vec![1, 2, 3]);
+        MembersPerSession::<Test>::put(2);
+
+        let committee = <Elections as SessionManager<AccountId>>::new_session(1);
+
+        assert_eq!(committee, Some(vec![1, 2]));
+        System::assert_has_event(Event::Elections(crate::Event::CommitteeRotated(
+            1,
+            vec![1, 2],
+        )));
+    });
+}
+
+#[test]
+fn committee_has_at_most_max_members() {
+    new_test_ext(vec![1, 2, 3], vec![]).execute_with(|| {
+        let max_members = MaxMembers::get();
+        let validators: Vec<AccountId> = (0..max_members as u64 + 10).collect();
+        set_active_era(Some(1));
+        set_era_validators(validators.clone());
+        MembersPerSession::<Test>::put(max_members + 10);
+
+        let committee = <Elections as SessionManager<AccountId>>::new_session(1)
+            .expect("the committee is rotated after the era 0");
+        assert_eq!(committee.len(), max_members as usize);
+
+        set_committees(committee.clone(), vec![]);
+        <Elections as SessionManager<AccountId>>::end_session(1);
+        let pending = PendingFinalityParticipation::<Test>::get().unwrap();
+        assert_eq!(
+            pending
+                .rewards
+                .into_iter()
+                .map(|(validator, _)| validator)
+                .collect::<Vec<_>>(),
+            committee
+        );
+    });
+}
+
+#[test]
+fn era_validators_are_split_into_reserved_and_the_rest() {
+    new_test_ext(vec![1, 2, 3], vec![]).execute_with(|| {
+        set_active_era(Some(1));
+        set_era_validators(vec![1, 2, 3]);
+        ErasReserved::<Test>::put(bounded(vec![2]));
+
+        assert_eq!(
+            Elections::era_validators(),
+            EraValidators {
+                reserved: vec![2],
+                non_reserved: vec![1, 3],
+            }
+        );
+    });
+}
+
+#[test]
+fn era_validators_keep_reserved_of_active_era_until_next_one_starts() {
+    new_test_ext(vec![1, 2, 3], vec![2]).execute_with(|| {
+        set_active_era(Some(1));
+        set_era_validators(vec![1, 2, 3]);
+        set_era_start_session(2, 5);
+        ErasReserved::<Test>::put(bounded(vec![2]));
+        assert_ok!(Elections::change_reserved_members(Origin::root(), vec![3]));
+
+        <Elections as SessionManager<AccountId>>::new_session(5);
+        assert_eq!(
+            Elections::era_validators(),
+            EraValidators {
+                reserved: vec![2],
+                non_reserved: vec![1, 3],
+            }
+        );
+
+        set_active_era(Some(2));
+        <Elections as SessionManager<AccountId>>::start_session(5);
+        assert_eq!(
+            Elections::era_validators(),
+            EraValidators {
+                reserved: vec![3],
+                non_reserved: vec![1, 2],
+            }
+        );
+        assert_eq!(NextEraReserved::<Test>::get(), None);
+    });
+}
+
+#[test]
+fn benched_validators_keep_non_committee_rewards() {
+    new_test_ext(vec![1, 2, 3], vec![]).execute_with(|| {
+        set_active_era(Some(1));
+        set_era_validators(vec![1, 2, 3]);
+        set_committees(vec![1, 2], vec![]);
+        MembersPerSession::<Test>::put(2);
+        BenchedValidators::<Test>::insert(3, 5);
+        ValidatorEraTotalReward::<Test>::put(ValidatorTotalRewards(
+            [(1, 1_000), (2, 1_000), (3, 1_000)]
+                .into_iter()
+                .collect::<BTreeMap<_, _>>()
+                .try_into()
+                .unwrap(),
+        ));
+
+        // The benched validator is not chosen to the committee, but it is still paid for the
+        // session as a non-committee member.
+        let committee = <Elections as SessionManager<AccountId>>::new_session(2).unwrap();
+        assert!(!committee.contains(&3));
+        assert_eq!(
+            Elections::era_reward_points_preview(),
+            vec![(1, 0), (2, 0), (3, 200)]
+        );
+    });
+}
+
+#[test]
+fn reward_points_preview_includes_current_session() {
+    new_test_ext(vec![1, 2], vec![]).execute_with(|| {
+        set_active_era(Some(1));
+        set_era_validators(vec![1, 2, 3]);
+        set_committees(vec![1, 2], vec![]);
+        ValidatorEraTotalReward::<Test>::put(ValidatorTotalRewards(
+            [(1, 1_000), (2, 1_000), (3, 1_000)]
+                .into_iter()
+                .collect::<BTreeMap<_, _>>()
+                .try_into()
+                .unwrap(),
+        ));
+        SessionValidatorBlockCount::<Test>::insert(1, 2);
+        MockProvider::add_rewards(vec![(2, 50)]);
+
+        // A full session is worth 1_000 / SESSIONS_PER_ERA, half of it is paid to the committee
+        // for block production.
+        assert_eq!(
+            Elections::era_reward_points_preview(),
+            vec![(1, 100), (2, 50), (3, 200)]
+        );
+        assert_eq!(Elections::validator_block_count(1), 2);
+    });
+}
@@ -1,4 +1,6 @@
-use frame_support::{pallet_prelude::Get, traits::Currency};
+use codec::Encode;
+use frame_support::{pallet_prelude::Get, sp_runtime::RuntimeAppPublic, traits::Currency};
+use primitives::{first_block_of_session, AuthorityId, ParticipationProof, KEY_TYPE};
 use sp_staking::{EraIndex, SessionIndex};
 use sp_std::vec::Vec;
 
@@ -7,6 +9,8 @@ pub trait SessionInfoProvider<T: frame_system::Config> {
     fn current_session_index() -> SessionIndex;
     /// Returns list containing validators that in the current session produce&finalize blocks.
     fn current_committee() -> Vec<T::AccountId>;
+    /// Returns list containing validators that will produce&finalize blocks in the next session.
+    fn next_committee() -> Vec<T::AccountId>;
 }
 
 impl<T> SessionInfoProvider<T> for pallet_session::Pallet<T>
@@ -24,6 +28,84 @@ where
             .map(|a| a.into())
             .collect()
     }
+
+    fn next_committee() -> Vec<T::AccountId> {
+        pallet_session::QueuedKeys::<T>::get()
+            .into_iter()
+            .map(|(a, _)| a.into())
+            .collect()
+    }
+}
+
+pub trait SessionPeriodProvider {
+    /// Returns the number of blocks in the `session`.
+    fn session_period(session: SessionIndex) -> u32;
+}
+
+impl<T> SessionPeriodProvider for pallet_aleph::Pallet<T>
+where
+    T: pallet_aleph::Config,
+{
+    fn session_period(session: SessionIndex) -> u32 {
+        pallet_aleph::Pallet::<T>::session_period(session)
+    }
+}
+
+pub trait FinalityParticipationVerifier<T: frame_system::Config> {
+    /// Returns the validator whose participation in the finalization of the `session` is proven,
+    /// if the proof is valid. Members are indexed like the finality authorities of the session.
+    fn participant(session: SessionIndex, proof: &ParticipationProof) -> Option<T::AccountId>;
+
+    /// Sets up `n` finality authorities of the `session` and returns valid proofs of their
+    /// participation.
+    #[cfg(feature = "runtime-benchmarks")]
+    fn benchmark_proofs(session: SessionIndex, n: u32) -> Vec<ParticipationProof>;
+}
+
+impl<T> FinalityParticipationVerifier<T> for pallet_aleph::Pallet<T>
+where
+    T: pallet_aleph::Config<AuthorityId = AuthorityId> + pallet_session::Config,
+    T::ValidatorId: Into<T::AccountId>,
+{
+    fn participant(session: SessionIndex, proof: &ParticipationProof) -> Option<T::AccountId> {
+        let authorities = pallet_aleph::Pallet::<T>::session_authorities(session)?;
+        let authority = authorities.get(proof.member as usize)?;
+        let schedule = pallet_aleph::Pallet::<T>::session_period_schedule();
+        if proof.block < first_block_of_session(&schedule, session)
+            || proof.block >= first_block_of_session(&schedule, session.saturating_add(1))
+        {
+            return None;
+        }
+        // Only the hashes of the recent blocks are kept, older ones are all zeros.
+        let hash = frame_system::Pallet::<T>::block_hash(T::BlockNumber::from(proof.block));
+        if hash == T::Hash::default() || !authority.verify(&hash, &proof.signature) {
+            return None;
+        }
+        pallet_session::KeyOwner::<T>::get((KEY_TYPE, authority.encode())).map(Into::into)
+    }
+
+    #[cfg(feature = "runtime-benchmarks")]
+    fn benchmark_proofs(session: SessionIndex, n: u32) -> Vec<ParticipationProof> {
+        use frame_support::sp_runtime::traits::Hash;
+
+        let schedule = pallet_aleph::Pallet::<T>::session_period_schedule();
+        let block = first_block_of_session(&schedule, session);
+        let hash = T::Hashing::hash_of(&block);
+        frame_system::BlockHash::<T>::insert(T::BlockNumber::from(block), hash);
+        let authorities: Vec<_> = (0..n).map(|_| AuthorityId::generate_pair(None)).collect();
+        pallet_aleph::Pallet::<T>::set_session_authorities(session, &authorities);
+        authorities
+            .iter()
+            .enumerate()
+            .map(|(member, authority)| ParticipationProof {
+                member: member as u32,
+                block,
+                signature: authority
+                    .sign(&hash)
+                    .expect("the key of the authority was just generated"),
+            })
+            .collect()
+    }
 }
 
 pub trait ValidatorRewardsHandler<T: frame_system::Config> {
@@ -33,6 +115,10 @@ pub trait ValidatorRewardsHandler<T: frame_system::Config> {
     fn validator_totals(era: EraIndex) -> Vec<(T::AccountId, u128)>;
     /// Add reward for validators
     fn add_rewards(rewards: impl IntoIterator<Item = (T::AccountId, u32)>);
+    /// Add reward for validators in the `era`, which might already have ended.
+    fn add_era_rewards(era: EraIndex, rewards: impl IntoIterator<Item = (T::AccountId, u32)>);
+    /// Returns the reward points of validators added so far for the `era`.
+    fn era_reward_points(era: EraIndex) -> Vec<(T::AccountId, u32)>;
 }
 
 impl<T> ValidatorRewardsHandler<T> for pallet_staking::Pallet<T>
@@ -53,6 +139,23 @@ where
     fn add_rewards(rewards: impl IntoIterator<Item = (T::AccountId, u32)>) {
         pallet_staking::Pallet::<T>::reward_by_ids(rewards);
     }
+
+    fn add_era_rewards(era: EraIndex, rewards: impl IntoIterator<Item = (T::AccountId, u32)>) {
+        pallet_staking::ErasRewardPoints::<T>::mutate(era, |points| {
+            for (validator, reward) in rewards {
+                let individual = points.individual.entry(validator).or_default();
+                *individual = individual.saturating_add(reward);
+                points.total = points.total.saturating_add(reward);
+            }
+        });
+    }
+
+    fn era_reward_points(era: EraIndex) -> Vec<(T::AccountId, u32)> {
+        pallet_staking::ErasRewardPoints::<T>::get(era)
+            .individual
+            .into_iter()
+            .collect()
+    }
 }
 
 pub trait EraInfoProvider {
@@ -81,3 +184,25 @@ where
         T::SessionsPerEra::get()
     }
 }
+
+pub trait ValidatorBondProvider<T: frame_system::Config> {
+    /// Returns the minimal bond a validator needs to be elected.
+    fn min_validator_bond() -> u128;
+    /// Returns the active bond of the `stash`, or `None` if it is not bonded.
+    fn active_bond(stash: &T::AccountId) -> Option<u128>;
+}
+
+impl<T> ValidatorBondProvider<T> for pallet_staking::Pallet<T>
+where
+    T: pallet_staking::Config,
+    <T::Currency as Currency<T::AccountId>>::Balance: Into<u128>,
+{
+    fn min_validator_bond() -> u128 {
+        pallet_staking::MinValidatorBond::<T>::get().into()
+    }
+
+    fn active_bond(stash: &T::AccountId) -> Option<u128> {
+        let controller = pallet_staking::Bonded::<T>::get(stash)?;
+        pallet_staking::Ledger::<T>::get(controller).map(|ledger| ledger.active.into())
+    }
+}
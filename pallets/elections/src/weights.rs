@@ -0,0 +1,110 @@
+//! Weights for pallet_elections.
+//!
+//! The weights were estimated from the storage accesses of each call and still have to be
+//! replaced with the output of `scripts/run_benchmarks.sh`, run on the reference hardware.
+
+#![allow(unused_parens)]
+#![allow(unused_imports)]
+
+use frame_support::{
+    traits::Get,
+    weights::{constants::RocksDbWeight, Weight},
+};
+use sp_std::marker::PhantomData;
+
+/// Weight functions needed for pallet_elections.
+pub trait WeightInfo {
+    fn change_members(n: u32) -> Weight;
+    fn set_members_per_session() -> Weight;
+    fn change_reserved_members(n: u32) -> Weight;
+    fn set_election_mode() -> Weight;
+    fn set_rotation_mode() -> Weight;
+    fn set_underperformance_thresholds() -> Weight;
+    fn note_finality_participation(n: u32) -> Weight;
+}
+
+/// Weights for pallet_elections using the Substrate node and recommended hardware.
+pub struct SubstrateWeight<T>(PhantomData<T>);
+impl<T: frame_system::Config> WeightInfo for SubstrateWeight<T> {
+    // Storage: Elections ReservedMembers (r:1 w:0)
+    // Storage: Elections Members (r:0 w:1)
+    fn change_members(n: u32) -> Weight {
+        (18_000_000 as Weight)
+            .saturating_add((250_000 as Weight).saturating_mul(n as Weight))
+            .saturating_add(T::DbWeight::get().reads(1 as Weight))
+            .saturating_add(T::DbWeight::get().writes(1 as Weight))
+    }
+    // Storage: Elections MembersPerSession (r:0 w:1)
+    fn set_members_per_session() -> Weight {
+        (9_000_000 as Weight).saturating_add(T::DbWeight::get().writes(1 as Weight))
+    }
+    // Storage: Elections Members (r:1 w:0)
+    // Storage: Elections ReservedMembers (r:0 w:1)
+    fn change_reserved_members(n: u32) -> Weight {
+        (16_000_000 as Weight)
+            .saturating_add((250_000 as Weight).saturating_mul(n as Weight))
+            .saturating_add(T::DbWeight::get().reads(1 as Weight))
+            .saturating_add(T::DbWeight::get().writes(1 as Weight))
+    }
+    // Storage: Elections CurrentElectionMode (r:0 w:1)
+    fn set_election_mode() -> Weight {
+        (9_000_000 as Weight).saturating_add(T::DbWeight::get().writes(1 as Weight))
+    }
+    // Storage: Elections CurrentRotationMode (r:0 w:1)
+    fn set_rotation_mode() -> Weight {
+        (9_000_000 as Weight).saturating_add(T::DbWeight::get().writes(1 as Weight))
+    }
+    // Storage: Elections UnderperformanceThreshold (r:0 w:1)
+    // Storage: Elections UnderperformanceBenchEras (r:0 w:1)
+    fn set_underperformance_thresholds() -> Weight {
+        (10_000_000 as Weight).saturating_add(T::DbWeight::get().writes(2 as Weight))
+    }
+    // Storage: Elections PendingFinalityParticipation (r:1 w:1)
+    // Storage: Staking ErasRewardPoints (r:1 w:1)
+    // Storage: Aleph SessionAuthorities (r:1 w:0)
+    // Storage: Aleph SessionPeriodChanges (r:1 w:0)
+    // Storage: System BlockHash (r:1 w:0)
+    // Storage: Session KeyOwner (r:1 w:0)
+    fn note_finality_participation(n: u32) -> Weight {
+        (30_000_000 as Weight)
+            .saturating_add((70_000_000 as Weight).saturating_mul(n as Weight))
+            .saturating_add(T::DbWeight::get().reads(4 as Weight))
+            .saturating_add(T::DbWeight::get().reads((2 as Weight).saturating_mul(n as Weight)))
+            .saturating_add(T::DbWeight::get().writes(2 as Weight))
+    }
+}
+
+// For backwards compatibility and tests
+impl WeightInfo for () {
+    fn change_members(n: u32) -> Weight {
+        (18_000_000 as Weight)
+            .saturating_add((250_000 as Weight).saturating_mul(n as Weight))
+            .saturating_add(RocksDbWeight::get().reads(1 as Weight))
+            .saturating_add(RocksDbWeight::get().writes(1 as Weight))
+    }
+    fn set_members_per_session() -> Weight {
+        (9_000_000 as Weight).saturating_add(RocksDbWeight::get().writes(1 as Weight))
+    }
+    fn change_reserved_members(n: u32) -> Weight {
+        (16_000_000 as Weight)
+            .saturating_add((250_000 as Weight).saturating_mul(n as Weight))
+            .saturating_add(RocksDbWeight::get().reads(1 as Weight))
+            .saturating_add(RocksDbWeight::get().writes(1 as Weight))
+    }
+    fn set_election_mode() -> Weight {
+        (9_000_000 as Weight).saturating_add(RocksDbWeight::get().writes(1 as Weight))
+    }
+    fn set_rotation_mode() -> Weight {
+        (9_000_000 as Weight).saturating_add(RocksDbWeight::get().writes(1 as Weight))
+    }
+    fn set_underperformance_thresholds() -> Weight {
+        (10_000_000 as Weight).saturating_add(RocksDbWeight::get().writes(2 as Weight))
+    }
+    fn note_finality_participation(n: u32) -> Weight {
+        (30_000_000 as Weight)
+            .saturating_add((70_000_000 as Weight).saturating_mul(n as Weight))
+            .saturating_add(RocksDbWeight::get().reads(4 as Weight))
+            .saturating_add(RocksDbWeight::get().reads((2 as Weight).saturating_mul(n as Weight)))
+            .saturating_add(RocksDbWeight::get().writes(2 as Weight))
+    }
+}
@@ -1,6 +1,7 @@
 #![allow(clippy::too_many_arguments, clippy::unnecessary_mut_passed)]
 #![cfg_attr(not(feature = "std"), no_std)]
-use codec::{Decode, Encode};
+use codec::{Codec, Decode, Encode};
+use scale_info::TypeInfo;
 use sp_core::crypto::KeyTypeId;
 use sp_runtime::ConsensusEngineId;
 pub use sp_staking::SessionIndex;
@@ -48,18 +49,159 @@ pub const DEFAULT_MEMBERS_PER_SESSION: u32 = 4;
 pub const ADDRESSES_ENCODING: u32 = 42;
 pub const DEFAULT_UNIT_CREATION_DELAY: u64 = 300;
 
+pub const DEFAULT_MAX_DATA_BRANCH_LEN: u32 = 7;
+// Upper bound on the length of the proposed branch, so that proposals stay small.
+pub const MAX_MAX_DATA_BRANCH_LEN: u32 = 256;
+
 #[derive(Encode, Decode, PartialEq, Eq, sp_std::fmt::Debug)]
 pub enum ApiError {
     DecodeKey,
 }
 
+/// Version of the finality protocol, i.e. of AlephBFT, its parameters and the justification format.
+pub type Version = u32;
+
+pub const DEFAULT_FINALITY_VERSION: Version = 0;
+/// The newest finality version nodes can run, the runtime does not switch to any later one.
+pub const MAX_FINALITY_VERSION: Version = 1;
+
+/// A change of the finality version, applying from the given session on.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, TypeInfo, sp_std::fmt::Debug)]
+pub struct VersionChange {
+    pub version_incoming: Version,
+    pub session: SessionIndex,
+}
+
+/// A change of the session length, applying to the given session and all the following ones.
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, TypeInfo, sp_std::fmt::Debug)]
+pub struct SessionPeriodChange {
+    pub session: SessionIndex,
+    /// The number of the first block of `session`, so that the boundaries of the following
+    /// sessions do not depend on the earlier changes.
+    pub first_block: u32,
+    pub period: u32,
+}
+
+/// Identifies the inherent reporting the finality participation, the value of
+/// `sp_inherents::InherentIdentifier`.
+pub const FINALITY_PARTICIPATION_INHERENT_IDENTIFIER: [u8; 8] = *b"alephfpt";
+
+/// A signature that a finality authority of a session contributed to the justification of one of
+/// the blocks of the session, proving that it took part in the finalization.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, TypeInfo, sp_std::fmt::Debug)]
+pub struct ParticipationProof {
+    /// The index of the authority among the Aleph authorities of the session.
+    pub member: u32,
+    /// The number of the block whose hash was signed.
+    pub block: u32,
+    pub signature: AuthoritySignature,
+}
+
+/// Which finality authorities of the session contributed signatures to its justifications.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, TypeInfo, sp_std::fmt::Debug)]
+pub struct FinalityParticipation {
+    pub session: SessionIndex,
+    pub participants: Vec<ParticipationProof>,
+}
+
+/// The validators of an era, split into the reserved ones, which are in the committee of every
+/// session, and the rest.
+#[derive(Encode, Decode, Clone, PartialEq, Eq, TypeInfo, sp_std::fmt::Debug)]
+pub struct EraValidators<AccountId> {
+    pub reserved: Vec<AccountId>,
+    pub non_reserved: Vec<AccountId>,
+}
+
+/// Returns the number of the first block of the session. The schedule has to be nonempty, sorted by
+/// session, contain only nonzero periods and consistent first blocks. Sessions before the first
+/// change of the schedule are assumed to have its period.
+pub fn first_block_of_session(schedule: &[SessionPeriodChange], session: SessionIndex) -> u32 {
+    let change = match schedule
+        .iter()
+        .rev()
+        .find(|change| change.session <= session)
+        .or_else(|| schedule.first())
+    {
+        Some(change) => change,
+        None => return 0,
+    };
+    if session >= change.session {
+        change
+            .first_block
+            .saturating_add((session - change.session).saturating_mul(change.period))
+    } else {
+        change
+            .first_block
+            .saturating_sub((change.session - session).saturating_mul(change.period))
+    }
+}
+
+/// Returns the session the block belongs to. The schedule has the same requirements as in
+/// [`first_block_of_session`].
+pub fn session_of_block(schedule: &[SessionPeriodChange], block: u32) -> SessionIndex {
+    let change = match schedule
+        .iter()
+        .rev()
+        .find(|change| change.first_block <= block)
+        .or_else(|| schedule.first())
+    {
+        Some(change) => change,
+        None => return 0,
+    };
+    if block >= change.first_block {
+        change.session + (block - change.first_block) / change.period
+    } else {
+        let sessions_before = (change.first_block - block + change.period - 1) / change.period;
+        change.session.saturating_sub(sessions_before)
+    }
+}
+
+/// The version of `AlephSessionApi` that added the session period schedule, the authority history,
+/// the maximum branch length and the finality versions. Runtimes with older versions only provide
+/// a constant session period, so clients have to check the version before calling these methods.
+pub const SESSION_SCHEDULE_API_VERSION: u32 = 2;
+
 sp_api::decl_runtime_apis! {
+    #[api_version(2)]
     pub trait AlephSessionApi
     {
+        /// The authorities of the next session, taking an announced authority override into account.
         fn next_session_authorities() -> Result<Vec<AuthorityId>, ApiError>;
         fn authorities() -> Vec<AuthorityId>;
+        /// The authorities of a past or the current session, if they are still kept in the history.
+        /// Added in version 2.
+        fn authorities_for_session(session: SessionIndex) -> Option<Vec<AuthorityId>>;
+        /// The length of the current session. Before version 2 the length of all the sessions.
         fn session_period() -> u32;
+        /// The session length changes still relevant to the recent sessions, followed by the
+        /// scheduled ones. Starts with the initial period for session 0 until that is pruned.
+        /// Added in version 2.
+        fn session_period_schedule() -> Vec<SessionPeriodChange>;
         fn millisecs_per_block() -> u64;
+        /// Maximum number of blocks above the last finalized allowed in an AlephBFT proposal.
+        /// Added in version 2.
+        fn max_data_branch_len() -> u32;
+        /// The finality version of the current session. Added in version 2.
+        fn finality_version() -> Version;
+        /// The finality version of the given session, taking the scheduled change into account.
+        /// Added in version 2.
+        fn finality_version_for_session(session: SessionIndex) -> Version;
+    }
+
+    pub trait ElectionsApi<AccountId>
+    where
+        AccountId: Codec,
+    {
+        /// The committee of the next session, which is chosen when the current one starts.
+        fn next_session_committee() -> Vec<AccountId>;
+        /// The validators of the active era, split into the reserved ones and the rest.
+        fn era_validators() -> EraValidators<AccountId>;
+        /// How many blocks the validator has produced in the current session so far.
+        fn validator_block_count(account: AccountId) -> u32;
+        /// The reward points of the active era, including the ones the committee would get if the
+        /// current session ended now, but not the ones for finality participation still to be
+        /// reported.
+        fn era_reward_points_preview() -> Vec<(AccountId, u32)>;
     }
 }
 
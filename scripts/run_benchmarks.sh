@@ -0,0 +1,22 @@
+#!/bin/bash
+
+# Regenerates the weights of our pallets from their benchmarks. Run it on the reference hardware.
+
+set -e
+
+PALLETS=${PALLETS:-"aleph elections"}
+
+cargo build --release -p aleph-node --features runtime-benchmarks
+
+for pallet in ${PALLETS}; do
+    ./target/release/aleph-node benchmark \
+        --chain=testnet \
+        --execution=wasm \
+        --wasm-execution=compiled \
+        --pallet="pallet_${pallet}" \
+        --extrinsic='*' \
+        --steps=50 \
+        --repeat=20 \
+        --template=.maintain/pallet-weight-template.hbs \
+        --output="pallets/${pallet}/src/weights.rs"
+done